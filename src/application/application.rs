@@ -1,4 +1,5 @@
-use std::{ thread, time, ops::{ Add, Sub } };
+use std::{ thread, time };
+use std::time::{ SystemTime, UNIX_EPOCH };
 
 use glutin;
 use gl;
@@ -12,18 +13,27 @@ use world;
 use world::traits::Updatable;
 use utility::traits::Translatable;
 
+const QUICKSAVE_PATH: &str = "quicksave.dws";
+/// Frames are not rendered more often than this, vsync usually limits them further.
+const MIN_FRAME_TIME: time::Duration = time::Duration::from_millis(8);
+const MAX_SIM_STEPS_PER_FRAME: u32 = 25;
+
 pub struct Application {
     world: world::World,
+    renderer: world::WorldRenderer,
     shader_program: graphics::ShaderProgram,
     window: glutin::GlWindow,
     events_loop: glutin::EventsLoop,
     quit: bool,
-    time_passed: u32,
-    sleep_time: time::Duration
+    cursor_pos: (f64, f64),
+    sim_clock: world::SimClock,
+    screenshot_requested: bool,
+    /// Of the last frame.
+    render_stats: world::RenderStats
 }
 
 impl Application {
-    pub fn new(window_size: (f64, f64)) -> Result<Application, ApplicationError> {
+    pub fn new(window_size: (f64, f64), world_config: world::WorldConfig) -> Result<Application, ApplicationError> {
         let events_loop = glutin::EventsLoop::new();
         let window = window::init_window(window_size, &events_loop)?;
         let shader_program = graphics::ShaderProgramBuilder::new()
@@ -31,15 +41,19 @@ impl Application {
             .add_fragment_shader("resources/shader/FragmentShader.glsl")
             .finish()?;
         
-        let world = world::World::new(5, [128, 128])?;
+        let world = world::World::new(&world_config)?;
+        let renderer = world::WorldRenderer::new(&world, world::Camera::default())?;
         let app = Self {
             events_loop: events_loop,
             window: window,
             shader_program: shader_program,
             world: world,
+            renderer: renderer,
             quit: false,
-            time_passed: 0,
-            sleep_time: time::Duration::from_millis(50)
+            cursor_pos: (0., 0.),
+            sim_clock: world::SimClock::new(world::SIM_STEP, MAX_SIM_STEPS_PER_FRAME),
+            screenshot_requested: false,
+            render_stats: world::RenderStats::default()
         };
         Ok(app)
     }
@@ -48,13 +62,20 @@ impl Application {
         self.shader_program.use_program();
         let mut last_time = time::Instant::now();
         while !self.quit {
+            let frame_start = time::Instant::now();
             self.handle_events();
-            self.world.tick(self.time_passed);
+            let steps = self.sim_clock.advance(frame_start.duration_since(last_time));
+            last_time = frame_start;
+            for _ in 0..steps {
+                self.world.tick(self.sim_clock.get_step());
+            }
+            self.world.update();
+            self.renderer.update(&mut self.world, self.sim_clock.get_alpha());
             self.render()?;
-            self.time_passed = last_time.elapsed().as_secs() as u32 * 1000 + last_time.elapsed().subsec_millis();
-            last_time = time::Instant::now();
-            self.handle_sleep_time();
-            thread::sleep(self.sleep_time);
+            let frame_time = frame_start.elapsed();
+            if frame_time < MIN_FRAME_TIME {
+                thread::sleep(MIN_FRAME_TIME - frame_time);
+            }
         }
         Ok(())
     }
@@ -74,7 +95,9 @@ impl Application {
                     glutin::WindowEvent::CloseRequested => { self.quit = true; },
                     glutin::WindowEvent::Resized(logical_size) => { self.handle_resize(logical_size.into()); },
                     glutin::WindowEvent::KeyboardInput { input, .. } => { self.handle_keyboard_input(input); },
-                    glutin::WindowEvent::MouseWheel { delta, phase, .. } => { self.handle_mousewheel(delta, phase); }
+                    glutin::WindowEvent::MouseWheel { delta, phase, .. } => { self.handle_mousewheel(delta, phase); },
+                    glutin::WindowEvent::CursorMoved { position, .. } => { self.cursor_pos = position.into(); },
+                    glutin::WindowEvent::MouseInput { state, button, .. } => { self.handle_mouse_input(state, button); }
                     _ => {}
                 }
             },
@@ -97,25 +120,102 @@ impl Application {
         match (input.virtual_keycode, input.state) {
             (Some(keycode), glutin::ElementState::Pressed) => {
                 match keycode {
-                    glutin::VirtualKeyCode::A => self.world.move_camera(Vector3::new(-1., 1., 0.)),
-                    glutin::VirtualKeyCode::D => self.world.move_camera(Vector3::new(1., -1., 0.)),
-                    glutin::VirtualKeyCode::W => self.world.move_camera(Vector3::new(1., 1., 0.)),
-                    glutin::VirtualKeyCode::S => self.world.move_camera(Vector3::new(-1., -1., 0.)),
-                    glutin::VirtualKeyCode::R => self.world.move_camera(Vector3::new(0., 0., 1.)),
-                    glutin::VirtualKeyCode::F => self.world.move_camera(Vector3::new(0., 0., -1.)),
-                    glutin::VirtualKeyCode::P => self.world.toggle_camera_projection(),
+                    glutin::VirtualKeyCode::A => self.renderer.move_camera(Vector3::new(-1., 1., 0.)),
+                    glutin::VirtualKeyCode::D => self.renderer.move_camera(Vector3::new(1., -1., 0.)),
+                    glutin::VirtualKeyCode::W => self.renderer.move_camera(Vector3::new(1., 1., 0.)),
+                    glutin::VirtualKeyCode::S => self.renderer.move_camera(Vector3::new(-1., -1., 0.)),
+                    glutin::VirtualKeyCode::R => self.renderer.move_camera(Vector3::new(0., 0., 1.)),
+                    glutin::VirtualKeyCode::F => self.renderer.move_camera(Vector3::new(0., 0., -1.)),
+                    glutin::VirtualKeyCode::P => self.renderer.toggle_camera_projection(),
+                    glutin::VirtualKeyCode::Space => self.sim_clock.toggle_pause(),
+                    glutin::VirtualKeyCode::Key1 => self.sim_clock.set_speed(world::SimSpeed::Normal),
+                    glutin::VirtualKeyCode::Key2 => self.sim_clock.set_speed(world::SimSpeed::Double),
+                    glutin::VirtualKeyCode::Key3 => self.sim_clock.set_speed(world::SimSpeed::Fast),
+                    glutin::VirtualKeyCode::N => self.sim_clock.request_single_step(),
+                    glutin::VirtualKeyCode::F2 => info!("World stats:\n{}", self.world.get_stats()),
+                    glutin::VirtualKeyCode::F3 => info!("Job scheduler: {}", self.world.get_job_scheduler()),
+                    glutin::VirtualKeyCode::F4 => info!("Render stats:\n{}", self.render_stats),
+                    glutin::VirtualKeyCode::F5 => self.quicksave(),
+                    glutin::VirtualKeyCode::F9 => self.quickload(),
+                    glutin::VirtualKeyCode::F12 => self.screenshot_requested = true,
                     _ => {}
                 }
             },
             (_, _) => {}
         }
     }
+    fn quicksave(&self) {
+        let camera = world::SavedCamera::from_camera(self.renderer.get_camera());
+        match self.world.save(QUICKSAVE_PATH, &camera) {
+            Ok(_) => {},
+            Err(e) => { error!("Could not save world: {}", e); }
+        }
+    }
+
+    fn quickload(&mut self) {
+        let (world, saved_camera) = match world::World::load(QUICKSAVE_PATH) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!("Could not load world: {}", e);
+                return;
+            }
+        };
+        let mut camera = world::Camera::default();
+        saved_camera.apply_to(&mut camera);
+        match world::WorldRenderer::new(&world, camera) {
+            Ok(renderer) => {
+                self.world = world;
+                self.renderer = renderer;
+            },
+            Err(e) => { error!("Could not create renderer for loaded world: {}", e); }
+        }
+    }
+
+    fn handle_mouse_input(&mut self, state: glutin::ElementState, button: glutin::MouseButton) {
+        match (state, button) {
+            (glutin::ElementState::Pressed, glutin::MouseButton::Left) => {
+                let screen_pos = match self.get_cursor_screen_pos() {
+                    Some(screen_pos) => screen_pos,
+                    None => return
+                };
+                match self.renderer.pick(&self.world, screen_pos) {
+                    Some(pick) => {
+                        let pos = pick.get_pos();
+                        debug!("Picked field {}/{}/{}, face = {:?}", pos[0], pos[1], pos[2], pick.get_face());
+                        self.world.designate(pos[2], [[pos[0], pos[1]], [pos[0], pos[1]]], world::DesignationType::Dig);
+                    },
+                    None => { trace!("Picked nothing"); }
+                }
+            },
+            (glutin::ElementState::Pressed, glutin::MouseButton::Right) => {
+                let screen_pos = match self.get_cursor_screen_pos() {
+                    Some(screen_pos) => screen_pos,
+                    None => return
+                };
+                if let Some(pick) = self.renderer.pick(&self.world, screen_pos) {
+                    self.world.cancel_designation(pick.get_pos());
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Cursor position in normalized device coordinates.
+    fn get_cursor_screen_pos(&self) -> Option<[f32; 2]> {
+        let size = self.window.get_inner_size()?;
+        if size.width <= 0. || size.height <= 0. {
+            return None;
+        }
+        Some([(2. * self.cursor_pos.0 / size.width - 1.) as f32,
+              (1. - 2. * self.cursor_pos.1 / size.height) as f32])
+    }
+
     fn handle_mousewheel(&mut self, delta: glutin::MouseScrollDelta, phase: glutin::TouchPhase) {
         match phase {
             glutin::TouchPhase::Moved => {
                 match delta {
-                    glutin::MouseScrollDelta::LineDelta(_, dir) if dir > 0. => { self.world.get_camera_mut().zoom(0.9); },
-                    glutin::MouseScrollDelta::LineDelta(_, dir) if dir < 0. => { self.world.get_camera_mut().zoom(1.1); },
+                    glutin::MouseScrollDelta::LineDelta(_, dir) if dir > 0. => { self.renderer.get_camera_mut().zoom(0.9); },
+                    glutin::MouseScrollDelta::LineDelta(_, dir) if dir < 0. => { self.renderer.get_camera_mut().zoom(1.1); },
                     _ => { warn!("meh."); }
                 }
             },
@@ -123,26 +223,40 @@ impl Application {
         }
     }
 
-    fn handle_sleep_time(&mut self) {
-        const TARGET_FREQ: u32 = 30;
-        let diff: i32 = (self.time_passed * TARGET_FREQ) as i32 - 1000;
-        if diff.abs() as u32 > TARGET_FREQ {
-            let adj = time::Duration::from_millis(std::cmp::min(std::cmp::max(diff.abs() as u64 / 100, 1), self.sleep_time.subsec_millis() as u64));
-            match diff.signum() {
-                1 => self.sleep_time = self.sleep_time.sub(adj),
-                -1 => self.sleep_time = self.sleep_time.add(adj),
-                _ => {}
-            }
-        } 
-    }
-
     fn render(&mut self) -> Result<(), ApplicationError> {
         unsafe { gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT) }        
-        self.world.render(&self.shader_program)?;
+        self.render_stats = self.renderer.render(&self.world, &self.shader_program)?;
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            if let Err(e) = self.take_screenshot() {
+                error!("Could not take screenshot: {}", e);
+            }
+        }
         match self.window.swap_buffers() {
             Ok(_) => Ok(()),
             Err(e) => Err(ApplicationError::from(graphics::GraphicsError::from(e)))
         }
     }
-}
 
+    /// Renders the current frame again into a framebuffer of the window size and saves it as PNG.
+    fn take_screenshot(&self) -> Result<(), ApplicationError> {
+        let size: (u32, u32) = match self.window.get_inner_size() {
+            Some(logical_size) => logical_size.to_physical(self.window.get_hidpi_factor()).into(),
+            None => return Ok(())
+        };
+        if size.0 == 0 || size.1 == 0 {
+            return Ok(());
+        }
+        let framebuffer = graphics::Framebuffer::new(size)?;
+        framebuffer.bind()?;
+        unsafe { gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT) }
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let saved = match self.renderer.render(&self.world, &self.shader_program) {
+            Ok(_) => framebuffer.save_png(format!("screenshot_{}.png", timestamp)).map_err(ApplicationError::from),
+            Err(e) => Err(ApplicationError::from(e))
+        };
+        framebuffer.unbind();
+        self.handle_resize(size);
+        saved
+    }
+}
@@ -1,6 +1,8 @@
 pub mod application;
 pub mod application_error;
+pub mod offscreen;
 mod window;
 
 pub use self::application::Application;
 pub use self::application_error::ApplicationError;
+pub use self::offscreen::{ OffscreenSetup, render_offscreen };
@@ -0,0 +1,112 @@
+use std::panic;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use glutin;
+use gl;
+use glm::Vector3;
+
+use super::ApplicationError;
+use super::window;
+use graphics;
+use world;
+use world::traits::Updatable;
+use utility::Float;
+
+const GENERATION_POLL_TIME: Duration = Duration::from_millis(5);
+
+/// What to render without a window: the image size, how long to simulate before and where the camera is.
+#[derive(Clone)]
+pub struct OffscreenSetup {
+    size: (u32, u32),
+    ticks: u64,
+    perspective: bool,
+    camera_offset: Vector3<Float>
+}
+
+impl OffscreenSetup {
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            size: size,
+            ticks: 0,
+            perspective: false,
+            camera_offset: Vector3::new(0., 0., 0.)
+        }
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: (u32, u32)) {
+        self.size = size;
+    }
+
+    pub fn set_ticks(&mut self, ticks: u64) {
+        self.ticks = ticks;
+    }
+
+    pub fn set_perspective(&mut self, perspective: bool) {
+        self.perspective = perspective;
+    }
+
+    /// Moves the camera away from its default position, like the movement keys do.
+    pub fn set_camera_offset(&mut self, offset: [Float; 3]) {
+        self.camera_offset = Vector3::new(offset[0], offset[1], offset[2]);
+    }
+}
+
+impl Default for OffscreenSetup {
+    fn default() -> Self {
+        Self::new((1024, 768))
+    }
+}
+
+/// Creates the world of the config, simulates it and renders one frame into a PNG without showing a window.
+/// The same config and setup always give the same image on the same driver, which makes golden image tests possible.
+pub fn render_offscreen<P: AsRef<Path>>(world_config: &world::WorldConfig, setup: &OffscreenSetup, output_path: P) -> Result<(), ApplicationError> {
+    // winit panics instead of returning an error if there is no display to connect to
+    let events_loop = panic::catch_unwind(glutin::EventsLoop::new)
+        .map_err(|_| graphics::GraphicsError::FunctionFailure("no display for the offscreen context".to_string()))?;
+    let _context = window::init_offscreen_context(&events_loop)?;
+    let shader_program = graphics::ShaderProgramBuilder::new()
+        .add_vertex_shader("resources/shader/VertexShader.glsl")
+        .add_fragment_shader("resources/shader/FragmentShader.glsl")
+        .finish()?;
+    let framebuffer = graphics::Framebuffer::new(setup.size)?;
+
+    let mut world = world::World::new(world_config)?;
+    let mut renderer = world::WorldRenderer::new(&world, world::Camera::default())?;
+    if setup.perspective {
+        renderer.toggle_camera_projection();
+    }
+    renderer.move_camera(setup.camera_offset);
+    let (width, height) = framebuffer.get_size();
+    renderer.get_camera_mut().set_aspect_ratio(width as Float / height as Float);
+    while !world.is_generation_finished() {
+        world.update();
+        thread::sleep(GENERATION_POLL_TIME);
+    }
+    world.update();
+    for _ in 0..setup.ticks {
+        world.tick(world::SIM_STEP);
+        world.update();
+    }
+    renderer.update(&mut world, 0.);
+    while !renderer.is_meshing_finished() {
+        thread::sleep(GENERATION_POLL_TIME);
+        renderer.update(&mut world, 0.);
+    }
+
+    shader_program.use_program();
+    framebuffer.bind()?;
+    unsafe {
+        gl::ClearColor(0., 0., 0., 1.);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    }
+    let render_stats = renderer.render(&world, &shader_program)?;
+    debug!("Render stats:\n{}", render_stats);
+    framebuffer.save_png(output_path)?;
+    framebuffer.unbind();
+    Ok(())
+}
@@ -12,14 +12,33 @@ pub fn init_window(window_size: (f64, f64), events_loop: &glutin::EventsLoop) ->
         .with_vsync(true)
         .with_depth_buffer(8);
     let window = glutin::GlWindow::new(window_builder, context_builder, &events_loop)?;
-    info!("Loading opengl functions");
-    gl::load_with(|s| window.context().get_proc_address(s) as *const _);
-    graphics::check_opengl_error("gl::load_with")?;
-
     unsafe {
         window.make_current()?;
     }
+    init_opengl(&window)?;
     window.show();
+    Ok(window)
+}
+
+/// Creates a context without a visible window, for rendering into framebuffers only.
+/// It still needs a display connection, which can be a virtual one like Xvfb with Mesa llvmpipe.
+pub fn init_offscreen_context(events_loop: &glutin::EventsLoop) -> Result<glutin::Context, graphics::GraphicsError> {
+    info!("Creating offscreen context");
+    let context_builder = glutin::ContextBuilder::new()
+        .with_depth_buffer(8);
+    let context = glutin::Context::new(&events_loop, context_builder, true)?;
+    unsafe {
+        context.make_current()?;
+    }
+    init_opengl(&context)?;
+    Ok(context)
+}
+
+fn init_opengl<C: GlContext>(context: &C) -> Result<(), graphics::GraphicsError> {
+    info!("Loading opengl functions");
+    gl::load_with(|s| context.get_proc_address(s) as *const _);
+    graphics::check_opengl_error("gl::load_with")?;
+
     unsafe {
         gl::Enable(gl::DEPTH_TEST);
         gl::DepthFunc(gl::LESS);
@@ -33,5 +52,5 @@ pub fn init_window(window_size: (f64, f64), events_loop: &glutin::EventsLoop) ->
         Ok(version) => { info!("opengl version: {}", version) },
         Err(e) => { warn!("Could not convert opengl version string: {}", e); }
     }
-    Ok(window)
+    Ok(())
 }
@@ -5,6 +5,7 @@ extern crate chrono;
 
 extern crate dwarfs;
 
+use std::env;
 use std::io::Write;
 use log::Record;
 use env_logger::{ Builder, fmt::Formatter };
@@ -14,7 +15,15 @@ fn main() {
 
     init_custom_logger();
 
-    let app = match dwarfs::Application::new(WINDOW_SIZE) {
+    let world_config = match dwarfs::WorldConfig::from_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    let app = match dwarfs::Application::new(WINDOW_SIZE, world_config) {
         Ok(app) => app,
         Err(e) => {
             error!("{}", e);
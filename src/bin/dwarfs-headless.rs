@@ -0,0 +1,104 @@
+#[macro_use]
+extern crate log;
+extern crate env_logger;
+
+extern crate dwarfs;
+
+use std::env;
+use std::thread;
+use std::time::{ Duration, Instant };
+
+use dwarfs::{ World, WorldConfig, DesignationType, Updatable, SIM_STEP };
+
+const DEFAULT_TICKS: u64 = 1000;
+const GENERATION_POLL_TIME: Duration = Duration::from_millis(5);
+
+/// Runs the simulation without a window, for batch tests and benchmarks.
+/// Besides the world arguments, `--ticks <count>` sets the steps to simulate and
+/// `--dig <size>` designates a square of that size at the center of the map for digging.
+fn main() {
+    env_logger::init();
+
+    let (ticks, dig_size, world_args) = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let world_config = match WorldConfig::from_args(world_args.into_iter()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let mut world = match World::new(&world_config) {
+        Ok(world) => world,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    let generation_start = Instant::now();
+    while !world.is_generation_finished() {
+        world.update();
+        thread::sleep(GENERATION_POLL_TIME);
+    }
+    world.update();
+    let generation_time = generation_start.elapsed();
+    println!("seed = '{}', generated {} layers in {:.2}s", world_config.get_seed(), world.get_layers().len(), as_secs(generation_time));
+
+    if dig_size > 0 {
+        designate_center(&mut world, dig_size);
+    }
+
+    let sim_start = Instant::now();
+    for _ in 0..ticks {
+        world.tick(SIM_STEP);
+        world.update();
+    }
+    let sim_time = as_secs(sim_start.elapsed());
+    println!("{}", world.get_stats());
+    println!("simulated {} ticks in {:.2}s, {:.0} ticks/s", ticks, sim_time, ticks as f64 / sim_time.max(1e-9));
+}
+
+/// Splits off the arguments of the headless run, the remaining ones configure the world.
+fn parse_args(args: Vec<String>) -> Result<(u64, i32, Vec<String>), String> {
+    let mut ticks = DEFAULT_TICKS;
+    let mut dig_size = 0;
+    let mut world_args = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" | "--dig" => {
+                let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+                let invalid = |_| format!("Invalid argument: {} {}", arg, value);
+                match arg.as_str() {
+                    "--ticks" => ticks = value.parse().map_err(invalid)?,
+                    _ => dig_size = value.parse().map_err(invalid)?
+                }
+            },
+            _ => world_args.push(arg)
+        }
+    }
+    Ok((ticks, dig_size, world_args))
+}
+
+/// Designates the topmost cube of every position in the square.
+fn designate_center(world: &mut World, size: i32) {
+    let layer_size = world.get_config().get_layer_size();
+    let min = [(layer_size[0] - size) / 2, (layer_size[1] - size) / 2];
+    for x in min[0]..min[0] + size {
+        for y in min[1]..min[1] + size {
+            if let Some(level) = world.get_surface_level([x, y]) {
+                world.designate(level, [[x, y], [x, y]], DesignationType::Dig);
+            }
+        }
+    }
+}
+
+fn as_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
+}
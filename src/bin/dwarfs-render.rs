@@ -0,0 +1,81 @@
+#[macro_use]
+extern crate log;
+extern crate env_logger;
+
+extern crate dwarfs;
+
+use std::env;
+
+use dwarfs::{ WorldConfig, OffscreenSetup, render_offscreen };
+
+const DEFAULT_OUTPUT_PATH: &str = "render.png";
+
+/// Renders a world into a PNG without showing a window. Besides the world arguments, supported are
+/// `--output <path>`, `--size <width>x<height>`, `--ticks <count>`, `--camera <x>,<y>,<z>` and `--perspective`.
+fn main() {
+    env_logger::init();
+
+    let (output_path, setup, world_args) = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let world_config = match WorldConfig::from_args(world_args.into_iter()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    match render_offscreen(&world_config, &setup, &output_path) {
+        Ok(_) => println!("seed = '{}', rendered to '{}'", world_config.get_seed(), output_path),
+        Err(e) => error!("{}", e)
+    }
+}
+
+/// Splits off the arguments of the render setup, the remaining ones configure the world.
+fn parse_args(args: Vec<String>) -> Result<(String, OffscreenSetup, Vec<String>), String> {
+    let mut output_path = DEFAULT_OUTPUT_PATH.to_string();
+    let mut setup = OffscreenSetup::default();
+    let mut world_args = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--perspective" {
+            setup.set_perspective(true);
+            continue;
+        }
+        if !["--output", "--size", "--ticks", "--camera"].contains(&arg.as_str()) {
+            world_args.push(arg);
+            continue;
+        }
+        let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+        let invalid = || format!("Invalid argument: {} {}", arg, value);
+        match arg.as_str() {
+            "--output" => output_path = value.clone(),
+            "--size" => {
+                let size = parse_numbers::<u32>(&value, 'x').ok_or_else(invalid)?;
+                match size.as_slice() {
+                    [width, height] if *width > 0 && *height > 0 => setup.set_size((*width, *height)),
+                    _ => return Err(invalid())
+                }
+            },
+            "--ticks" => setup.set_ticks(value.parse().map_err(|_| invalid())?),
+            _ => {
+                let offset = parse_numbers::<f32>(&value, ',').ok_or_else(invalid)?;
+                match offset.as_slice() {
+                    [x, y, z] => setup.set_camera_offset([*x, *y, *z]),
+                    _ => return Err(invalid())
+                }
+            }
+        }
+    }
+    Ok((output_path, setup, world_args))
+}
+
+fn parse_numbers<T: std::str::FromStr>(value: &str, separator: char) -> Option<Vec<T>> {
+    value.split(separator)
+        .map(|part| part.trim().parse().ok())
+        .collect()
+}
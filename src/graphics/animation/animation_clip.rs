@@ -0,0 +1,127 @@
+use glm::{ Vector3, Vector4 };
+
+use utility::Float;
+use super::{ NodeTransform, blend_quaternions };
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChannelProperty {
+    Translation,
+    Rotation,
+    Scale
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Linear,
+    Step
+}
+
+/// Keyframes of one property of one node. Translations and scales use the first three components of the values,
+/// rotations are unit quaternions.
+#[derive(Clone, Debug)]
+pub struct Channel {
+    node: String,
+    /// Index of the node in the mesh, set by binding the clip.
+    part: Option<usize>,
+    property: ChannelProperty,
+    interpolation: Interpolation,
+    times: Vec<Float>,
+    values: Vec<Vector4<Float>>
+}
+
+/// A looping animation, sampled into the pose of a mesh.
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    name: String,
+    duration: Float,
+    channels: Vec<Channel>
+}
+
+impl Channel {
+    /// Expects as many values as times, with the times ascending.
+    pub fn new(node: &str, property: ChannelProperty, interpolation: Interpolation, times: Vec<Float>, values: Vec<Vector4<Float>>) -> Self {
+        debug_assert!(times.len() == values.len() && !times.is_empty());
+        debug_assert!(times.windows(2).all(|w| w[0] <= w[1]));
+        Self {
+            node: node.to_string(),
+            part: None,
+            property: property,
+            interpolation: interpolation,
+            times: times,
+            values: values
+        }
+    }
+
+    pub fn get_end_time(&self) -> Float {
+        *self.times.last().unwrap_or(&0.)
+    }
+
+    pub fn sample(&self, time: Float) -> Vector4<Float> {
+        let next = self.times.iter().position(|t| *t > time).unwrap_or(self.times.len());
+        if next == 0 {
+            return self.values[0];
+        }
+        if next == self.times.len() || self.interpolation == Interpolation::Step {
+            return self.values[next - 1];
+        }
+        let (start, end) = (self.times[next - 1], self.times[next]);
+        let weight = (time - start) / (end - start);
+        let (a, b) = (self.values[next - 1], self.values[next]);
+        match self.property {
+            ChannelProperty::Rotation => blend_quaternions(a, b, weight),
+            _ => a + (b - a) * weight
+        }
+    }
+
+    fn apply(&self, time: Float, pose: &mut [NodeTransform]) {
+        let transform = match self.part.and_then(|part| pose.get_mut(part)) {
+            Some(transform) => transform,
+            None => return
+        };
+        let value = self.sample(time);
+        match self.property {
+            ChannelProperty::Translation => transform.set_translation(Vector3::new(value.x, value.y, value.z)),
+            ChannelProperty::Rotation => transform.set_rotation(value),
+            ChannelProperty::Scale => transform.set_scale(Vector3::new(value.x, value.y, value.z))
+        }
+    }
+}
+
+impl AnimationClip {
+    pub fn new(name: &str, channels: Vec<Channel>) -> Self {
+        let duration = channels.iter().map(|c| c.get_end_time()).fold(0., Float::max);
+        Self {
+            name: name.to_string(),
+            duration: duration,
+            channels: channels
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Resolves the node names of the channels to the indices of the mesh parts, channels of other nodes are dropped.
+    pub fn bind(&mut self, part_names: &[&str]) {
+        for channel in self.channels.iter_mut() {
+            channel.part = part_names.iter().position(|name| *name == channel.node);
+        }
+        let unbound = self.channels.iter().filter(|c| c.part.is_none()).count();
+        if unbound > 0 {
+            trace!("Clip '{}': dropped {} channels of nodes not in the mesh", self.name, unbound);
+        }
+        self.channels.retain(|c| c.part.is_some());
+    }
+
+    /// Overrides the animated properties of the pose, the time wraps around at the end of the clip.
+    pub fn sample(&self, time: Float, pose: &mut [NodeTransform]) {
+        let time = if self.duration > 0. { time % self.duration } else { 0. };
+        for channel in self.channels.iter() {
+            channel.apply(time, pose);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use graphics::Mesh;
+use utility::Float;
+use super::NodeTransform;
+
+/// A clip being played, times are the ones of the last two simulation steps.
+#[derive(Clone)]
+struct Playback {
+    clip: String,
+    time: Float,
+    previous_time: Float
+}
+
+/// Plays the clips of a mesh for one object, switching clips blends from the old to the new one.
+/// Time advances with the simulation steps, the pose is interpolated between the last two of them.
+pub struct Animator {
+    current: Option<Playback>,
+    /// The clip blended out.
+    fading: Option<Playback>,
+    blend_duration: Float,
+    blend_time: Float,
+    previous_blend_time: Float,
+    pose: Vec<NodeTransform>
+}
+
+impl Animator {
+    pub fn get_clip_name(&self) -> Option<&str> {
+        self.current.as_ref().map(|p| p.clip.as_str())
+    }
+
+    /// Does nothing if the clip is already playing, clips the mesh lacks leave it in its rest pose.
+    pub fn play(&mut self, mesh: &Mesh, clip: &str, blend_duration: Float) {
+        if self.get_clip_name() == Some(clip) {
+            return;
+        }
+        if mesh.get_clip(clip).is_none() {
+            warn!("Mesh has no animation clip '{}'", clip);
+        }
+        self.fading = self.current.take();
+        self.current = Some(Playback {
+            clip: clip.to_string(),
+            time: 0.,
+            previous_time: 0.
+        });
+        self.blend_duration = if self.fading.is_some() { blend_duration } else { 0. };
+        self.blend_time = 0.;
+        self.previous_blend_time = 0.;
+    }
+
+    /// Advances the clips by one simulation step of the given seconds.
+    pub fn tick(&mut self, step: Float) {
+        for playback in self.current.iter_mut().chain(self.fading.iter_mut()) {
+            playback.previous_time = playback.time;
+            playback.time += step;
+        }
+        self.previous_blend_time = self.blend_time;
+        self.blend_time = (self.blend_time + step).min(self.blend_duration);
+        if self.previous_blend_time >= self.blend_duration {
+            self.fading = None;
+        }
+    }
+
+    /// Samples the clips at alpha between the last two simulation steps.
+    pub fn update_pose(&mut self, mesh: &Mesh, alpha: Float) {
+        self.pose = mesh.create_rest_pose();
+        if let Some(ref current) = self.current {
+            sample(mesh, current, alpha, &mut self.pose);
+        }
+        if let Some(ref fading) = self.fading {
+            let blend_time = self.previous_blend_time + (self.blend_time - self.previous_blend_time) * alpha;
+            let weight = if self.blend_duration > 0. { blend_time / self.blend_duration } else { 1. };
+            let mut fading_pose = mesh.create_rest_pose();
+            sample(mesh, fading, alpha, &mut fading_pose);
+            for (transform, fading_transform) in self.pose.iter_mut().zip(fading_pose.iter()) {
+                *transform = fading_transform.blend(transform, weight);
+            }
+        }
+    }
+
+    /// None until a pose was created.
+    pub fn get_pose(&self) -> Option<&[NodeTransform]> {
+        if self.pose.is_empty() {
+            return None;
+        }
+        Some(&self.pose)
+    }
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self {
+            current: None,
+            fading: None,
+            blend_duration: 0.,
+            blend_time: 0.,
+            previous_blend_time: 0.,
+            pose: Vec::new()
+        }
+    }
+}
+
+fn sample(mesh: &Mesh, playback: &Playback, alpha: Float, pose: &mut [NodeTransform]) {
+    if let Some(clip) = mesh.get_clip(&playback.clip) {
+        let time = playback.previous_time + (playback.time - playback.previous_time) * alpha;
+        clip.sample(time, pose);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod node_transform;
+pub mod animation_clip;
+pub mod animator;
+
+pub use self::node_transform::{ NodeTransform, blend_quaternions };
+pub use self::animation_clip::{ AnimationClip, Channel, ChannelProperty, Interpolation };
+pub use self::animator::Animator;
\ No newline at end of file
@@ -0,0 +1,65 @@
+use glm::{ Vector3, Vector4, Matrix4, dot, normalize };
+
+use graphics::transformation::{ create_translation_matrix, create_scale_matrix, create_rotation_matrix_from_quaternion, create_quaternion_from_euler };
+use graphics::mesh::Node;
+use utility::traits::{ Translatable, Rotatable, Scalable };
+use utility::Float;
+
+/// The transformation of one node in a pose, rotations are unit quaternions so they can be interpolated.
+#[derive(Copy, Clone, Debug)]
+pub struct NodeTransform {
+    translation: Vector3<Float>,
+    rotation: Vector4<Float>,
+    scale: Vector3<Float>
+}
+
+impl NodeTransform {
+    pub fn from_node(node: &Node) -> Self {
+        Self {
+            translation: node.get_translation(),
+            rotation: create_quaternion_from_euler(node.get_rotation()),
+            scale: node.get_scale()
+        }
+    }
+
+    pub fn set_translation(&mut self, translation: Vector3<Float>) {
+        self.translation = translation;
+    }
+
+    pub fn set_rotation(&mut self, rotation: Vector4<Float>) {
+        self.rotation = rotation;
+    }
+
+    pub fn set_scale(&mut self, scale: Vector3<Float>) {
+        self.scale = scale;
+    }
+
+    pub fn create_matrix(&self) -> Matrix4<Float> {
+        create_translation_matrix(self.translation) * create_rotation_matrix_from_quaternion(self.rotation) * create_scale_matrix(self.scale)
+    }
+
+    /// Weight 0 gives self, weight 1 gives other.
+    pub fn blend(&self, other: &NodeTransform, weight: Float) -> NodeTransform {
+        NodeTransform {
+            translation: self.translation + (other.translation - self.translation) * weight,
+            rotation: blend_quaternions(self.rotation, other.rotation, weight),
+            scale: self.scale + (other.scale - self.scale) * weight
+        }
+    }
+}
+
+impl Default for NodeTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::new(0., 0., 0.),
+            rotation: Vector4::new(0., 0., 0., 1.),
+            scale: Vector3::new(1., 1., 1.)
+        }
+    }
+}
+
+/// Normalized linear interpolation along the shorter way, close enough to slerp for keyframes a few degrees apart.
+pub fn blend_quaternions(a: Vector4<Float>, b: Vector4<Float>, weight: Float) -> Vector4<Float> {
+    let b = if dot(a, b) < 0. { -b } else { b };
+    normalize(a + (b - a) * weight)
+}
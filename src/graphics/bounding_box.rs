@@ -0,0 +1,78 @@
+use glm::{ Vector3, Vector4, Matrix4 };
+
+use utility::Float;
+
+/// An axis aligned box, min is never greater than max on any axis.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BoundingBox {
+    min: Vector3<Float>,
+    max: Vector3<Float>
+}
+
+impl BoundingBox {
+    pub fn new(min: Vector3<Float>, max: Vector3<Float>) -> Self {
+        debug_assert!(min.x <= max.x && min.y <= max.y && min.z <= max.z);
+        Self {
+            min: min,
+            max: max
+        }
+    }
+
+    /// The smallest box around the points, None without any.
+    pub fn from_points<I: IntoIterator<Item = Vector3<Float>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut bounding_box = Self::new(first, first);
+        for point in points {
+            bounding_box.add_point(point);
+        }
+        Some(bounding_box)
+    }
+
+    pub fn get_min(&self) -> Vector3<Float> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Vector3<Float> {
+        self.max
+    }
+
+    pub fn add_point(&mut self, point: Vector3<Float>) {
+        for axis in 0..3 {
+            self.min[axis] = self.min[axis].min(point[axis]);
+            self.max[axis] = self.max[axis].max(point[axis]);
+        }
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        let mut bounding_box = *self;
+        bounding_box.add_point(other.min);
+        bounding_box.add_point(other.max);
+        bounding_box
+    }
+
+    /// Grows the box by the margin on every side.
+    pub fn expand(&self, margin: Float) -> BoundingBox {
+        let offset = Vector3::new(margin, margin, margin);
+        BoundingBox::new(self.min - offset, self.max + offset)
+    }
+
+    pub fn get_corners(&self) -> [Vector3<Float>; 8] {
+        let mut corners = [self.min; 8];
+        for (index, corner) in corners.iter_mut().enumerate() {
+            for axis in 0..3 {
+                if index & (1 << axis) != 0 {
+                    corner[axis] = self.max[axis];
+                }
+            }
+        }
+        corners
+    }
+
+    /// The box around the transformed corners, which may be larger than the transformed box itself.
+    pub fn transform(&self, matrix: &Matrix4<Float>) -> BoundingBox {
+        let corners = self.get_corners();
+        let transformed = corners.iter().map(|c| (*matrix * Vector4::new(c.x, c.y, c.z, 1.)).truncate(3));
+        BoundingBox::from_points(transformed).unwrap_or(*self)
+    }
+}
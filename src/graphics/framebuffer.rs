@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use gl;
+use gl::types::{ GLuint, GLsizei };
+use image;
+
+use graphics::{ GraphicsError, check_opengl_error };
+
+/// An offscreen render target with a color and a depth renderbuffer.
+pub struct Framebuffer {
+    fbo: GLuint,
+    color_rbo: GLuint,
+    depth_rbo: GLuint,
+    size: (u32, u32)
+}
+
+impl Framebuffer {
+    pub fn new(size: (u32, u32)) -> Result<Framebuffer, GraphicsError> {
+        debug_assert!(size.0 > 0 && size.1 > 0);
+        let mut framebuffer = Framebuffer {
+            fbo: 0,
+            color_rbo: 0,
+            depth_rbo: 0,
+            size: size
+        };
+        unsafe {
+            gl::GenFramebuffers(1, &mut framebuffer.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.fbo);
+            gl::GenRenderbuffers(1, &mut framebuffer.color_rbo);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, framebuffer.color_rbo);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, size.0 as GLsizei, size.1 as GLsizei);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, framebuffer.color_rbo);
+            gl::GenRenderbuffers(1, &mut framebuffer.depth_rbo);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, framebuffer.depth_rbo);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, size.0 as GLsizei, size.1 as GLsizei);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, framebuffer.depth_rbo);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+        check_opengl_error("framebuffer creation")?;
+        let status = unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0); }
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(GraphicsError::FunctionFailure(format!("framebuffer incomplete, status = {:#x}", status)));
+        }
+        debug!("Created framebuffer, id = {}, size = {}x{}", framebuffer.fbo, size.0, size.1);
+        Ok(framebuffer)
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Redirects rendering into the framebuffer and sets the viewport to its size.
+    pub fn bind(&self) -> Result<(), GraphicsError> {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.size.0 as GLsizei, self.size.1 as GLsizei);
+        }
+        check_opengl_error("gl::BindFramebuffer")?;
+        Ok(())
+    }
+
+    /// Renders into the window again, the viewport has to be restored by the caller.
+    pub fn unbind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0); }
+    }
+
+    /// Reads back the color buffer, the first row of the image is the top of the rendered frame.
+    pub fn read_image(&self) -> Result<image::RgbaImage, GraphicsError> {
+        let (width, height) = self.size;
+        let row_len = width as usize * 4;
+        let mut pixels = vec![0u8; row_len * height as usize];
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(0, 0, width as GLsizei, height as GLsizei, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        check_opengl_error("gl::ReadPixels")?;
+        let flipped: Vec<u8> = pixels.chunks(row_len)
+            .rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect();
+        match image::RgbaImage::from_raw(width, height, flipped) {
+            Some(image) => Ok(image),
+            None => Err(GraphicsError::FunctionFailure("framebuffer image creation".to_string()))
+        }
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), GraphicsError> {
+        let image = self.read_image()?;
+        image.save(path.as_ref()).map_err(image::ImageError::IoError)?;
+        info!("Saved framebuffer to '{}'", path.as_ref().display());
+        Ok(())
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        debug!("Deleting framebuffer id = {}", self.fbo);
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.depth_rbo);
+            gl::DeleteRenderbuffers(1, &self.color_rbo);
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+        match check_opengl_error("gl::DeleteFramebuffers") {
+            Ok(_) => {},
+            Err(e) => error!("{}", e)
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use glm::{ Vector4, Matrix4, dot };
+
+use utility::Float;
+use super::BoundingBox;
+
+/// The six planes bounding the visible volume of a projection view matrix, normals pointing inwards.
+/// Works for orthographic and perspective projections alike.
+pub struct Frustum {
+    planes: [Vector4<Float>; 6]
+}
+
+impl Frustum {
+    /// Extracts the planes from the rows of the matrix, for clip space depth from -w to w.
+    pub fn from_matrix(matrix: &Matrix4<Float>) -> Self {
+        let row = |r: usize| Vector4::new(matrix[0][r], matrix[1][r], matrix[2][r], matrix[3][r]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let mut planes = [w + x, w - x, w + y, w - y, w + z, w - z];
+        for plane in planes.iter_mut() {
+            let length = (plane.x * plane.x + plane.y * plane.y + plane.z * plane.z).sqrt();
+            if length > 0. {
+                *plane = *plane / length;
+            }
+        }
+        Self {
+            planes: planes
+        }
+    }
+
+    /// Conservative, boxes near the edges of the frustum may pass without being visible.
+    pub fn intersects(&self, bounding_box: &BoundingBox) -> bool {
+        let (min, max) = (bounding_box.get_min(), bounding_box.get_max());
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let corner = Vector4::new(
+                if plane.x >= 0. { max.x } else { min.x },
+                if plane.y >= 0. { max.y } else { min.y },
+                if plane.z >= 0. { max.z } else { min.z },
+                1.);
+            dot(*plane, corner) >= 0.
+        })
+    }
+}
@@ -6,6 +6,7 @@ use image;
 
 use super::shader::{ ShaderError, ShaderProgramError };
 use super::mesh::MeshError;
+use super::texture::AtlasError;
 use super::OpenglError;
 
 #[derive(Debug)]
@@ -17,8 +18,8 @@ pub enum GraphicsError {
     Mesh(MeshError),
     ShaderProgram(ShaderProgramError),
     Opengl(OpenglError),
-    FunctionFailure(String),
-    InvalidImageFormat(String)
+    Atlas(AtlasError),
+    FunctionFailure(String)
 }
 
 impl From<glutin::CreationError> for GraphicsError {
@@ -57,6 +58,12 @@ impl From<ShaderProgramError> for GraphicsError {
     }
 }
 
+impl From<AtlasError> for GraphicsError {
+    fn from(err: AtlasError) -> GraphicsError {
+        GraphicsError::Atlas(err)
+    }
+}
+
 impl From<OpenglError> for GraphicsError {
     fn from(err: OpenglError) -> GraphicsError {
         GraphicsError::Opengl(err)
@@ -73,8 +80,8 @@ impl Error for GraphicsError {
             GraphicsError::ShaderProgram(_) => "shader program",
             GraphicsError::Mesh(_) => "mesh",
             GraphicsError::Opengl(_) => "opengl",
-            GraphicsError::FunctionFailure(_) => "function failure",
-            GraphicsError::InvalidImageFormat(_) => "invalid image format"        
+            GraphicsError::Atlas(_) => "atlas",
+            GraphicsError::FunctionFailure(_) => "function failure"
         }
     }
 
@@ -87,8 +94,8 @@ impl Error for GraphicsError {
             GraphicsError::ShaderProgram(ref err) => Some(err),
             GraphicsError::Mesh(ref err) => Some(err),
             GraphicsError::Opengl(ref err) => Some(err),
-            GraphicsError::FunctionFailure(_) => None,
-            GraphicsError::InvalidImageFormat(_) => None
+            GraphicsError::Atlas(ref err) => Some(err),
+            GraphicsError::FunctionFailure(_) => None
         }
     }
 }
@@ -103,8 +110,8 @@ impl fmt::Display for GraphicsError {
             GraphicsError::ShaderProgram(ref err) => write!(f, "{}/{}", self.description(), err),
             GraphicsError::Mesh(ref err) => write!(f, "{}/{}", self.description(), err),
             GraphicsError::Opengl(ref err) => write!(f, "{}/{}", self.description(), err),
-            GraphicsError::FunctionFailure(ref func_name) => write!(f, "{} @ {}", self.description(), func_name),
-            GraphicsError::InvalidImageFormat(ref img_name) => write!(f, "{}: Image not of format rgba8: '{}'", self.description(), img_name)
+            GraphicsError::Atlas(ref err) => write!(f, "{}/{}", self.description(), err),
+            GraphicsError::FunctionFailure(ref func_name) => write!(f, "{} @ {}", self.description(), func_name)
         }
     }
 }
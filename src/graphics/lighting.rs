@@ -0,0 +1,55 @@
+use glm::{ Vector3, normalize };
+
+use graphics::shader::{ ShaderProgram, ShaderProgramError };
+use utility::Float;
+
+/// A directional sun and an ambient term, colors are given as linear rgb intensities.
+/// Both are dimmed by the occlusion of the vertices, there are no shadows.
+#[derive(Deserialize, Copy, Clone)]
+#[serde(default)]
+pub struct Lighting {
+    sun_direction: [Float; 3],
+    sun_color: [Float; 3],
+    ambient_color: [Float; 3],
+    /// How much of the light fully occluded vertices lose.
+    occlusion_strength: Float
+}
+
+impl Lighting {
+    /// The direction the sun light travels in, normalized.
+    pub fn get_sun_direction(&self) -> Vector3<Float> {
+        normalize(Vector3::new(self.sun_direction[0], self.sun_direction[1], self.sun_direction[2]))
+    }
+
+    pub fn get_sun_color(&self) -> Vector3<Float> {
+        Vector3::new(self.sun_color[0], self.sun_color[1], self.sun_color[2])
+    }
+
+    pub fn get_ambient_color(&self) -> Vector3<Float> {
+        Vector3::new(self.ambient_color[0], self.ambient_color[1], self.ambient_color[2])
+    }
+
+    pub fn get_occlusion_strength(&self) -> Float {
+        self.occlusion_strength
+    }
+
+    /// Expects the program to be in use.
+    pub fn set_uniforms(&self, shader: &ShaderProgram) -> Result<(), ShaderProgramError> {
+        shader.set_uniform("sunDirection", &self.get_sun_direction())?;
+        shader.set_uniform("sunColor", &self.get_sun_color())?;
+        shader.set_uniform("ambientColor", &self.get_ambient_color())?;
+        shader.set_uniform("occlusionStrength", &self.get_occlusion_strength())?;
+        Ok(())
+    }
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            sun_direction: [0.4, 0.6, -1.],
+            sun_color: [0.7, 0.7, 0.65],
+            ambient_color: [0.4, 0.4, 0.45],
+            occlusion_strength: 0.75
+        }
+    }
+}
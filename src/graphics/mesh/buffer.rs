@@ -1,14 +1,16 @@
 use std::{ ptr, io, ffi::c_void, mem::size_of };
 use std::collections::btree_map::{ BTreeMap, Entry };
 use gl::types::{ GLuint, GLsizeiptr, GLenum };
+use glm::Vector3;
 
-use graphics::{ check_opengl_error, OpenglError, mesh::{ Vertex, Triangle } };
+use graphics::{ check_opengl_error, OpenglError, BoundingBox, mesh::{ Vertex, Triangle } };
 use utility::Float;
 
 pub struct Buffer {
     position: Vec<Float>,
     uv: Vec<Float>,
     normal: Vec<Float>,
+    occlusion: Vec<Float>,
     index: Vec<GLuint>
 }
 
@@ -22,10 +24,19 @@ impl Buffer {
         self.index.len()
     }
 
-    pub fn create_vbos(&self) -> Result<[GLuint; 4], OpenglError> {
-        let mut vbos: [GLuint; 4] = [0; 4];
+    pub fn get_vertex_count(&self) -> usize {
+        self.position.len() / 3
+    }
+
+    /// None for empty buffers.
+    pub fn get_bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.position.chunks(3).map(|p| Vector3::new(p[0], p[1], p[2])))
+    }
+
+    pub fn create_vbos(&self) -> Result<[GLuint; 5], OpenglError> {
+        let mut vbos: [GLuint; 5] = [0; 5];
         
-        unsafe { gl::GenBuffers(4, &mut vbos[0] as * mut GLuint) };
+        unsafe { gl::GenBuffers(5, &mut vbos[0] as * mut GLuint) };
         check_opengl_error("gl::GenBuffers")?;
 
         match fill_buffer(vbos[0], gl::ARRAY_BUFFER, (self.position.len() * size_of::<Float>()) as GLsizeiptr, self.position.as_ptr() as * const _) {
@@ -52,7 +63,15 @@ impl Buffer {
             }
         }
 
-        match fill_buffer(vbos[3], gl::ELEMENT_ARRAY_BUFFER, (self.index.len() * size_of::<GLuint>()) as GLsizeiptr, self.index.as_ptr() as * const _) {
+        match fill_buffer(vbos[3], gl::ARRAY_BUFFER, (self.occlusion.len() * size_of::<Float>()) as GLsizeiptr, self.occlusion.as_ptr() as * const _) {
+            Ok(_) => {},
+            Err(e) => {
+                delete_vbos(vbos);
+                return Err(e);
+            }
+        }
+
+        match fill_buffer(vbos[4], gl::ELEMENT_ARRAY_BUFFER, (self.index.len() * size_of::<GLuint>()) as GLsizeiptr, self.index.as_ptr() as * const _) {
             Ok(_) => {},
             Err(e) => {
                 delete_vbos(vbos);
@@ -69,6 +88,7 @@ impl From<&[Triangle]> for Buffer {
         let mut position_buffer: Vec<Float> = Vec::new();
         let mut uv_buffer: Vec<Float> = Vec::new();
         let mut normal_buffer: Vec<Float> = Vec::new();
+        let mut occlusion_buffer: Vec<Float> = Vec::new();
         let mut index_buffer: Vec<GLuint> = Vec::new();
         for triangle in triangles.iter() {
             for vertex in triangle.as_vertices() {
@@ -84,6 +104,7 @@ impl From<&[Triangle]> for Buffer {
                         position_buffer.extend(vertex.get_pos().as_array());
                         uv_buffer.extend(vertex.get_uv().as_array());
                         normal_buffer.extend(vertex.get_normal().as_array());
+                        occlusion_buffer.push(vertex.get_occlusion());
                         index_buffer.push(new_index);
                         v.insert(new_index);
                     }
@@ -94,6 +115,7 @@ impl From<&[Triangle]> for Buffer {
             position: position_buffer,
             uv: uv_buffer,
             normal: normal_buffer,
+            occlusion: occlusion_buffer,
             index: index_buffer
         }
     }
@@ -115,6 +137,6 @@ fn fill_buffer(buffer_id: GLuint, buffer_type: GLenum, buffer_size: GLsizeiptr,
     Ok(()) 
 }
 
-fn delete_vbos(buffers: [GLuint; 4]) {
-    unsafe { gl::DeleteBuffers(4, &buffers[0] as * const GLuint); }
+fn delete_vbos(buffers: [GLuint; 5]) {
+    unsafe { gl::DeleteBuffers(5, &buffers[0] as * const GLuint); }
 }
\ No newline at end of file
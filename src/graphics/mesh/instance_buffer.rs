@@ -0,0 +1,97 @@
+use std::cell::Cell;
+use std::{ ffi::c_void, mem::size_of };
+use gl;
+use gl::types::{ GLuint, GLsizei, GLsizeiptr };
+use glm::Matrix4;
+
+use utility::Float;
+use graphics::{ check_opengl_error, OpenglError };
+
+/// Attribute location of the first column of the per instance model matrix, the other columns follow.
+pub const INSTANCE_MATRIX_LOCATION: GLuint = 4;
+
+/// Model matrices streamed to the GPU once per draw, one for every instance drawn.
+/// The buffer is refilled for each draw, so one of them serves all meshes of a frame.
+pub struct InstanceBuffer {
+    vbo: GLuint,
+    instance_count: Cell<usize>
+}
+
+impl InstanceBuffer {
+    pub fn new() -> Result<Self, OpenglError> {
+        let mut vbo: GLuint = 0;
+        unsafe { gl::GenBuffers(1, &mut vbo); }
+        check_opengl_error("gl::GenBuffers")?;
+        Ok(Self {
+            vbo: vbo,
+            instance_count: Cell::new(0)
+        })
+    }
+
+    pub fn get_instance_count(&self) -> usize {
+        self.instance_count.get()
+    }
+
+    /// Replaces the matrices, orphaning the old storage so drawing from it does not stall the upload.
+    pub fn upload(&self, matrices: &[Matrix4<Float>]) -> Result<(), OpenglError> {
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            check_opengl_error("gl::BindBuffer")?;
+            gl::BufferData(gl::ARRAY_BUFFER, (matrices.len() * size_of::<Matrix4<Float>>()) as GLsizeiptr, matrices.as_ptr() as * const _, gl::STREAM_DRAW);
+            check_opengl_error("gl::BufferData")?;
+        }
+        self.instance_count.set(matrices.len());
+        Ok(())
+    }
+
+    /// Sources the matrix columns of the bound vao from this buffer, advancing once per instance.
+    pub fn enable(&self) -> Result<(), OpenglError> {
+        let stride = size_of::<Matrix4<Float>>() as GLsizei;
+        let column_size = size_of::<Matrix4<Float>>() / 4;
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            check_opengl_error("gl::BindBuffer")?;
+            for column in 0..4 {
+                let location = INSTANCE_MATRIX_LOCATION + column as GLuint;
+                gl::EnableVertexAttribArray(location);
+                check_opengl_error("gl::EnableVertexAttribArray")?;
+                gl::VertexAttribPointer(location, 4, gl::FLOAT, gl::FALSE, stride, (column * column_size) as * const c_void);
+                check_opengl_error("gl::VertexAttribPointer")?;
+                gl::VertexAttribDivisor(location, 1);
+                check_opengl_error("gl::VertexAttribDivisor")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Leaves the bound vao reading the generic identity matrix again, see `set_identity_instance`.
+    pub fn disable(&self) -> Result<(), OpenglError> {
+        for column in 0..4 {
+            unsafe { gl::DisableVertexAttribArray(INSTANCE_MATRIX_LOCATION + column); }
+            check_opengl_error("gl::DisableVertexAttribArray")?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for InstanceBuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.vbo as * const GLuint); }
+        match check_opengl_error("gl::DeleteBuffers") {
+            Ok(_) => {},
+            Err(e) => error!("{}", e)
+        }
+    }
+}
+
+/// Disabled instance attributes read the generic attribute values, which this sets to the identity matrix.
+/// Draws without instances then transform their vertices by the MVP uniform alone.
+pub fn set_identity_instance() -> Result<(), OpenglError> {
+    for column in 0..4 {
+        let mut values: [Float; 4] = [0.; 4];
+        values[column] = 1.;
+        unsafe { gl::VertexAttrib4f(INSTANCE_MATRIX_LOCATION + column as GLuint, values[0], values[1], values[2], values[3]); }
+    }
+    check_opengl_error("gl::VertexAttrib4f")?;
+    Ok(())
+}
@@ -6,43 +6,103 @@ use gl;
 use gl::types::{ GLint, GLuint, GLenum, GLsizeiptr };
 use glm::{ Matrix4, Vector3, builtin::{ dot, normalize } };
 
-use utility::read_obj;
-use graphics::{ check_opengl_error, OpenglError, mesh::{ Vertex, Triangle } };
-use super::{ VAO, Node, MeshError, Buffer };
+use utility::{ Float, load_obj, read_gltf };
+use graphics::{ check_opengl_error, OpenglError, GraphicsError, ShaderProgram, BoundingBox, mesh::{ Vertex, Triangle } };
+use graphics::animation::{ AnimationClip, NodeTransform };
+use super::{ VAO, Node, MeshError, Buffer, InstanceBuffer };
 
+/// Animations move the parts out of their rest pose, the bounds of animated meshes are grown by this on every side.
+const ANIMATED_BOUNDS_MARGIN: Float = 0.25;
+
+/// The nodes of a mesh are rendered as separate parts, so animations can move them.
 pub struct Mesh {
-    vao: Option<VAO>,
-    nodes: Vec<Node>
+    parts: Vec<MeshPart>,
+    clips: BTreeMap<String, AnimationClip>,
+    nodes: Vec<Node>,
+    bounding_box: Option<BoundingBox>
+}
+
+/// A node of the mesh with its untransformed triangles, parents come before their children.
+struct MeshPart {
+    name: String,
+    parent: Option<usize>,
+    rest_transform: NodeTransform,
+    vao: Option<VAO>
 }
 
 impl Mesh {
-    pub fn from_obj(obj_path: &str) -> Result<Mesh, MeshError> {
-        Self::from_triangles(&read_obj(obj_path)?)
+    /// Texture layers are resolved from the texture names of the mtl materials, see `ObjMaterial::get_texture_name`.
+    pub fn from_obj<F>(obj_path: &str, mut resolve_layer: F) -> Result<Mesh, MeshError>
+    where F: FnMut(&str) -> Option<u32> {
+        let mut model = load_obj(obj_path)?;
+        model.set_uv_layers(|material| resolve_layer(material.get_texture_name()?));
+        Self::from_triangles(&model.into_triangles())
+    }
+
+    /// All root nodes of the default scene in one mesh, with all animations of the file, see `read_gltf`.
+    pub fn from_gltf<F>(gltf_path: &str, resolve_layer: F) -> Result<Mesh, MeshError>
+    where F: FnMut(&str) -> Option<u32> {
+        let (nodes, clips) = read_gltf(gltf_path, resolve_layer)?;
+        Self::from_nodes(nodes, clips)
+    }
+
+    /// Clips without channels for the nodes are dropped.
+    pub fn from_nodes(nodes: Vec<Node>, clips: Vec<AnimationClip>) -> Result<Mesh, MeshError> {
+        let mut mesh = Self {
+            parts: Vec::new(),
+            clips: BTreeMap::new(),
+            nodes: nodes,
+            bounding_box: None
+        };
+        mesh.build()?;
+        let part_names: Vec<&str> = mesh.parts.iter().map(|p| p.name.as_str()).collect();
+        for mut clip in clips {
+            clip.bind(&part_names);
+            if !clip.is_empty() {
+                mesh.clips.insert(clip.get_name().to_string(), clip);
+            }
+        }
+        if !mesh.clips.is_empty() {
+            mesh.bounding_box = mesh.bounding_box.map(|b| b.expand(ANIMATED_BOUNDS_MARGIN));
+        }
+        Ok(mesh)
     }
 
     pub fn from_triangles(triangles: &[Triangle]) -> Result<Mesh, MeshError> {
         let mut node = Node::default();
         triangles.iter()
             .for_each(|t| node.add_triangle(*t));
-        let buffer = Buffer::from(node.create_transformed_triangles());
-        let vao = VAO::try_from(buffer)?;
-        let mut nodes = Vec::new();
-        nodes.push(node);
-        Ok(
-            Self {
-                vao: Some(vao),
-                nodes: nodes
-            }
-        )
+        Self::from_nodes(vec![node], Vec::new())
     }
 
+    /// The bounds of the mesh in its rest pose, None without triangles.
+    pub fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.bounding_box
+    }
+
+    /// For meshes created from a vao, whose triangles are unknown.
+    pub fn set_bounding_box(&mut self, bounding_box: Option<BoundingBox>) {
+        self.bounding_box = bounding_box;
+    }
+
+    /// Replaces the parts with a single one of the vao.
     pub fn set_vao(&mut self, vao: VAO) {
-        self.vao = Some(vao);
+        self.parts = vec![MeshPart {
+            name: String::new(),
+            parent: None,
+            rest_transform: NodeTransform::default(),
+            vao: Some(vao)
+        }];
     }
 
     pub fn build(&mut self) -> Result<(), MeshError> {
-        let buffer = Buffer::from(self.copy_triangles());
-        self.vao = Some(VAO::try_from(buffer)?);
+        let mut parts = Vec::new();
+        for node in self.nodes.iter() {
+            add_parts(node, None, &mut parts)?;
+        }
+        self.parts = parts;
+        let triangles = self.copy_triangles();
+        self.bounding_box = BoundingBox::from_points(triangles.iter().flat_map(|t| t.as_vertices().iter().map(|v| v.get_pos())));
         Ok(())
     }
 
@@ -51,10 +111,19 @@ impl Mesh {
     }
 
     pub fn get_vertex_count(&self) -> u32 {
-        match self.vao {
-            Some(ref vao) => vao.get_index_count(),
-            _ => 0
-        }
+        self.parts.iter()
+            .filter_map(|p| p.vao.as_ref())
+            .map(|vao| vao.get_index_count())
+            .sum()
+    }
+
+    pub fn get_clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    /// The transformations of the parts without animation.
+    pub fn create_rest_pose(&self) -> Vec<NodeTransform> {
+        self.parts.iter().map(|p| p.rest_transform).collect()
     }
 
     pub fn copy_triangles(&self) -> Vec<Triangle> {
@@ -65,10 +134,65 @@ impl Mesh {
         triangles
     }
 
-    pub fn render(&self) -> Result<(), MeshError> {
-        match self.vao {
-            Some(ref vao) => vao.render(),
-            None => { Ok(()) }
+    /// Renders every part with its transformation in the pose, or in the rest pose without one.
+    pub fn render(&self, shader: &ShaderProgram, mvp: &Matrix4<Float>, pose: Option<&[NodeTransform]>) -> Result<(), GraphicsError> {
+        let part_matrices = self.create_part_matrices(pose);
+        for (part, matrix) in self.parts.iter().zip(part_matrices.iter()) {
+            if let Some(ref vao) = part.vao {
+                shader.set_mvp_matrix(&(*mvp * *matrix))?;
+                vao.render()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders all instances with one draw call per part, the instance matrices are streamed through the buffer.
+    pub fn render_instanced(&self, shader: &ShaderProgram, view_projection: &Matrix4<Float>, instances: &[MeshInstance], instance_buffer: &InstanceBuffer) -> Result<(), GraphicsError> {
+        if instances.is_empty() {
+            return Ok(());
+        }
+        shader.set_mvp_matrix(view_projection)?;
+        let part_matrices: Vec<Vec<Matrix4<Float>>> = instances.iter()
+            .map(|instance| self.create_part_matrices(instance.pose))
+            .collect();
+        let mut instance_matrices = Vec::with_capacity(instances.len());
+        for (index, part) in self.parts.iter().enumerate() {
+            if let Some(ref vao) = part.vao {
+                instance_matrices.clear();
+                instance_matrices.extend(instances.iter().zip(part_matrices.iter()).map(|(instance, matrices)| instance.model * matrices[index]));
+                instance_buffer.upload(&instance_matrices)?;
+                vao.render_instanced(instance_buffer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The transformation of every part relative to the mesh.
+    fn create_part_matrices(&self, pose: Option<&[NodeTransform]>) -> Vec<Matrix4<Float>> {
+        let mut part_matrices: Vec<Matrix4<Float>> = Vec::with_capacity(self.parts.len());
+        for (index, part) in self.parts.iter().enumerate() {
+            let transform = pose.and_then(|p| p.get(index)).unwrap_or(&part.rest_transform);
+            let matrix = match part.parent {
+                Some(parent) => part_matrices[parent] * transform.create_matrix(),
+                None => transform.create_matrix()
+            };
+            part_matrices.push(matrix);
+        }
+        part_matrices
+    }
+}
+
+/// One placement of a mesh in an instanced draw.
+pub struct MeshInstance<'a> {
+    model: Matrix4<Float>,
+    pose: Option<&'a [NodeTransform]>
+}
+
+impl<'a> MeshInstance<'a> {
+    pub fn new(model: Matrix4<Float>, pose: Option<&'a [NodeTransform]>) -> Self {
+        Self {
+            model: model,
+            pose: pose
         }
     }
 }
@@ -76,8 +200,28 @@ impl Mesh {
 impl Default for Mesh {
     fn default() -> Self {
         Self {
-            vao: None,
-            nodes: Vec::new()
+            parts: Vec::new(),
+            clips: BTreeMap::new(),
+            nodes: Vec::new(),
+            bounding_box: None
         }
     }
 }
+
+fn add_parts(node: &Node, parent: Option<usize>, parts: &mut Vec<MeshPart>) -> Result<(), MeshError> {
+    let vao = match node.get_triangles().is_empty() {
+        true => None,
+        false => Some(VAO::try_from(Buffer::from(node.get_triangles()))?)
+    };
+    parts.push(MeshPart {
+        name: node.get_name().to_string(),
+        parent: parent,
+        rest_transform: NodeTransform::from_node(node),
+        vao: vao
+    });
+    let index = parts.len() - 1;
+    for child in node.get_children() {
+        add_parts(child, Some(index), parts)?;
+    }
+    Ok(())
+}
@@ -20,6 +20,14 @@ impl MeshManager {
         }
     }
 
+    /// Adds the scene of the gltf file as one mesh, see `Mesh::from_gltf`.
+    pub fn load_gltf<F>(&mut self, gltf_path: &str, id: &str, resolve_layer: F) -> Result<(), MeshError>
+    where F: FnMut(&str) -> Option<u32> {
+        self.add_mesh(Mesh::from_gltf(gltf_path, resolve_layer)?, id);
+        debug!("Loaded mesh '{}' from '{}'", id, gltf_path);
+        Ok(())
+    }
+
     pub fn get_mesh_rc(&self, id: &str) -> Result<Rc<Mesh>, MeshError> {
         match self.mesh_map.get(id) {
             Some(m) => Ok(m.clone()),
@@ -6,12 +6,14 @@ pub mod mesh_error;
 pub mod vao;
 pub mod node;
 pub mod buffer;
+pub mod instance_buffer;
 
 pub use self::vertex::Vertex;
 pub use self::triangle::Triangle;
-pub use self::mesh::Mesh;
+pub use self::mesh::{ Mesh, MeshInstance };
 pub use self::mesh_manager::MeshManager;
 pub use self::mesh_error::MeshError;
 pub use self::vao::VAO;
 pub use self::node::Node;
 pub use self::buffer::Buffer;
+pub use self::instance_buffer::InstanceBuffer;
@@ -1,23 +1,41 @@
-use glm::{ Vector3, Matrix4, GenNum };
+use glm::{ Vector3, Matrix4, GenNum, length };
 
-use utility::traits::{ Translatable, Rotatable };
+use utility::traits::{ Translatable, Rotatable, Scalable };
 use utility::Float;
-use graphics::transformation::{ create_rotation_matrix };
+use graphics::transformation::{ create_rotation_matrix, create_euler_rotation };
 use super::Triangle;
 
+/// Triangles and child nodes sharing one transformation, which applies to the children as well.
 pub struct Node {
+    name: String,
     translation: Vector3<Float>,
     rotation: Vector3<Float>,
     scale: Vector3<Float>,
     triangles: Vec<Triangle>,
+    children: Vec<Node>
 }
 
 impl Node {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
 
+    /// The triangles of the node and all its children, in the space of the parent node.
     pub fn create_transformed_triangles(&self) -> Vec<Triangle> {
         let rotation_matrix = create_rotation_matrix(self.rotation);
         let mut transformed_triangles = self.triangles.clone();
+        for child in self.children.iter() {
+            transformed_triangles.extend(child.create_transformed_triangles());
+        }
         for t in transformed_triangles.iter_mut() {
+            t.scale(self.scale);
             t.rotate(rotation_matrix);
             t.move_vertices(self.translation);
         }
@@ -30,15 +48,43 @@ impl Node {
     pub fn add_triangles(&mut self, triangles: Vec<Triangle>) {
         self.triangles.extend(triangles);
     }
+
+    pub fn add_child(&mut self, child: Node) {
+        self.children.push(child);
+    }
+
+    pub fn get_triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    pub fn get_children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Sets the transformation from a matrix without shear.
+    pub fn set_matrix(&mut self, matrix: Matrix4<Float>) {
+        let columns = [matrix[0].truncate(3), matrix[1].truncate(3), matrix[2].truncate(3)];
+        self.translation = matrix[3].truncate(3);
+        self.scale = Vector3::new(length(columns[0]), length(columns[1]), length(columns[2]));
+        let mut rotation = [[0.; 3]; 3];
+        for (col, column) in columns.iter().enumerate() {
+            for row in 0..3 {
+                rotation[row][col] = if self.scale[col] > 0. { column[row] / self.scale[col] } else { 0. };
+            }
+        }
+        self.rotation = create_euler_rotation(rotation);
+    }
 }
 
 impl Default for Node {
     fn default() -> Self {
         Self {
+            name: String::new(),
             translation: Vector3::from_s(0.),
             rotation: Vector3::from_s(0.),
             scale: Vector3::from_s(1.),
-            triangles: Vec::new()
+            triangles: Vec::new(),
+            children: Vec::new()
         }
     }
 }
@@ -61,3 +107,11 @@ impl Rotatable for Node {
     }
 }
 
+impl Scalable for Node {
+    fn set_scale(&mut self, new_scale: Vector3<Float>) {
+        self.scale = new_scale;
+    }
+    fn get_scale(&self) -> Vector3<Float> {
+        self.scale
+    }
+}
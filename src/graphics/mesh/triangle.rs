@@ -58,6 +58,10 @@ impl Triangle {
         self.vertex.iter_mut().for_each(|v| v.rotate(rotation_matrix));
     }
 
+    pub fn scale(&mut self, scale: Vector3<Float>) {
+        self.vertex.iter_mut().for_each(|v| v.scale(scale));
+    }
+
     //TODO remove normals from vertices, add normal to triangle
     pub fn get_normal(&self) -> Vector3<Float> {
         self.vertex[0].get_normal()
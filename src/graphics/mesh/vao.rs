@@ -3,16 +3,16 @@ use std::collections::btree_map::Entry;
 use std::convert::TryFrom;
 use std::{ ptr, io, ffi::c_void, mem::size_of };
 use gl;
-use gl::types::{ GLint, GLuint, GLenum, GLsizeiptr };
+use gl::types::{ GLint, GLuint, GLenum, GLsizei, GLsizeiptr };
 use glm::Matrix4;
 
 use utility::{ Float, read_obj };
 use graphics::{ check_opengl_error, OpenglError, mesh::{ Vertex, Triangle } };
-use super::{ MeshError, Buffer };
+use super::{ MeshError, Buffer, InstanceBuffer, instance_buffer::set_identity_instance };
 
 pub struct VAO {
     vao: GLuint,
-    vbos: [GLuint; 4],
+    vbos: [GLuint; 5],
     index_count: GLuint
 }
 
@@ -22,6 +22,7 @@ impl VAO {
     }
 
     pub fn render(&self) -> Result<(), MeshError> {
+        set_identity_instance()?;
         unsafe {
             gl::BindVertexArray(self.vao);
             gl::DrawElements(
@@ -34,6 +35,26 @@ impl VAO {
         check_opengl_error("Mesh::render")?;
         Ok(())
     }
+
+    /// Draws the vao once for every matrix in the instance buffer, in a single call.
+    pub fn render_instanced(&self, instances: &InstanceBuffer) -> Result<(), MeshError> {
+        unsafe { gl::BindVertexArray(self.vao); }
+        check_opengl_error("gl::BindVertexArray")?;
+        instances.enable()?;
+        unsafe {
+            gl::DrawElementsInstanced(
+                gl::TRIANGLES,
+                self.index_count as i32,
+                gl::UNSIGNED_INT,
+                ptr::null(),
+                instances.get_instance_count() as GLsizei
+            );
+        }
+        let result = check_opengl_error("Mesh::render_instanced");
+        instances.disable()?;
+        result?;
+        Ok(())
+    }
 }
 
 impl TryFrom<Buffer> for VAO {
@@ -70,7 +91,7 @@ impl Drop for VAO {
     }
 }
 
-fn load_vao(vbos: &[GLuint; 4]) -> Result<GLuint, OpenglError> {
+fn load_vao(vbos: &[GLuint; 5]) -> Result<GLuint, OpenglError> {
     let mut vao: GLuint = 0;
 
     unsafe { gl::GenVertexArrays(1, &mut vao); }
@@ -85,8 +106,9 @@ fn load_vao(vbos: &[GLuint; 4]) -> Result<GLuint, OpenglError> {
         }
     }
 
-    for (index, vbo) in vbos[..3].iter().enumerate() {
-        match assign_buffer_to_vao(*vbo, index as GLuint, 3, gl::FLOAT) {
+    for (index, vbo) in vbos[..4].iter().enumerate() {
+        let size = if index < 3 { 3 } else { 1 };
+        match assign_buffer_to_vao(*vbo, index as GLuint, size, gl::FLOAT) {
             Ok(_) => {},
             Err(e) => {
                 delete_vao(vao);
@@ -95,7 +117,7 @@ fn load_vao(vbos: &[GLuint; 4]) -> Result<GLuint, OpenglError> {
         }
     }
 
-    unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vbos[3]); }
+    unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, vbos[4]); }
     match check_opengl_error("gl::BindBuffer") {
         Ok(_) => {},
         Err(e) => {
@@ -113,7 +135,7 @@ fn load_vao(vbos: &[GLuint; 4]) -> Result<GLuint, OpenglError> {
         }
     }
 
-    for i in 0..3 {
+    for i in 0..4 {
         unsafe { gl::DisableVertexAttribArray(i) }
         match check_opengl_error("gl::DisableVertexAttribArray") {
             Ok(_) => {},
@@ -138,8 +160,8 @@ fn assign_buffer_to_vao(vbo: GLuint, index: GLuint, size: GLint, data_type: GLen
     Ok(())
 }
 
-fn delete_vbos(buffers: [GLuint; 4]) {
-    unsafe { gl::DeleteBuffers(4, &buffers[0] as * const GLuint); }
+fn delete_vbos(buffers: [GLuint; 5]) {
+    unsafe { gl::DeleteBuffers(5, &buffers[0] as * const GLuint); }
 }
 
 fn delete_vao(vao: GLuint) {
@@ -10,7 +10,9 @@ use utility::{ Float, cmp_vec, traits::Transformable  };
 pub struct Vertex {
     pos: Vector3<Float>,
     uv: Vector3<Float>,
-    normal: Vector3<Float>
+    normal: Vector3<Float>,
+    /// How much light from the surroundings is blocked, from 0 for none to 1 for all.
+    occlusion: Float
 }
 
 impl Vertex {
@@ -27,6 +29,10 @@ impl Vertex {
         self.normal
     }
 
+    pub fn get_occlusion(&self) -> Float {
+        self.occlusion
+    }
+
     pub fn set_pos(&mut self, new_pos: Vector3<Float>) {
         self.pos = new_pos;
     }
@@ -39,6 +45,11 @@ impl Vertex {
         self.normal = new_normal;
     }
 
+    pub fn set_occlusion(&mut self, new_occlusion: Float) {
+        debug_assert!(new_occlusion >= 0. && new_occlusion <= 1.);
+        self.occlusion = new_occlusion;
+    }
+
     pub fn set_uv_layer(&mut self, layer: u32) {
         self.uv.z = layer as Float;
     }
@@ -52,6 +63,15 @@ impl Vertex {
         self.normal = (rotation_matrix * self.normal.extend(1.)).truncate(3);
     }
 
+    /// Normals are scaled inversely, so they stay perpendicular to the scaled surface.
+    pub fn scale(&mut self, scale: Vector3<Float>) {
+        self.pos = self.pos * scale;
+        let normal = self.normal / scale;
+        if normal.x != 0. || normal.y != 0. || normal.z != 0. {
+            self.normal = normalize(normal);
+        }
+    }
+
     pub fn on_plane(&self, axis: usize, value: Float) -> bool {
         debug_assert!(axis < 3);
         (self.pos[axis] - value).abs() < 1e-3
@@ -63,7 +83,8 @@ impl Default for Vertex {
         Vertex {
             pos: Vector3::from_s(0.),
             uv: Vector3::from_s(0.),
-            normal: Vector3::from_s(0.), 
+            normal: Vector3::from_s(0.),
+            occlusion: 0.
         }
     }
 }
@@ -75,7 +96,7 @@ impl PartialEq for Vertex {
                 match cmp_vec(&self.uv, &other.uv) {
                     Ordering::Equal => {
                         match cmp_vec(&self.normal, &other.normal) {
-                            Ordering::Equal => true,
+                            Ordering::Equal => (self.occlusion - other.occlusion).abs() < 1e-3,
                             _ => false
                         }
                     },
@@ -96,7 +117,7 @@ impl Ord for Vertex {
                 match cmp_vec(&self.uv, &other.uv) {
                     Ordering::Equal => {
                         match cmp_vec(&self.normal, &other.normal) {
-                            Ordering::Equal => Ordering::Equal,
+                            Ordering::Equal => cmp_occlusion(self.occlusion, other.occlusion),
                             order => order
                         }
                     },
@@ -116,10 +137,19 @@ impl PartialOrd for Vertex {
 
 impl fmt::Display for Vertex {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "v = {:.2}/{:.2}/{:.2}, uv = {:.2}/{:.2}/{:.2}, n = {:.2}/{:.2}/{:.2}",
+        write!(f, "v = {:.2}/{:.2}/{:.2}, uv = {:.2}/{:.2}/{:.2}, n = {:.2}/{:.2}/{:.2}, occlusion = {:.2}",
             self.pos[0], self.pos[1], self.pos[2],
             self.uv[0], self.uv[1], self.uv[2],
-            self.normal[0], self.normal[1], self.normal[2])
+            self.normal[0], self.normal[1], self.normal[2],
+            self.occlusion)
+    }
+}
+
+fn cmp_occlusion(lhs: Float, rhs: Float) -> Ordering {
+    match lhs - rhs {
+        diff if diff < -1e-3 => Ordering::Less,
+        diff if diff > 1e-3 => Ordering::Greater,
+        _ => Ordering::Equal
     }
 }
 
@@ -5,6 +5,11 @@ pub mod projection;
 pub mod transformation;
 pub mod version;
 pub mod graphics_error;
+pub mod framebuffer;
+pub mod lighting;
+pub mod animation;
+pub mod bounding_box;
+pub mod frustum;
 mod utility;
 mod opengl_error;
 
@@ -16,7 +21,12 @@ pub use self::mesh::Mesh;
 pub use self::mesh::mesh_manager::MeshManager;
 pub use self::mesh::triangle::Triangle;
 pub use self::projection::{ Projection, create_orthographic_projection };
-pub use self::transformation::{ create_transformation_matrix, create_translation_matrix, create_rotation_matrix, create_scale_matrix, create_direction, create_orthographic_projection_matrix };
+pub use self::transformation::{ create_transformation_matrix, create_translation_matrix, create_rotation_matrix, create_scale_matrix, create_euler_rotation_from_quaternion, create_quaternion_from_euler, multiply_quaternions, create_rotation_matrix_from_quaternion, create_direction, create_orthographic_projection_matrix };
 pub use self::graphics_error::GraphicsError;
+pub use self::framebuffer::Framebuffer;
+pub use self::lighting::Lighting;
+pub use self::animation::{ Animator, AnimationClip };
+pub use self::bounding_box::BoundingBox;
+pub use self::frustum::Frustum;
 pub use self::opengl_error::{ OpenglError, check_opengl_error };
 pub use self::version::get_opengl_version;
@@ -2,8 +2,12 @@ pub mod shader_program;
 pub mod shader_program_builder;
 pub mod shader_program_error;
 pub mod shader_error;
+pub mod shader_variable;
+pub mod uniform;
 
 pub use self::shader_program::ShaderProgram;
 pub use self::shader_program_builder::ShaderProgramBuilder;
 pub use self::shader_program_error::ShaderProgramError;
 pub use self::shader_error::ShaderError;
+pub use self::shader_variable::ShaderVariable;
+pub use self::uniform::Uniform;
@@ -1,29 +1,43 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use gl;
-use gl::types::{ GLint, GLuint };
+use gl::types::{ GLint, GLuint, GLenum, GLsizei };
 use glm::Matrix4;
 
 use utility::Float;
-use graphics::{ check_opengl_error };
-use super::ShaderProgramError;
+use graphics::check_opengl_error;
+use super::{ ShaderProgramError, ShaderVariable, Uniform };
 
+/// Uniforms are looked up by name on first use and cached afterwards.
+/// Uniforms the program lacks, or the driver optimized away, are warned about once and ignored.
 pub struct ShaderProgram {
-    id: GLuint, 
-    mvp_handle: GLint,
-    texture_array_handle: GLint
+    id: GLuint,
+    uniform_locations: RefCell<HashMap<String, Option<GLint>>>
 }
 
+/// Every program is expected to have these, a missing one is a linkage mistake.
+const REQUIRED_UNIFORMS: [&str; 1] = ["MVP"];
+const TEXTURE_ARRAY_UNIT: i32 = 0;
+
 impl ShaderProgram {
-    
+
     pub fn new(program_id: GLuint) -> Result<ShaderProgram, ShaderProgramError> {
         debug_assert!(program_id != 0);
         let program = Self {
             id: program_id,
-            mvp_handle: get_resource_handle(program_id, "MVP")?,
-            texture_array_handle: get_resource_handle(program_id, "textureArray")?
+            uniform_locations: RefCell::new(HashMap::new())
         };
+        for uniform in program.get_active_uniforms()? {
+            debug!("Active uniform: {}", uniform);
+        }
+        for attribute in program.get_active_attributes()? {
+            debug!("Active attribute: {}", attribute);
+        }
+        for name in REQUIRED_UNIFORMS.iter() {
+            program.require_uniform(name)?;
+        }
         program.use_program();
-        unsafe { gl::Uniform1i(program.texture_array_handle, 0) }
-        check_opengl_error("gl::Uniform1i")?;
+        program.set_sampler("textureArray", TEXTURE_ARRAY_UNIT)?;
         Ok(program)
     }
 
@@ -34,12 +48,91 @@ impl ShaderProgram {
     }
 
     pub fn set_mvp_matrix(&self, mvp_matrix: &Matrix4<Float>) -> Result<(), ShaderProgramError> {
-        unsafe {
-            gl::UniformMatrix4fv(self.mvp_handle, 1, gl::FALSE, mvp_matrix.as_array().as_ptr() as * const Float);
+        self.set_uniform("MVP", mvp_matrix)
+    }
+
+    /// Expects the program to be in use, does nothing if the program has no such uniform.
+    pub fn set_uniform<U: Uniform>(&self, name: &str, value: &U) -> Result<(), ShaderProgramError> {
+        if let Some(location) = self.get_uniform_location(name)? {
+            value.set_uniform(location);
+            check_opengl_error("gl::Uniform")?;
         }
-        check_opengl_error("gl::UniformMatrix4fv")?;
         Ok(())
     }
+
+    /// Binds a sampler uniform to a texture unit.
+    pub fn set_sampler(&self, name: &str, texture_unit: i32) -> Result<(), ShaderProgramError> {
+        debug_assert!(texture_unit >= 0);
+        self.set_uniform(name, &texture_unit)
+    }
+
+    /// Fails instead of warning if the uniform is missing.
+    pub fn require_uniform(&self, name: &str) -> Result<GLint, ShaderProgramError> {
+        match self.get_uniform_location(name)? {
+            Some(location) => Ok(location),
+            None => Err(ShaderProgramError::MissingUniform(name.to_string()))
+        }
+    }
+
+    pub fn get_uniform_location(&self, name: &str) -> Result<Option<GLint>, ShaderProgramError> {
+        if let Some(location) = self.uniform_locations.borrow().get(name) {
+            return Ok(*location);
+        }
+        let location = match get_uniform_handle(self.id, name)? {
+            -1 => {
+                warn!("Shader program has no active uniform '{}', setting it is ignored", name);
+                None
+            },
+            handle => Some(handle)
+        };
+        self.uniform_locations.borrow_mut().insert(name.to_string(), location);
+        Ok(location)
+    }
+
+    pub fn get_active_uniforms(&self) -> Result<Vec<ShaderVariable>, ShaderProgramError> {
+        self.get_active_variables(gl::ACTIVE_UNIFORMS, gl::ACTIVE_UNIFORM_MAX_LENGTH)
+    }
+
+    pub fn get_active_attributes(&self) -> Result<Vec<ShaderVariable>, ShaderProgramError> {
+        self.get_active_variables(gl::ACTIVE_ATTRIBUTES, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH)
+    }
+
+    fn get_active_variables(&self, count_param: GLenum, max_length_param: GLenum) -> Result<Vec<ShaderVariable>, ShaderProgramError> {
+        let func_name = if count_param == gl::ACTIVE_UNIFORMS { "gl::GetActiveUniform" } else { "gl::GetActiveAttrib" };
+        let mut count: GLint = 0;
+        let mut max_length: GLint = 0;
+        unsafe {
+            gl::GetProgramiv(self.id, count_param, &mut count);
+            gl::GetProgramiv(self.id, max_length_param, &mut max_length);
+        }
+        check_opengl_error("gl::GetProgramiv")?;
+        let mut variables = Vec::with_capacity(count as usize);
+        for index in 0..count as GLuint {
+            let mut name_bytes: Vec<u8> = vec![0; max_length as usize + 1];
+            let mut name_length: GLsizei = 0;
+            let mut size: GLint = 0;
+            let mut variable_type: GLenum = 0;
+            let location = unsafe {
+                if count_param == gl::ACTIVE_UNIFORMS {
+                    gl::GetActiveUniform(self.id, index, name_bytes.len() as GLsizei, &mut name_length, &mut size, &mut variable_type, name_bytes.as_mut_ptr() as *mut _);
+                } else {
+                    gl::GetActiveAttrib(self.id, index, name_bytes.len() as GLsizei, &mut name_length, &mut size, &mut variable_type, name_bytes.as_mut_ptr() as *mut _);
+                }
+                name_bytes.truncate(name_length as usize);
+                name_bytes.push(0);
+                if count_param == gl::ACTIVE_UNIFORMS {
+                    gl::GetUniformLocation(self.id, name_bytes.as_ptr() as *const _)
+                } else {
+                    gl::GetAttribLocation(self.id, name_bytes.as_ptr() as *const _)
+                }
+            };
+            check_opengl_error(func_name)?;
+            name_bytes.pop();
+            let name = String::from_utf8_lossy(&name_bytes).into_owned();
+            variables.push(ShaderVariable::new(name, location, variable_type, size));
+        }
+        Ok(variables)
+    }
 }
 
 impl Drop for ShaderProgram {
@@ -53,14 +146,11 @@ impl Drop for ShaderProgram {
     }
 }
 
-fn get_resource_handle(program_id: GLuint, resource_name: &str) -> Result<GLint, ShaderProgramError> {
-    let res_name_zero_term = resource_name.to_string() + "\0";
+fn get_uniform_handle(program_id: GLuint, uniform_name: &str) -> Result<GLint, ShaderProgramError> {
+    let name_zero_term = uniform_name.to_string() + "\0";
     let handle: GLint = unsafe {
-        gl::GetUniformLocation(program_id, res_name_zero_term.as_ptr() as *const _)
+        gl::GetUniformLocation(program_id, name_zero_term.as_ptr() as *const _)
     };
-    if handle == -1 {
-        check_opengl_error("gl::GetUniformLocation")?;
-        return Err(ShaderProgramError::FunctionFailure("gl::GetUniformLocation".to_string()));
-    }
+    check_opengl_error("gl::GetUniformLocation")?;
     Ok(handle)
 }
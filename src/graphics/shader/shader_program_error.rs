@@ -10,6 +10,7 @@ use graphics::OpenglError;
 pub enum ShaderProgramError {
     Linkage(String),
     Opengl(OpenglError),
+    MissingUniform(String),
     FunctionFailure(String)
 }
 
@@ -25,6 +26,7 @@ impl Error for ShaderProgramError {
         match *self {
             ShaderProgramError::Linkage(_) => "linkage",
             ShaderProgramError::Opengl(_) => "opengl",
+            ShaderProgramError::MissingUniform(_) => "missing uniform",
             ShaderProgramError::FunctionFailure(_) => "function failure"
         }
     }
@@ -33,6 +35,7 @@ impl Error for ShaderProgramError {
         match *self {
             ShaderProgramError::Linkage(_) => None,
             ShaderProgramError::Opengl(ref err) => Some(err),
+            ShaderProgramError::MissingUniform(_) => None,
             ShaderProgramError::FunctionFailure(_) => None
         }
     }
@@ -43,6 +46,7 @@ impl fmt::Display for ShaderProgramError {
         match *self {
             ShaderProgramError::Linkage(ref program_log) => write!(f, "{}: {}", self.description(), program_log),
             ShaderProgramError::Opengl(ref err) => write!(f, "{}/{}", self.description(), err),
+            ShaderProgramError::MissingUniform(ref name) => write!(f, "{}: {}", self.description(), name),
             ShaderProgramError::FunctionFailure(ref func_name) => write!(f, "{} @ {}", self.description(), func_name)
         }
     }
@@ -0,0 +1,51 @@
+use std::fmt;
+use gl;
+use gl::types::{ GLenum, GLint };
+
+/// An active uniform or attribute of a linked program, as reported by the driver.
+#[derive(Clone, Debug)]
+pub struct ShaderVariable {
+    name: String,
+    location: GLint,
+    variable_type: GLenum,
+    /// Number of elements for arrays, 1 otherwise.
+    size: GLint
+}
+
+impl ShaderVariable {
+    pub fn new(name: String, location: GLint, variable_type: GLenum, size: GLint) -> Self {
+        Self {
+            name: name,
+            location: location,
+            variable_type: variable_type,
+            size: size
+        }
+    }
+}
+
+impl fmt::Display for ShaderVariable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} @ {}", get_type_name(self.variable_type), self.name, self.location)?;
+        if self.size > 1 {
+            write!(f, " [{}]", self.size)?;
+        }
+        Ok(())
+    }
+}
+
+fn get_type_name(variable_type: GLenum) -> &'static str {
+    match variable_type {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::UNSIGNED_INT => "uint",
+        gl::BOOL => "bool",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        _ => "unknown"
+    }
+}
@@ -0,0 +1,65 @@
+use gl;
+use gl::types::GLint;
+use glm::{ Vector2, Vector3, Vector4, Matrix3, Matrix4 };
+
+use utility::Float;
+
+/// A value that can be written into a uniform of the program in use.
+pub trait Uniform {
+    fn set_uniform(&self, location: GLint);
+}
+
+impl Uniform for Float {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform1f(location, *self) }
+    }
+}
+
+impl Uniform for i32 {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform1i(location, *self) }
+    }
+}
+
+impl Uniform for u32 {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform1ui(location, *self) }
+    }
+}
+
+/// Booleans are passed as ints, like GLSL expects them.
+impl Uniform for bool {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform1i(location, *self as GLint) }
+    }
+}
+
+impl Uniform for Vector2<Float> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform2f(location, self.x, self.y) }
+    }
+}
+
+impl Uniform for Vector3<Float> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform3f(location, self.x, self.y, self.z) }
+    }
+}
+
+impl Uniform for Vector4<Float> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::Uniform4f(location, self.x, self.y, self.z, self.w) }
+    }
+}
+
+impl Uniform for Matrix3<Float> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, self.as_array().as_ptr() as * const Float) }
+    }
+}
+
+impl Uniform for Matrix4<Float> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, self.as_array().as_ptr() as * const Float) }
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+use std::error::Error;
+
+use ron;
+
+use utility::FileError;
+
+#[derive(Debug)]
+pub enum AtlasError {
+    File(FileError),
+    Parse(ron::de::Error),
+    InvalidTileSize([u32; 2]),
+    DuplicateName(String),
+    DuplicateLayer(u32),
+    LayerOutOfRange(u32, u32),
+    TileOutOfBounds([u32; 2], [u32; 2]),
+    UnknownTile(String)
+}
+
+impl From<FileError> for AtlasError {
+    fn from(err: FileError) -> Self {
+        AtlasError::File(err)
+    }
+}
+
+impl From<ron::de::Error> for AtlasError {
+    fn from(err: ron::de::Error) -> Self {
+        AtlasError::Parse(err)
+    }
+}
+
+impl Error for AtlasError {
+
+    fn description(&self) -> &str {
+        match *self {
+            AtlasError::File(_) => "file",
+            AtlasError::Parse(_) => "parse",
+            AtlasError::InvalidTileSize(_) => "invalid tile size",
+            AtlasError::DuplicateName(_) => "duplicate name",
+            AtlasError::DuplicateLayer(_) => "duplicate layer",
+            AtlasError::LayerOutOfRange(_, _) => "layer out of range",
+            AtlasError::TileOutOfBounds(_, _) => "tile out of bounds",
+            AtlasError::UnknownTile(_) => "unknown tile"
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            AtlasError::File(ref err) => Some(err),
+            AtlasError::Parse(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AtlasError::File(ref err) => write!(f, "{}/{}", self.description(), err),
+            AtlasError::Parse(ref err) => write!(f, "{}: {}", self.description(), err),
+            AtlasError::InvalidTileSize(size) => write!(f, "{}: {}x{}", self.description(), size[0], size[1]),
+            AtlasError::DuplicateName(ref name) => write!(f, "{}: '{}'", self.description(), name),
+            AtlasError::DuplicateLayer(layer) => write!(f, "{}: {}", self.description(), layer),
+            AtlasError::LayerOutOfRange(layer, count) => write!(f, "{}: layer {} with {} layers", self.description(), layer, count),
+            AtlasError::TileOutOfBounds(origin, image_size) => write!(f, "{}: origin {}/{}, image size {}x{}", self.description(), origin[0], origin[1], image_size[0], image_size[1]),
+            AtlasError::UnknownTile(ref name) => write!(f, "{}: '{}'", self.description(), name)
+        }
+    }
+}
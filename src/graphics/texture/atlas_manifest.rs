@@ -0,0 +1,97 @@
+use std::collections::BTreeSet;
+
+use ron;
+
+use utility::read_file;
+use super::AtlasError;
+
+/// Describes which tiles of an atlas image are loaded into which texture array layers.
+#[derive(Deserialize)]
+pub struct AtlasManifest {
+    atlas: String,
+    tile_size: [u32; 2],
+    tiles: Vec<AtlasTile>
+}
+
+#[derive(Deserialize)]
+pub struct AtlasTile {
+    name: String,
+    origin: [u32; 2],
+    layer: u32
+}
+
+impl AtlasManifest {
+    pub fn from_file(manifest_path: &str) -> Result<Self, AtlasError> {
+        let content = read_file(manifest_path)?;
+        let manifest: AtlasManifest = ron::de::from_str(&content)?;
+        manifest.validate()?;
+        debug!("Read atlas manifest '{}': atlas = '{}', tiles = {}", manifest_path, manifest.atlas, manifest.tiles.len());
+        Ok(manifest)
+    }
+
+    pub fn get_atlas_path(&self) -> &str {
+        &self.atlas
+    }
+
+    pub fn get_tile_size(&self) -> [u32; 2] {
+        self.tile_size
+    }
+
+    pub fn get_tiles(&self) -> &[AtlasTile] {
+        &self.tiles
+    }
+
+    pub fn get_layer(&self, name: &str) -> Option<u32> {
+        self.tiles.iter()
+            .find(|t| t.name == name)
+            .map(|t| t.layer)
+    }
+
+    pub fn require_layer(&self, name: &str) -> Result<u32, AtlasError> {
+        self.get_layer(name).ok_or_else(|| AtlasError::UnknownTile(name.to_string()))
+    }
+
+    /// Checks everything which can be checked without opening the atlas image.
+    /// Tile bounds are checked by the `TextureArrayBuilder`.
+    fn validate(&self) -> Result<(), AtlasError> {
+        if self.tile_size[0] == 0 || self.tile_size[1] == 0 {
+            return Err(AtlasError::InvalidTileSize(self.tile_size));
+        }
+        let mut names = BTreeSet::new();
+        for tile in self.tiles.iter() {
+            if !names.insert(tile.name.as_str()) {
+                return Err(AtlasError::DuplicateName(tile.name.clone()));
+            }
+        }
+        validate_layers(self.tiles.iter().map(|t| t.layer))
+    }
+}
+
+impl AtlasTile {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_origin(&self) -> [u32; 2] {
+        self.origin
+    }
+
+    pub fn get_layer(&self) -> u32 {
+        self.layer
+    }
+}
+
+/// Layers must be unique and cover the texture array without gaps.
+pub fn validate_layers<I: Iterator<Item = u32>>(layers: I) -> Result<(), AtlasError> {
+    let mut used_layers = BTreeSet::new();
+    for layer in layers {
+        if !used_layers.insert(layer) {
+            return Err(AtlasError::DuplicateLayer(layer));
+        }
+    }
+    let layer_count = used_layers.len() as u32;
+    match used_layers.iter().find(|layer| **layer >= layer_count) {
+        Some(layer) => Err(AtlasError::LayerOutOfRange(*layer, layer_count)),
+        None => Ok(())
+    }
+}
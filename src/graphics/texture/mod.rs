@@ -1,5 +1,9 @@
 pub mod texture_array;
 pub mod texture_array_builder;
+pub mod atlas_manifest;
+pub mod atlas_error;
 
 pub use self::texture_array::TextureArray;
 pub use self::texture_array_builder::TextureArrayBuilder;
+pub use self::atlas_manifest::{ AtlasManifest, AtlasTile };
+pub use self::atlas_error::AtlasError;
@@ -7,7 +7,8 @@ use gl::types::{ GLint, GLuint, GLsizei };
 
 use graphics::{ GraphicsError, OpenglError, check_opengl_error };
 use utility::Float;
-use super::TextureArray;
+use super::{ TextureArray, AtlasManifest, AtlasError };
+use super::atlas_manifest::validate_layers;
 
 pub struct TextureArrayBuilder {
     atlas_path: String,
@@ -30,8 +31,34 @@ impl TextureArrayBuilder {
         self
     }
 
+    pub fn from_manifest(manifest: &AtlasManifest) -> TextureArrayBuilder {
+        manifest.get_tiles()
+            .iter()
+            .fold(TextureArrayBuilder::new(manifest.get_atlas_path(), manifest.get_tile_size()), |builder, tile| {
+                let origin = tile.get_origin();
+                builder.add_texture([origin[0], origin[1], tile.get_layer()])
+            })
+    }
+
     pub fn finish(self) -> Result<TextureArray, GraphicsError> {
         info!("Creating texture array");
+        validate_layers(self.texture_origins.iter().map(|o| o[2]))?;
+
+        debug!("Opening atlas image '{}'", self.atlas_path);
+        let img = match image::open(self.atlas_path.clone())? {
+            image::DynamicImage::ImageRgba8(img) => img,
+            img => {
+                warn!("Atlas image '{}' not of format rgba8, converting", self.atlas_path);
+                img.to_rgba()
+            }
+        };
+        let image_size = [img.width(), img.height()];
+        for origin in self.texture_origins.iter() {
+            if origin[0] + self.texture_size[0] > image_size[0] || origin[1] + self.texture_size[1] > image_size[1] {
+                return Err(AtlasError::TileOutOfBounds([origin[0], origin[1]], image_size).into());
+            }
+        }
+
         let mipmaps = {
             let dim = min(self.texture_size[0], self.texture_size[1]) as Float;
             dim.log(2.0) as u32
@@ -45,14 +72,6 @@ impl TextureArrayBuilder {
         )?;
         debug!("Id = {}, size = {}x{}x{}, mipmaps = {}", texture_id, self.texture_size[0], self.texture_size[1], layer_count, mipmaps);
 
-        debug!("Opening atlas image '{}'", self.atlas_path);
-        let img = match image::open(self.atlas_path.clone())? {
-            image::DynamicImage::ImageRgba8(img) => img,
-            _ => { 
-                delete_texture(texture_id);
-                return Err(GraphicsError::InvalidImageFormat(self.atlas_path.to_string()));
-            } 
-        };
         debug!("Adding images to texture");
         match add_subimages(texture_id, img, self.texture_size, &self.texture_origins) {
             Ok(_) => {},
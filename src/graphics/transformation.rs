@@ -19,6 +19,56 @@ pub fn create_rotation_matrix(rotation: Vector3<Float>) -> Matrix4<Float> {
     glm::ext::rotate(&one, rotation.z as Float, glm::Vector3::<Float>::new(0., 0., 1.))
 }
 
+/// The angles for `create_rotation_matrix` that give the rotation matrix, indexed by row and column.
+pub fn create_euler_rotation(matrix: [[Float; 3]; 3]) -> Vector3<Float> {
+    let sin_y = matrix[0][2].max(-1.).min(1.);
+    let y = sin_y.asin();
+    if sin_y.abs() < 0.9999 {
+        Vector3::new((-matrix[1][2]).atan2(matrix[2][2]), y, (-matrix[0][1]).atan2(matrix[0][0]))
+    } else {
+        // gimbal lock, the x and z rotations share an axis
+        Vector3::new(matrix[2][1].atan2(matrix[1][1]), y, 0.)
+    }
+}
+
+/// The angles for `create_rotation_matrix` that give the rotation of the unit quaternion (x, y, z, w).
+pub fn create_euler_rotation_from_quaternion(quaternion: Vector4<Float>) -> Vector3<Float> {
+    let (x, y, z, w) = (quaternion.x, quaternion.y, quaternion.z, quaternion.w);
+    create_euler_rotation([
+        [1. - 2. * (y * y + z * z), 2. * (x * y - z * w), 2. * (x * z + y * w)],
+        [2. * (x * y + z * w), 1. - 2. * (x * x + z * z), 2. * (y * z - x * w)],
+        [2. * (x * z - y * w), 2. * (y * z + x * w), 1. - 2. * (x * x + y * y)]
+    ])
+}
+
+/// The unit quaternion (x, y, z, w) of the rotation `create_rotation_matrix` creates for the angles.
+pub fn create_quaternion_from_euler(rotation: Vector3<Float>) -> Vector4<Float> {
+    let axis_quaternion = |angle: Float, axis: usize| {
+        let mut quaternion = Vector4::new(0., 0., 0., (angle / 2.).cos());
+        quaternion[axis] = (angle / 2.).sin();
+        quaternion
+    };
+    multiply_quaternions(multiply_quaternions(axis_quaternion(rotation.x, 0), axis_quaternion(rotation.y, 1)), axis_quaternion(rotation.z, 2))
+}
+
+/// The rotation of b followed by the rotation of a.
+pub fn multiply_quaternions(a: Vector4<Float>, b: Vector4<Float>) -> Vector4<Float> {
+    Vector4::new(
+        a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+        a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+        a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z)
+}
+
+pub fn create_rotation_matrix_from_quaternion(quaternion: Vector4<Float>) -> Matrix4<Float> {
+    let (x, y, z, w) = (quaternion.x, quaternion.y, quaternion.z, quaternion.w);
+    Matrix4::new(
+        Vector4::new(1. - 2. * (y * y + z * z), 2. * (x * y + z * w), 2. * (x * z - y * w), 0.),
+        Vector4::new(2. * (x * y - z * w), 1. - 2. * (x * x + z * z), 2. * (y * z + x * w), 0.),
+        Vector4::new(2. * (x * z + y * w), 2. * (y * z - x * w), 1. - 2. * (x * x + y * y), 0.),
+        Vector4::new(0., 0., 0., 1.))
+}
+
 pub fn create_scale_matrix(scale: Vector3<Float>) -> Matrix4<Float> {
     glm::ext::scale(&Matrix4::<Float>::one(), scale)
 }
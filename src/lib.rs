@@ -9,9 +9,16 @@ extern crate glm;
 extern crate image;
 extern crate num_traits;
 extern crate rand;
+extern crate rand_xorshift;
 #[macro_use]
 extern crate lazy_static;
 extern crate futures;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate ron;
+extern crate serde_json;
+extern crate base64;
 
 pub mod application;
 mod graphics;
@@ -19,4 +26,6 @@ mod world;
 mod utility;
 
 
-pub use application::Application;
+pub use application::{ Application, ApplicationError, OffscreenSetup, render_offscreen };
+pub use world::{ World, WorldConfig, WorldStats, DesignationType, SIM_STEP };
+pub use world::traits::Updatable;
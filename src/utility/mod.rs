@@ -1,12 +1,17 @@
 pub mod read_file;
 pub mod read_obj;
+pub mod read_mtl;
+pub mod read_gltf;
 pub mod file_error;
 pub mod cmp;
 pub mod traits;
 pub mod float;
+mod obj_line;
 
 pub use self::read_file::read_file;
-pub use self::read_obj::read_obj;
+pub use self::read_obj::{ read_obj, load_obj };
+pub use self::read_mtl::{ read_mtl, ObjMaterial };
+pub use self::read_gltf::read_gltf;
 pub use self::file_error::FileError;
 pub use self::cmp::cmp_vec;
 pub use self::float::Float;
\ No newline at end of file
@@ -0,0 +1,71 @@
+use std::str::FromStr;
+
+use super::FileError;
+
+/// A statement of an obj or mtl file: the keyword and its arguments, without comments.
+pub struct ObjLine<'a> {
+    pub number: usize,
+    pub keyword: &'a str,
+    pub args: Vec<&'a str>
+}
+
+impl<'a> ObjLine<'a> {
+    pub fn error(&self, file_path: &str, message: &str) -> FileError {
+        FileError::UnexpectedFormat(format!("{}:{}: {}", file_path, self.number, message))
+    }
+
+    /// Parses the argument at index, a missing or malformed argument is an error of the line.
+    pub fn parse_arg<T: FromStr>(&self, file_path: &str, index: usize) -> Result<T, FileError> {
+        match self.args.get(index) {
+            Some(arg) => arg.parse().map_err(|_| self.error(file_path, &format!("'{}' is not a valid argument of '{}'", arg, self.keyword))),
+            None => Err(self.error(file_path, &format!("'{}' needs at least {} arguments", self.keyword, index + 1)))
+        }
+    }
+
+    pub fn check_arg_count(&self, file_path: &str, min: usize, max: usize) -> Result<(), FileError> {
+        if self.args.len() < min || self.args.len() > max {
+            return Err(self.error(file_path, &format!("'{}' expects {} to {} arguments, got {}", self.keyword, min, max, self.args.len())));
+        }
+        Ok(())
+    }
+}
+
+/// Splits the content into statements, joining lines continued with a trailing backslash.
+/// Empty and comment lines are skipped, line numbers start at 1.
+pub fn split_lines(content: &str) -> Vec<ObjLine<'_>> {
+    let mut statements = Vec::new();
+    let mut pending: Option<(usize, Vec<&str>)> = None;
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = match raw_line.find('#') {
+            Some(comment_start) => &raw_line[..comment_start],
+            None => raw_line
+        };
+        let line = line.trim();
+        let (continued, line) = match line.ends_with('\\') {
+            true => (true, &line[..line.len() - 1]),
+            false => (false, line)
+        };
+        let (number, mut words) = pending.take().unwrap_or((index + 1, Vec::new()));
+        words.extend(line.split_whitespace());
+        if continued {
+            pending = Some((number, words));
+            continue;
+        }
+        if words.is_empty() {
+            continue;
+        }
+        let keyword = words.remove(0);
+        statements.push(ObjLine {
+            number: number,
+            keyword: keyword,
+            args: words
+        });
+    }
+    if let Some((number, mut words)) = pending {
+        if !words.is_empty() {
+            let keyword = words.remove(0);
+            statements.push(ObjLine { number: number, keyword: keyword, args: words });
+        }
+    }
+    statements
+}
@@ -0,0 +1,721 @@
+use std::fs;
+use std::path::Path;
+use std::collections::BTreeMap;
+use std::f32::consts::FRAC_1_SQRT_2;
+use glm::{ Vector3, Vector4, Matrix4, cross, length, normalize };
+use serde::de::IgnoredAny;
+use serde_json;
+use base64;
+
+use graphics::mesh::{ Vertex, Triangle, Node };
+use graphics::{ create_euler_rotation_from_quaternion, create_quaternion_from_euler, multiply_quaternions };
+use graphics::animation::{ AnimationClip, Channel, ChannelProperty, Interpolation };
+use utility::Float;
+use utility::traits::{ Translatable, Rotatable, Scalable };
+use super::FileError;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+const MODE_TRIANGLES: u64 = 4;
+/// Quarter turn around x, which turns the y axis into the z axis.
+const Y_UP_TO_Z_UP: Vector4<Float> = Vector4 { x: FRAC_1_SQRT_2, y: 0., z: 0., w: FRAC_1_SQRT_2 };
+/// Guards against node hierarchies referring to themselves.
+const MAX_NODE_DEPTH: usize = 64;
+
+/// The parsed json of a gltf file together with the content of its buffers.
+struct Document<'a> {
+    path: &'a str,
+    root: &'a GltfRoot,
+    buffers: Vec<Vec<u8>>
+}
+
+/// The parts of the gltf json that are read, unknown properties are ignored.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GltfRoot {
+    scene: Option<usize>,
+    scenes: Vec<GltfScene>,
+    nodes: Vec<GltfNode>,
+    meshes: Vec<GltfMesh>,
+    materials: Vec<GltfMaterial>,
+    textures: Vec<GltfTexture>,
+    images: Vec<GltfImage>,
+    accessors: Vec<GltfAccessor>,
+    buffer_views: Vec<GltfBufferView>,
+    buffers: Vec<GltfBuffer>,
+    animations: Vec<GltfAnimation>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfScene {
+    nodes: Vec<usize>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfNode {
+    name: Option<String>,
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    matrix: Option<Vec<Float>>,
+    translation: Option<Vec<Float>>,
+    rotation: Option<Vec<Float>>,
+    scale: Option<Vec<Float>>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfMesh {
+    primitives: Vec<GltfPrimitive>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfPrimitive {
+    attributes: BTreeMap<String, usize>,
+    indices: Option<usize>,
+    material: Option<usize>,
+    mode: Option<u64>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GltfMaterial {
+    name: Option<String>,
+    pbr_metallic_roughness: Option<GltfPbrMetallicRoughness>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GltfPbrMetallicRoughness {
+    base_color_texture: Option<GltfTextureInfo>
+}
+
+#[derive(Deserialize)]
+struct GltfTextureInfo {
+    index: usize
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfTexture {
+    source: Option<usize>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfImage {
+    uri: Option<String>,
+    name: Option<String>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GltfAccessor {
+    buffer_view: Option<usize>,
+    byte_offset: usize,
+    component_type: u32,
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    element_type: String,
+    sparse: Option<IgnoredAny>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GltfBufferView {
+    buffer: usize,
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GltfBuffer {
+    uri: Option<String>,
+    byte_length: usize
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfAnimation {
+    name: Option<String>,
+    channels: Vec<GltfChannel>,
+    samplers: Vec<GltfSampler>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfChannel {
+    sampler: Option<usize>,
+    target: GltfChannelTarget
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfChannelTarget {
+    node: Option<usize>,
+    path: Option<String>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GltfSampler {
+    input: Option<usize>,
+    output: Option<usize>,
+    interpolation: Option<String>
+}
+
+/// Reads the default scene of a gltf or glb file, one node for each root node of the scene, and all animations.
+/// The materials are resolved to texture layers by the name of their base color image, or by their own name if they have no texture.
+/// Models are converted from the y up of gltf to the z up of the world by rotating the root nodes and their keyframes.
+/// Animation channels refer to nodes by name, so names should be unique.
+pub fn read_gltf<F>(gltf_path: &str, mut resolve_layer: F) -> Result<(Vec<Node>, Vec<AnimationClip>), FileError>
+where F: FnMut(&str) -> Option<u32> {
+    let bytes = fs::read(gltf_path)?;
+    let (root, glb_buffer) = if bytes.starts_with(GLB_MAGIC) {
+        parse_glb(gltf_path, &bytes)?
+    } else {
+        (parse_json(gltf_path, &bytes)?, None)
+    };
+    let mut document = Document {
+        path: gltf_path,
+        root: &root,
+        buffers: Vec::new()
+    };
+    document.buffers = document.load_buffers(glb_buffer)?;
+
+    let mut material_layers = Vec::new();
+    for material in root.materials.iter() {
+        let layer = match document.get_material_texture_name(material) {
+            Some(name) => {
+                let layer = resolve_layer(&name);
+                if layer.is_none() {
+                    warn!("{}: no texture layer for '{}'", gltf_path, name);
+                }
+                layer
+            },
+            None => None
+        };
+        material_layers.push(layer);
+    }
+    let mut meshes = Vec::new();
+    for mesh in root.meshes.iter() {
+        meshes.push(document.read_mesh(mesh, &material_layers)?);
+    }
+
+    let root_indices = document.get_scene_nodes()?;
+    let mut nodes = Vec::new();
+    for node_index in root_indices.iter() {
+        let mut node = document.read_node(*node_index, &meshes, 0)?;
+        let translation = rotate_to_z_up(node.get_translation().extend(0.));
+        node.set_translation(Vector3::new(translation.x, translation.y, translation.z));
+        let rotation = multiply_quaternions(Y_UP_TO_Z_UP, create_quaternion_from_euler(node.get_rotation()));
+        node.set_rotation(create_euler_rotation_from_quaternion(rotation));
+        nodes.push(node);
+    }
+    let mut clips = Vec::new();
+    for (index, animation) in root.animations.iter().enumerate() {
+        clips.push(document.read_animation(index, animation, &root_indices)?);
+    }
+    debug!("Read gltf file '{}': buffers = {}, materials = {}, meshes = {}, root nodes = {}, animations = {}",
+        gltf_path, document.buffers.len(), material_layers.len(), meshes.len(), nodes.len(), clips.len());
+    Ok((nodes, clips))
+}
+
+impl<'a> Document<'a> {
+    fn error(&self, message: &str) -> FileError {
+        format_error(self.path, message)
+    }
+
+    fn load_buffers(&self, glb_buffer: Option<Vec<u8>>) -> Result<Vec<Vec<u8>>, FileError> {
+        let mut glb_buffer = glb_buffer;
+        let mut buffers = Vec::new();
+        for (index, buffer) in self.root.buffers.iter().enumerate() {
+            let data = match buffer.uri {
+                Some(ref uri) if uri.starts_with("data:") => {
+                    let encoded = match uri.find(";base64,") {
+                        Some(start) => &uri[start + 8..],
+                        None => return Err(self.error(&format!("data uri of buffer {} is not base64", index)))
+                    };
+                    base64::decode(encoded).map_err(|e| self.error(&format!("buffer {}: {}", index, e)))?
+                },
+                Some(ref uri) => fs::read(get_relative_path(self.path, uri))?,
+                None => match glb_buffer.take() {
+                    Some(data) => data,
+                    None => return Err(self.error(&format!("buffer {} has neither uri nor binary chunk", index)))
+                }
+            };
+            if data.len() < buffer.byte_length {
+                return Err(self.error(&format!("buffer {} has {} bytes, expected {}", index, data.len(), buffer.byte_length)));
+            }
+            buffers.push(data);
+        }
+        Ok(buffers)
+    }
+
+    /// The file stem of the base color image, like "dwarf" for "textures/dwarf.png", or the name of the material.
+    fn get_material_texture_name(&self, material: &GltfMaterial) -> Option<String> {
+        let image = material.pbr_metallic_roughness.as_ref()
+            .and_then(|pbr| pbr.base_color_texture.as_ref())
+            .and_then(|texture_info| self.root.textures.get(texture_info.index))
+            .and_then(|texture| texture.source)
+            .and_then(|source| self.root.images.get(source));
+        let image_name = image.and_then(|image| {
+            match image.uri {
+                Some(ref uri) if !uri.starts_with("data:") => Path::new(uri).file_stem().and_then(|stem| stem.to_str()),
+                _ => image.name.as_ref().map(|name| name.as_str())
+            }
+        });
+        image_name.or_else(|| material.name.as_ref().map(|name| name.as_str())).map(|name| name.to_string())
+    }
+
+    /// The triangles of all primitives of the mesh, in gltf space.
+    fn read_mesh(&self, mesh: &GltfMesh, material_layers: &[Option<u32>]) -> Result<Vec<Triangle>, FileError> {
+        let mut triangles = Vec::new();
+        for primitive in mesh.primitives.iter() {
+            let mode = primitive.mode.unwrap_or(MODE_TRIANGLES);
+            if mode != MODE_TRIANGLES {
+                warn!("{}: skipping primitive with mode {}, only triangles are supported", self.path, mode);
+                continue;
+            }
+            let attribute = |name: &str| primitive.attributes.get(name).cloned();
+            let positions = match attribute("POSITION") {
+                Some(index) => self.read_accessor(index, 3)?,
+                None => return Err(self.error("primitive without POSITION"))
+            };
+            let vertex_count = positions.len() / 3;
+            let normals = match attribute("NORMAL") {
+                Some(index) => Some(self.read_accessor(index, 3)?),
+                None => None
+            };
+            let uvs = match attribute("TEXCOORD_0") {
+                Some(index) => Some(self.read_accessor(index, 2)?),
+                None => None
+            };
+            if normals.as_ref().map(|n| n.len() / 3 != vertex_count).unwrap_or(false) || uvs.as_ref().map(|u| u.len() / 2 != vertex_count).unwrap_or(false) {
+                return Err(self.error("primitive attributes differ in count"));
+            }
+            let indices: Vec<usize> = match primitive.indices {
+                Some(index) => self.read_accessor(index, 1)?.into_iter().map(|i| i as usize).collect(),
+                None => (0..vertex_count).collect()
+            };
+            if let Some(index) = indices.iter().find(|i| **i >= vertex_count) {
+                return Err(self.error(&format!("vertex index {} is out of range, {} vertices", index, vertex_count)));
+            }
+            let layer = primitive.material
+                .and_then(|material| material_layers.get(material).cloned())
+                .unwrap_or(None);
+            for face in indices.chunks(3).filter(|face| face.len() == 3) {
+                let pos = |i: usize| Vector3::new(positions[3 * i] as Float, positions[3 * i + 1] as Float, positions[3 * i + 2] as Float);
+                let edge_cross = cross(pos(face[1]) - pos(face[0]), pos(face[2]) - pos(face[0]));
+                let flat_normal = if length(edge_cross) > 0. { normalize(edge_cross) } else { Vector3::new(0., 1., 0.) };
+                let mut triangle = Triangle::default();
+                for (corner, &i) in face.iter().enumerate() {
+                    let mut vertex = Vertex::default();
+                    vertex.set_pos(pos(i));
+                    vertex.set_normal(match normals {
+                        Some(ref n) => Vector3::new(n[3 * i] as Float, n[3 * i + 1] as Float, n[3 * i + 2] as Float),
+                        None => flat_normal
+                    });
+                    // gltf uvs start at the top of the image, obj ones at the bottom
+                    if let Some(ref uv) = uvs {
+                        vertex.set_uv(Vector3::new(uv[2 * i] as Float, 1. - uv[2 * i + 1] as Float, 0.));
+                    }
+                    if let Some(layer) = layer {
+                        vertex.set_uv_layer(layer);
+                    }
+                    triangle.set_vertex(vertex, corner);
+                }
+                triangles.push(triangle);
+            }
+        }
+        Ok(triangles)
+    }
+
+    /// Reads the elements of the accessor as flat components, normalized integers are mapped to 0..1.
+    fn read_accessor(&self, index: usize, expected_components: usize) -> Result<Vec<f64>, FileError> {
+        let accessor = match self.root.accessors.get(index) {
+            Some(accessor) => accessor,
+            None => return Err(self.error(&format!("'accessors' has no element {}", index)))
+        };
+        if accessor.sparse.is_some() {
+            return Err(self.error(&format!("accessor {} is sparse, which is not supported", index)));
+        }
+        let components = match accessor.element_type.as_str() {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            _ => return Err(self.error(&format!("accessor {} has an unsupported type", index)))
+        };
+        if components != expected_components {
+            return Err(self.error(&format!("accessor {} has {} components, expected {}", index, components, expected_components)));
+        }
+        let component_type = accessor.component_type;
+        let component_size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(self.error(&format!("accessor {} has unknown component type {}", index, component_type)))
+        };
+        let normalized = accessor.normalized;
+        let count = accessor.count;
+        let out_of_range = || self.error(&format!("accessor {} has offsets or counts out of range", index));
+        let value_count = count.checked_mul(components).ok_or_else(out_of_range)?;
+        let view_index = match accessor.buffer_view {
+            Some(view_index) => view_index,
+            None => return Ok(vec![0.; value_count])
+        };
+        let view = match self.root.buffer_views.get(view_index) {
+            Some(view) => view,
+            None => return Err(self.error(&format!("'bufferViews' has no element {}", view_index)))
+        };
+        let buffer = match self.buffers.get(view.buffer) {
+            Some(buffer) => buffer,
+            None => return Err(self.error(&format!("buffer view {} refers to missing buffer {}", view_index, view.buffer)))
+        };
+        let element_size = component_size * components;
+        let stride = view.byte_stride.unwrap_or(element_size);
+        // the offsets are read from the file, so they may overflow
+        let start = view.byte_offset.checked_add(accessor.byte_offset).ok_or_else(out_of_range)?;
+        let view_end = view.byte_offset.checked_add(view.byte_length).ok_or_else(out_of_range)?;
+        let end = match count {
+            0 => start,
+            _ => stride.checked_mul(count - 1)
+                .and_then(|offset| offset.checked_add(start))
+                .and_then(|offset| offset.checked_add(element_size))
+                .ok_or_else(out_of_range)?
+        };
+        if end > view_end.min(buffer.len()) {
+            return Err(self.error(&format!("accessor {} reaches beyond its buffer view", index)));
+        }
+
+        let mut values = Vec::with_capacity(value_count);
+        for element in 0..count {
+            for component in 0..components {
+                let offset = start + element * stride + component * component_size;
+                let bytes = &buffer[offset..offset + component_size];
+                let value = match component_type {
+                    5120 => bytes[0] as i8 as f64,
+                    5121 => bytes[0] as f64,
+                    5122 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    5123 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    5125 => read_u32(bytes) as f64,
+                    _ => f32::from_bits(read_u32(bytes)) as f64
+                };
+                values.push(match (normalized, component_type) {
+                    (true, 5120) => (value / 127.).max(-1.),
+                    (true, 5121) => value / 255.,
+                    (true, 5122) => (value / 32767.).max(-1.),
+                    (true, 5123) => value / 65535.,
+                    _ => value
+                });
+            }
+        }
+        Ok(values)
+    }
+
+    /// The root nodes of the default scene, or the nodes that are no child of another one if there are no scenes.
+    fn get_scene_nodes(&self) -> Result<Vec<usize>, FileError> {
+        if self.root.scenes.is_empty() {
+            let nodes = &self.root.nodes;
+            let is_child = |index: usize| nodes.iter().any(|node| node.children.contains(&index));
+            return Ok((0..nodes.len()).filter(|index| !is_child(*index)).collect());
+        }
+        let scene_index = self.root.scene.unwrap_or(0);
+        match self.root.scenes.get(scene_index) {
+            Some(scene) => Ok(scene.nodes.clone()),
+            None => Err(self.error(&format!("'scenes' has no element {}", scene_index)))
+        }
+    }
+
+    fn get_node_name(&self, index: usize) -> String {
+        self.root.nodes.get(index)
+            .and_then(|node| node.name.clone())
+            .unwrap_or_else(|| format!("node_{}", index))
+    }
+
+    /// Keyframes of root nodes are converted to z up like the nodes themselves.
+    fn read_animation(&self, index: usize, animation: &GltfAnimation, root_indices: &[usize]) -> Result<AnimationClip, FileError> {
+        let name = animation.name.clone()
+            .unwrap_or_else(|| format!("animation_{}", index));
+        let mut channels = Vec::new();
+        for channel in animation.channels.iter() {
+            let node_index = match channel.target.node {
+                Some(node_index) => node_index,
+                None => continue
+            };
+            let (property, components) = match channel.target.path.as_ref().map(|path| path.as_str()) {
+                Some("translation") => (ChannelProperty::Translation, 3),
+                Some("rotation") => (ChannelProperty::Rotation, 4),
+                Some("scale") => (ChannelProperty::Scale, 3),
+                path => {
+                    warn!("{}: skipping channel of animation '{}' with path {:?}", self.path, name, path);
+                    continue;
+                }
+            };
+            let sampler = match channel.sampler.and_then(|s| animation.samplers.get(s)) {
+                Some(sampler) => sampler,
+                None => return Err(self.error(&format!("channel of animation '{}' refers to a missing sampler", name)))
+            };
+            let (input, output) = match (sampler.input, sampler.output) {
+                (Some(input), Some(output)) => (input, output),
+                _ => return Err(self.error(&format!("sampler of animation '{}' lacks input or output", name)))
+            };
+            let times: Vec<Float> = self.read_accessor(input, 1)?.into_iter().map(|t| t as Float).collect();
+            let outputs = self.read_accessor(output, components)?;
+            let (interpolation, values_per_key, value_offset) = match sampler.interpolation.as_ref().map(|i| i.as_str()) {
+                Some("STEP") => (Interpolation::Step, 1, 0),
+                // the tangents are dropped, only the values are interpolated linearly
+                Some("CUBICSPLINE") => (Interpolation::Linear, 3, 1),
+                _ => (Interpolation::Linear, 1, 0)
+            };
+            if times.is_empty() || outputs.len() != times.len() * values_per_key * components {
+                return Err(self.error(&format!("sampler of animation '{}' has {} times but {} values", name, times.len(), outputs.len() / components)));
+            }
+            if times.windows(2).any(|w| w[0] > w[1]) {
+                return Err(self.error(&format!("keyframe times of animation '{}' are not ascending", name)));
+            }
+            let is_root = root_indices.contains(&node_index);
+            let values = (0..times.len()).map(|key| {
+                let start = (key * values_per_key + value_offset) * components;
+                let component = |i: usize| if i < components { outputs[start + i] as Float } else { 0. };
+                let value = Vector4::new(component(0), component(1), component(2), component(3));
+                match (is_root, property) {
+                    (true, ChannelProperty::Translation) => rotate_to_z_up(value),
+                    (true, ChannelProperty::Rotation) => multiply_quaternions(Y_UP_TO_Z_UP, value),
+                    _ => value
+                }
+            }).collect();
+            channels.push(Channel::new(&self.get_node_name(node_index), property, interpolation, times, values));
+        }
+        Ok(AnimationClip::new(&name, channels))
+    }
+
+    fn read_node(&self, index: usize, meshes: &[Vec<Triangle>], depth: usize) -> Result<Node, FileError> {
+        if depth > MAX_NODE_DEPTH {
+            return Err(self.error(&format!("node hierarchy deeper than {}, node {} might contain itself", MAX_NODE_DEPTH, index)));
+        }
+        let element = match self.root.nodes.get(index) {
+            Some(element) => element,
+            None => return Err(self.error(&format!("'nodes' has no element {}", index)))
+        };
+        let mut node = Node::new(&self.get_node_name(index));
+        if let Some(matrix) = self.get_floats(&element.matrix, "matrix", 16)? {
+            let column = |i: usize| Vector4::new(matrix[4 * i], matrix[4 * i + 1], matrix[4 * i + 2], matrix[4 * i + 3]);
+            node.set_matrix(Matrix4::new(column(0), column(1), column(2), column(3)));
+        }
+        if let Some(t) = self.get_floats(&element.translation, "translation", 3)? {
+            node.set_translation(Vector3::new(t[0], t[1], t[2]));
+        }
+        if let Some(r) = self.get_floats(&element.rotation, "rotation", 4)? {
+            node.set_rotation(create_euler_rotation_from_quaternion(Vector4::new(r[0], r[1], r[2], r[3])));
+        }
+        if let Some(s) = self.get_floats(&element.scale, "scale", 3)? {
+            node.set_scale(Vector3::new(s[0], s[1], s[2]));
+        }
+        if let Some(mesh_index) = element.mesh {
+            match meshes.get(mesh_index) {
+                Some(triangles) => node.add_triangles(triangles.clone()),
+                None => return Err(self.error(&format!("node {} refers to missing mesh {}", index, mesh_index)))
+            }
+        }
+        for child in element.children.iter() {
+            node.add_child(self.read_node(*child, meshes, depth + 1)?);
+        }
+        Ok(node)
+    }
+
+    fn get_floats<'b>(&self, floats: &'b Option<Vec<Float>>, key: &str, count: usize) -> Result<Option<&'b [Float]>, FileError> {
+        match floats {
+            Some(floats) if floats.len() != count => Err(self.error(&format!("'{}' needs {} numbers, got {}", key, count, floats.len()))),
+            Some(floats) => Ok(Some(floats.as_slice())),
+            None => Ok(None)
+        }
+    }
+}
+
+/// Splits a glb file into its json and binary chunk.
+fn parse_glb(glb_path: &str, bytes: &[u8]) -> Result<(GltfRoot, Option<Vec<u8>>), FileError> {
+    if bytes.len() < 12 || read_u32(&bytes[4..8]) != 2 {
+        return Err(format_error(glb_path, "not a glb file of version 2"));
+    }
+    let length = (read_u32(&bytes[8..12]) as usize).min(bytes.len());
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read_u32(&bytes[offset..offset + 4]) as usize;
+        let chunk_type = read_u32(&bytes[offset + 4..offset + 8]);
+        let chunk_start = offset + 8;
+        if chunk_start + chunk_length > length {
+            return Err(format_error(glb_path, &format!("chunk at byte {} reaches beyond the file", offset)));
+        }
+        let chunk = &bytes[chunk_start..chunk_start + chunk_length];
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(parse_json(glb_path, chunk)?),
+            GLB_CHUNK_BIN if bin.is_none() => bin = Some(chunk.to_vec()),
+            _ => trace!("Ignoring glb chunk of type {:#x} in '{}'", chunk_type, glb_path)
+        }
+        offset = chunk_start + chunk_length;
+    }
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => Err(format_error(glb_path, "no json chunk"))
+    }
+}
+
+fn parse_json(gltf_path: &str, bytes: &[u8]) -> Result<GltfRoot, FileError> {
+    serde_json::from_slice(bytes).map_err(|e| format_error(gltf_path, &e.to_string()))
+}
+
+fn format_error(gltf_path: &str, message: &str) -> FileError {
+    FileError::UnexpectedFormat(format!("{}: {}", gltf_path, message))
+}
+
+/// Rotates the first three components of the vector from y up to z up.
+fn rotate_to_z_up(vector: Vector4<Float>) -> Vector4<Float> {
+    Vector4::new(vector.x, -vector.z, vector.y, vector.w)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn get_relative_path(gltf_path: &str, file: &str) -> String {
+    match Path::new(gltf_path).parent() {
+        Some(dir) => dir.join(file).to_string_lossy().into_owned(),
+        None => file.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use glm::Vector3;
+    use base64;
+
+    use graphics::mesh::Node;
+    use utility::Float;
+    use utility::traits::Translatable;
+    use super::{ read_gltf, GLB_MAGIC, GLB_CHUNK_JSON, GLB_CHUNK_BIN };
+
+    /// One triangle in the xy plane of gltf, its last corner points up along y.
+    const POSITIONS: [f32; 9] = [0., 0., 0., 1., 0., 0., 0., 1., 0.];
+
+    fn get_position_bytes() -> Vec<u8> {
+        POSITIONS.iter().flat_map(|v| v.to_bits().to_le_bytes().to_vec()).collect()
+    }
+
+    /// A node with the triangle, moved along y. The buffer has no uri if none is given, like the one of a glb file.
+    fn create_json(uri: Option<&str>, count: usize, byte_stride: usize) -> String {
+        let uri = uri.map(|uri| format!("\"uri\": \"{}\", ", uri)).unwrap_or_default();
+        format!(r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "name": "Triangle", "mesh": 0, "translation": [0, 2, 0] }}],
+            "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}],
+            "buffers": [{{ {}"byteLength": 36 }}],
+            "bufferViews": [{{ "buffer": 0, "byteLength": 36, "byteStride": {} }}],
+            "accessors": [{{ "bufferView": 0, "componentType": 5126, "count": {}, "type": "VEC3" }}]
+        }}"#, uri, byte_stride, count)
+    }
+
+    fn create_glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut chunks = Vec::new();
+        for (chunk_type, data) in [(GLB_CHUNK_JSON, json.as_bytes()), (GLB_CHUNK_BIN, bin)].iter() {
+            chunks.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            chunks.extend_from_slice(&chunk_type.to_le_bytes());
+            chunks.extend_from_slice(data);
+        }
+        let mut glb = GLB_MAGIC.to_vec();
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(12 + chunks.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&chunks);
+        glb
+    }
+
+    fn write_file(name: &str, bytes: &[u8]) -> String {
+        let path = env::temp_dir().join(format!("dwarfs-{}-{}", name, std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn read_nodes(name: &str, bytes: &[u8]) -> Result<Vec<Node>, String> {
+        let path = write_file(name, bytes);
+        let result = read_gltf(&path, |_| None);
+        fs::remove_file(&path).unwrap();
+        result.map(|(nodes, _)| nodes).map_err(|e| e.to_string())
+    }
+
+    fn create_data_uri() -> String {
+        format!("data:application/octet-stream;base64,{}", base64::encode(&get_position_bytes()))
+    }
+
+    fn assert_vector(actual: Vector3<Float>, expected: [Float; 3]) {
+        let close = (0..3).all(|i| (actual[i] - expected[i]).abs() < 1e-5);
+        assert!(close, "{:?} is not {:?}", [actual.x, actual.y, actual.z], expected);
+    }
+
+    fn get_positions(node: &Node) -> Vec<Vector3<Float>> {
+        node.create_transformed_triangles().iter()
+            .flat_map(|triangle| triangle.get_vertices().iter().map(|vertex| vertex.get_pos()).collect::<Vec<_>>())
+            .collect()
+    }
+
+    #[test]
+    fn glb_chunks_are_read() {
+        let glb = create_glb(&create_json(None, 3, 12), &get_position_bytes());
+        let nodes = read_nodes("chunks.glb", &glb).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].get_name(), "Triangle");
+        assert_eq!(nodes[0].create_transformed_triangles().len(), 1);
+    }
+
+    #[test]
+    fn glb_chunks_beyond_the_file_are_rejected() {
+        let mut glb = create_glb(&create_json(None, 3, 12), &get_position_bytes());
+        let length = glb.len();
+        glb.truncate(length - 4);
+        let total_length = glb.len() as u32;
+        glb[8..12].copy_from_slice(&total_length.to_le_bytes());
+        assert!(read_nodes("truncated.glb", &glb).is_err());
+    }
+
+    #[test]
+    fn data_uris_are_decoded() {
+        let json = create_json(Some(&create_data_uri()), 3, 12);
+        let nodes = read_nodes("data_uri.gltf", json.as_bytes()).unwrap();
+        assert_eq!(nodes[0].create_transformed_triangles().len(), 1);
+
+        let json = create_json(Some("data:application/octet-stream;base64,not base64!"), 3, 12);
+        assert!(read_nodes("bad_data_uri.gltf", json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn accessors_beyond_their_view_are_rejected() {
+        let json = create_json(Some(&create_data_uri()), 4, 12);
+        assert!(read_nodes("long_accessor.gltf", json.as_bytes()).is_err());
+        // stride * count overflows, which must not wrap around into the bounds
+        let json = create_json(Some(&create_data_uri()), usize::max_value() / 2 + 2, 2);
+        assert!(read_nodes("overflowing_accessor.gltf", json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn y_up_is_converted_to_z_up() {
+        let json = create_json(Some(&create_data_uri()), 3, 12);
+        let nodes = read_nodes("z_up.gltf", json.as_bytes()).unwrap();
+        assert_vector(nodes[0].get_translation(), [0., 0., 2.]);
+        let positions = get_positions(&nodes[0]);
+        assert_vector(positions[0], [0., 0., 2.]);
+        assert_vector(positions[1], [1., 0., 2.]);
+        assert_vector(positions[2], [0., 0., 3.]);
+    }
+}
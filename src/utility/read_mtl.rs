@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use super::{ read_file, FileError };
+use super::obj_line::split_lines;
+
+/// A material of an mtl file, only the diffuse texture is used.
+#[derive(Clone, Debug)]
+pub struct ObjMaterial {
+    name: String,
+    diffuse_texture: Option<String>
+}
+
+impl ObjMaterial {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse_texture: None
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// The file stem of the diffuse texture, like "dwarf" for "textures/dwarf.png".
+    /// Textures are tiles of the atlas, this is the name of the tile.
+    pub fn get_texture_name(&self) -> Option<&str> {
+        let texture = self.diffuse_texture.as_ref()?;
+        Path::new(texture).file_stem().and_then(|stem| stem.to_str())
+    }
+}
+
+pub fn read_mtl(mtl_path: &str) -> Result<Vec<ObjMaterial>, FileError> {
+    let content = read_file(mtl_path)?;
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for line in split_lines(&content) {
+        if line.keyword == "newmtl" {
+            line.check_arg_count(mtl_path, 1, usize::max_value())?;
+            materials.push(ObjMaterial::new(&line.args.join(" ")));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err(line.error(mtl_path, &format!("'{}' before the first 'newmtl'", line.keyword)))
+        };
+        match line.keyword {
+            "map_Kd" => {
+                // options like "-s 1 1 1" come before the file name
+                match line.args.last() {
+                    Some(texture) => material.diffuse_texture = Some(texture.to_string()),
+                    None => return Err(line.error(mtl_path, "'map_Kd' without a texture"))
+                }
+            },
+            _ => trace!("Ignoring '{}' in '{}':{}", line.keyword, mtl_path, line.number)
+        }
+    }
+    debug!("Read mtl file '{}': materials = {}", mtl_path, materials.len());
+    Ok(materials)
+}
@@ -1,88 +1,226 @@
-use glm::{ Vector3 };
+use std::collections::BTreeMap;
+use std::path::Path;
+use glm::{ Vector3, cross, length, normalize };
 
 use graphics::mesh::{ Vertex, Triangle };
 use utility::Float;
-use super::{ read_file, FileError };
+use super::{ read_file, read_mtl, FileError, ObjMaterial };
+use super::obj_line::{ ObjLine, split_lines };
 
-pub fn read_obj(obj_path: &str) -> Result<Vec<Triangle>, FileError> {
-    let (verts, uvs, normals, faces) = read_raw_content(obj_path)?;
-    debug!("Read obj file '{}': vertices = {}, uvs = {}, normals = {}, faces = {}", obj_path, verts.len(), uvs.len(), normals.len(), faces.len());
-    let mut triangles: Vec<Triangle> = Vec::new();
-    for face in faces {
-        let mut triangle = Triangle::default();
-        for (i, indices) in face.iter().enumerate() {
-            let mut vert = Vertex::default();
-            let pos = verts[indices[0] - 1];
-            let uv = uvs[indices[1] - 1];
-            let normal = normals[indices[2] - 1];
-            vert.set_pos(Vector3::new(pos[0], pos[1], pos[2]));
-            vert.set_uv(Vector3::new(uv[0], uv[1], 0.));
-            vert.set_normal(Vector3::new(normal[0], normal[1], normal[2]));
-            triangle.set_vertex(vert, i);
+/// The triangles of one object or group of an obj file, sharing one material.
+pub struct ObjGroup {
+    object: String,
+    group: String,
+    material: Option<String>,
+    triangles: Vec<Triangle>
+}
+
+/// The content of an obj file and of the mtl files it references.
+pub struct ObjModel {
+    groups: Vec<ObjGroup>,
+    materials: BTreeMap<String, ObjMaterial>
+}
+
+/// Position, uv and normal index of a face vertex, already resolved to 0-based indices.
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+impl ObjGroup {
+    fn new(object: &str, group: &str, material: Option<String>) -> Self {
+        Self {
+            object: object.to_string(),
+            group: group.to_string(),
+            material: material,
+            triangles: Vec::new()
         }
-        triangles.push(triangle);
+    }
+}
+
+impl ObjModel {
+    pub fn get_triangle_count(&self) -> usize {
+        self.groups.iter().map(|g| g.triangles.len()).sum()
     }
 
-    Ok(triangles)
+    /// Sets the texture layer of every group whose material resolves to one.
+    /// Groups without a material keep layer 0, the number of groups whose material could not be resolved is returned.
+    pub fn set_uv_layers<F>(&mut self, mut resolve_layer: F) -> usize
+    where F: FnMut(&ObjMaterial) -> Option<u32> {
+        let mut unresolved = 0;
+        let materials = &self.materials;
+        for group in self.groups.iter_mut() {
+            let material_name = match group.material {
+                Some(ref name) => name,
+                None => continue
+            };
+            match materials.get(material_name).and_then(|material| resolve_layer(material)) {
+                Some(layer) => group.triangles.iter_mut().for_each(|t| t.set_uv_layer(layer)),
+                None => {
+                    warn!("No texture layer for material '{}' of {}/{}", material_name, group.object, group.group);
+                    unresolved += 1;
+                }
+            }
+        }
+        unresolved
+    }
+
+    pub fn into_triangles(self) -> Vec<Triangle> {
+        self.groups.into_iter().flat_map(|g| g.triangles.into_iter()).collect()
+    }
+}
+
+/// Reads all triangles of the obj file, regardless of object, group or material.
+pub fn read_obj(obj_path: &str) -> Result<Vec<Triangle>, FileError> {
+    Ok(load_obj(obj_path)?.into_triangles())
 }
-        
-fn read_raw_content(obj_path: &str) -> Result<(Vec<[Float; 3]>,
-                                               Vec<[Float; 2]>,
-                                               Vec<[Float; 3]>,
-                                               Vec<[[usize; 3]; 3]>),
-                                               FileError> {
+
+/// Reads an obj file with its materials. Polygons are triangulated as fans, so they should be convex.
+/// Missing uvs become 0, missing normals are replaced with the normal of the triangle.
+/// Mtl files are searched next to the obj file, a missing one only leaves its materials undefined.
+pub fn load_obj(obj_path: &str) -> Result<ObjModel, FileError> {
     let content = read_file(obj_path)?;
-    let mut verts: Vec<[Float; 3]> = Vec::new();
+    let mut verts: Vec<Vector3<Float>> = Vec::new();
     let mut uvs: Vec<[Float; 2]> = Vec::new();
-    let mut normals: Vec<[Float; 3]> = Vec::new();
-    let mut faces: Vec<[[usize; 3]; 3]> = Vec::new();
-    for line in content.lines() {
-        let fields: Vec<&str> = line.split(" ").collect();
-        match fields[0] {
+    let mut normals: Vec<Vector3<Float>> = Vec::new();
+    let mut materials: BTreeMap<String, ObjMaterial> = BTreeMap::new();
+    let mut groups: Vec<ObjGroup> = vec![ObjGroup::new("default", "default", None)];
+    for line in split_lines(&content) {
+        match line.keyword {
             "v" => {
-                if fields.len() != 4 {
-                    return Err(FileError::UnexpectedFormat(line.to_string()));
-                }
-                let x: Float = fields[1].parse()?;
-                let y: Float = fields[2].parse()?;
-                let z: Float = fields[3].parse()?;
-                verts.push([x, y, z]);
+                // an optional w or vertex color may follow
+                line.check_arg_count(obj_path, 3, 7)?;
+                verts.push(Vector3::new(line.parse_arg(obj_path, 0)?, line.parse_arg(obj_path, 1)?, line.parse_arg(obj_path, 2)?));
             },
             "vt" => {
-                if fields.len() != 3 {
-                    return Err(FileError::UnexpectedFormat(line.to_string()));
-                }
-                let u: Float = fields[1].parse()?;
-                let v: Float = fields[2].parse()?;
-                uvs.push([u, v]);
+                line.check_arg_count(obj_path, 1, 3)?;
+                let v = if line.args.len() > 1 { line.parse_arg(obj_path, 1)? } else { 0. };
+                uvs.push([line.parse_arg(obj_path, 0)?, v]);
             },
             "vn" => {
-                if fields.len() != 4 {
-                    return Err(FileError::UnexpectedFormat(line.to_string()));
-                }
-                let x: Float = fields[1].parse()?;
-                let y: Float = fields[2].parse()?;
-                let z: Float = fields[3].parse()?;
-                normals.push([x, y, z]);
+                line.check_arg_count(obj_path, 3, 3)?;
+                normals.push(Vector3::new(line.parse_arg(obj_path, 0)?, line.parse_arg(obj_path, 1)?, line.parse_arg(obj_path, 2)?));
             },
             "f" => {
-                if fields.len() != 4 {
-                    return Err(FileError::UnexpectedFormat(line.to_string()));
+                line.check_arg_count(obj_path, 3, usize::max_value())?;
+                let mut face = Vec::with_capacity(line.args.len());
+                for arg in &line.args {
+                    face.push(parse_face_vertex(&line, obj_path, arg, [verts.len(), uvs.len(), normals.len()])?);
                 }
-                let mut face: [[usize; 3]; 3] = [[0; 3]; 3];
-                for (i, field) in fields[1..].iter().enumerate() {
-                    let sub_fields: Vec<&str> = field.split("/").collect();
-                    if sub_fields.len() != 3 {
-                        return Err(FileError::UnexpectedFormat(line.to_string()));   
+                let group = groups.last_mut().unwrap();
+                for i in 1..face.len() - 1 {
+                    let triangle = create_triangle([face[0], face[i], face[i + 1]], &verts, &uvs, &normals);
+                    group.triangles.push(triangle);
+                }
+            },
+            "o" => {
+                let name = line.args.join(" ");
+                let material = groups.last().unwrap().material.clone();
+                start_group(&mut groups, ObjGroup::new(&name, "default", material));
+            },
+            "g" => {
+                let name = line.args.join(" ");
+                let (object, material) = {
+                    let current = groups.last().unwrap();
+                    (current.object.clone(), current.material.clone())
+                };
+                start_group(&mut groups, ObjGroup::new(&object, &name, material));
+            },
+            "usemtl" => {
+                line.check_arg_count(obj_path, 1, usize::max_value())?;
+                let name = line.args.join(" ");
+                if !materials.contains_key(&name) {
+                    warn!("{}:{}: material '{}' is not defined", obj_path, line.number, name);
+                }
+                let (object, group) = {
+                    let current = groups.last().unwrap();
+                    (current.object.clone(), current.group.clone())
+                };
+                start_group(&mut groups, ObjGroup::new(&object, &group, Some(name)));
+            },
+            "mtllib" => {
+                for mtl_file in &line.args {
+                    let mtl_path = get_relative_path(obj_path, mtl_file);
+                    match read_mtl(&mtl_path) {
+                        Ok(mtl_materials) => materials.extend(mtl_materials.into_iter().map(|m| (m.get_name().to_string(), m))),
+                        Err(FileError::IO(e)) => warn!("{}:{}: could not read '{}': {}", obj_path, line.number, mtl_path, e),
+                        Err(e) => return Err(e)
                     }
-                    face[i] = [sub_fields[0].parse()?,
-                                sub_fields[1].parse()?,
-                                sub_fields[2].parse()?];
                 }
-                faces.push(face);
             },
-            _ => {}
+            "s" | "l" | "p" => {},
+            _ => trace!("Ignoring '{}' in '{}':{}", line.keyword, obj_path, line.number)
+        }
+    }
+    groups.retain(|g| !g.triangles.is_empty());
+    let model = ObjModel {
+        groups: groups,
+        materials: materials
+    };
+    debug!("Read obj file '{}': vertices = {}, uvs = {}, normals = {}, triangles = {}, groups = {}, materials = {}",
+        obj_path, verts.len(), uvs.len(), normals.len(), model.get_triangle_count(), model.groups.len(), model.materials.len());
+    Ok(model)
+}
+
+/// Replaces the current group if nothing was added to it yet.
+fn start_group(groups: &mut Vec<ObjGroup>, group: ObjGroup) {
+    if groups.last().map(|g| g.triangles.is_empty()).unwrap_or(false) {
+        groups.pop();
+    }
+    groups.push(group);
+}
+
+/// Parses "v", "v/vt", "v//vn" or "v/vt/vn", counts are the numbers of elements defined so far.
+fn parse_face_vertex(line: &ObjLine, obj_path: &str, arg: &str, counts: [usize; 3]) -> Result<FaceVertex, FileError> {
+    let sub_fields: Vec<&str> = arg.split('/').collect();
+    if sub_fields.len() > 3 {
+        return Err(line.error(obj_path, &format!("face vertex '{}' has more than 3 indices", arg)));
+    }
+    let mut indices: [Option<usize>; 3] = [None; 3];
+    for (i, sub_field) in sub_fields.iter().enumerate() {
+        if sub_field.is_empty() {
+            if i == 0 {
+                return Err(line.error(obj_path, &format!("face vertex '{}' has no position", arg)));
+            }
+            continue;
         }
+        let index: i64 = sub_field.parse()
+            .map_err(|_| line.error(obj_path, &format!("'{}' of face vertex '{}' is not an index", sub_field, arg)))?;
+        indices[i] = Some(resolve_index(index, counts[i])
+            .ok_or_else(|| line.error(obj_path, &format!("index {} of face vertex '{}' is out of range, {} defined", index, arg, counts[i])))?);
+    }
+    Ok((indices[0].unwrap(), indices[1], indices[2]))
+}
+
+/// Positive indices start at 1, negative ones count back from the last element defined.
+fn resolve_index(index: i64, count: usize) -> Option<usize> {
+    let resolved = match index {
+        0 => return None,
+        i if i > 0 => i - 1,
+        i => count as i64 + i
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return None;
     }
-    Ok((verts, uvs, normals, faces))
-}
\ No newline at end of file
+    Some(resolved as usize)
+}
+
+fn create_triangle(face: [FaceVertex; 3], verts: &[Vector3<Float>], uvs: &[[Float; 2]], normals: &[Vector3<Float>]) -> Triangle {
+    let positions = [verts[face[0].0], verts[face[1].0], verts[face[2].0]];
+    let edge_cross = cross(positions[1] - positions[0], positions[2] - positions[0]);
+    let flat_normal = if length(edge_cross) > 0. { normalize(edge_cross) } else { Vector3::new(0., 0., 1.) };
+    let mut triangle = Triangle::default();
+    for (i, &(_, uv_index, normal_index)) in face.iter().enumerate() {
+        let mut vert = Vertex::default();
+        let uv = uv_index.map(|index| uvs[index]).unwrap_or([0., 0.]);
+        vert.set_pos(positions[i]);
+        vert.set_uv(Vector3::new(uv[0], uv[1], 0.));
+        vert.set_normal(normal_index.map(|index| normals[index]).unwrap_or(flat_normal));
+        triangle.set_vertex(vert, i);
+    }
+    triangle
+}
+
+fn get_relative_path(obj_path: &str, file: &str) -> String {
+    match Path::new(obj_path).parent() {
+        Some(dir) => dir.join(file).to_string_lossy().into_owned(),
+        None => file.to_string()
+    }
+}
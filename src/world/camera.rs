@@ -1,10 +1,10 @@
 use std::ops::Add;
-use glm::{ Vector3, Matrix4 };
+use glm::{ Vector3, Vector4, Matrix4, GenSquareMat, normalize, dot };
 use glm::ext::{ look_at, perspective };
 use num_traits::One;
 
-use graphics::{ Projection, create_direction, create_orthographic_projection, create_orthographic_projection_matrix, projection::{ create_default_orthographic, create_default_perspective } };
-use world::{ Model };
+use graphics::{ Frustum, Projection, create_direction, create_orthographic_projection, create_orthographic_projection_matrix, projection::{ create_default_orthographic, create_default_perspective } };
+use world::{ Model, Ray };
 use utility::traits::{ Translatable, Rotatable };
 use utility::Float;
 
@@ -20,6 +20,33 @@ impl Camera {
         self.projection_matrix * self.view_matrix * model.get_matrix()
     }
 
+    pub fn create_view_projection_matrix(&self) -> Matrix4<Float> {
+        self.projection_matrix * self.view_matrix
+    }
+
+    /// The visible volume of the current view and projection.
+    pub fn create_frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.create_view_projection_matrix())
+    }
+
+    /// Creates the ray through a point on the screen, given in normalized device coordinates.
+    /// The ray starts on the near plane and points away from the camera.
+    pub fn create_ray(&self, screen_pos: [Float; 2]) -> Option<Ray> {
+        let inverse = (self.projection_matrix * self.view_matrix).inverse()?;
+        let unproject = |depth: Float| {
+            let p = inverse * Vector4::new(screen_pos[0], screen_pos[1], depth, 1.);
+            Vector3::new(p.x / p.w, p.y / p.w, p.z / p.w)
+        };
+        let (near, far) = (unproject(-1.), unproject(1.));
+        let mut direction = normalize(far - near);
+        let mut origin = near;
+        if dot(direction, create_direction(self.model.get_rotation())) < 0. {
+            direction = -direction;
+            origin = far;
+        }
+        Some(Ray::new(origin, direction))
+    }
+
     pub fn zoom(&mut self, factor: Float) {
         match &mut self.projection {
             Projection::Orthographic { width, .. } => { 
@@ -32,6 +59,14 @@ impl Camera {
         self.update_projection();
     }
 
+    pub fn set_aspect_ratio(&mut self, ratio: Float) {
+        match &mut self.projection {
+            Projection::Orthographic { aspect_ratio, .. } => { *aspect_ratio = ratio; },
+            Projection::Perspective { aspect_ratio, .. } => { *aspect_ratio = ratio; }
+        }
+        self.update_projection();
+    }
+
     pub fn set_projection(&mut self, new_projection: Projection) {
         self.projection = new_projection;
         self.update_projection();
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::iter;
+
+use world::{ Layer, DIRECTION_VECTOR };
+use world::layer::{ Field, FieldType, FieldMaterial };
+use world::layer::field_generator::{ find_slope_direction, create_slope_field };
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DesignationType {
+    /// Removes the cube, leaving the cube below as floor.
+    Dig,
+    /// Removes the cube and turns the cube below into a ramp.
+    Channel,
+    /// Turns the cube into a ramp rising towards a neighbouring cube.
+    Ramp
+}
+
+/// Fields designated for a change, by position.
+pub struct Designations {
+    designations: BTreeMap<[i32; 3], DesignationType>
+}
+
+impl Designations {
+    /// Designates every cube inside the rectangle spanned by the corners, returns the number of designated fields.
+    pub fn designate_area(&mut self, layer: &Layer, corners: [[i32; 2]; 2], designation_type: DesignationType) -> usize {
+        let min = [corners[0][0].min(corners[1][0]), corners[0][1].min(corners[1][1])];
+        let max = [corners[0][0].max(corners[1][0]), corners[0][1].max(corners[1][1])];
+        let mut count = 0;
+        for y in min[1]..=max[1] {
+            for x in min[0]..=max[0] {
+                match layer.get_field([x, y]) {
+                    Some(field) if field.get_type() == FieldType::CUBE => {
+                        self.designations.insert([x, y, layer.get_level()], designation_type);
+                        count += 1;
+                    },
+                    _ => {}
+                }
+            }
+        }
+        count
+    }
+
+    pub fn designate(&mut self, pos: [i32; 3], designation_type: DesignationType) {
+        self.designations.insert(pos, designation_type);
+    }
+
+    pub fn get(&self, pos: [i32; 3]) -> Option<DesignationType> {
+        self.designations.get(&pos).cloned()
+    }
+
+    pub fn remove(&mut self, pos: [i32; 3]) -> Option<DesignationType> {
+        self.designations.remove(&pos)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ([i32; 3], DesignationType)> + '_ {
+        self.designations.iter().map(|(pos, t)| (*pos, *t))
+    }
+
+    pub fn len(&self) -> usize {
+        self.designations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.designations.is_empty()
+    }
+}
+
+impl Default for Designations {
+    fn default() -> Self {
+        Self {
+            designations: BTreeMap::new()
+        }
+    }
+}
+
+/// Changes the designated field and the slopes around it. Only the touched chunks get marked dirty.
+pub fn apply_designation(layers: &mut BTreeMap<i32, Layer>, pos: [i32; 3], designation_type: DesignationType) {
+    let pos_2d = [pos[0], pos[1]];
+    let material = match layers.get_mut(&pos[2]) {
+        Some(layer) => {
+            let material = layer.get_field(pos_2d).map(|f| f.get_material());
+            let new_field = match designation_type {
+                DesignationType::Dig | DesignationType::Channel => None,
+                DesignationType::Ramp => create_ramp(layer, pos_2d)
+            };
+            layer.set_field(pos_2d, new_field);
+            material
+        },
+        None => None
+    };
+    if designation_type == DesignationType::Channel {
+        if let Some(layer_below) = layers.get_mut(&(pos[2] - 1)) {
+            if is_cube(layer_below, pos_2d) {
+                let ramp = create_ramp(layer_below, pos_2d);
+                layer_below.set_field(pos_2d, ramp);
+                update_slopes_around(layers, [pos[0], pos[1], pos[2] - 1], None);
+            }
+        }
+    }
+    update_slopes_around(layers, pos, material);
+}
+
+/// Keeps the material of the cube, returns None if there is no neighbouring cube to lean against.
+fn create_ramp(layer: &Layer, pos: [i32; 2]) -> Option<Field> {
+    let mut field = match layer.get_field(pos) {
+        Some(field) => *field,
+        None => return None
+    };
+    match find_slope_direction(pos, |p| is_cube(layer, p)) {
+        Some(dir) => {
+            field.set_type(FieldType::SLOPE(dir));
+            Some(field)
+        },
+        None => None
+    }
+}
+
+/// Applies the slope rule of the generator to the changed field and its neighbours. Slopes which lost the cube
+/// behind them are turned or removed, and floors open to the sky get a slope of the material if a cube is next to them.
+fn update_slopes_around(layers: &mut BTreeMap<i32, Layer>, pos: [i32; 3], material: Option<FieldMaterial>) {
+    let positions: Vec<[i32; 2]> = iter::once([0, 0, 0])
+        .chain(DIRECTION_VECTOR.values().take(4).cloned())
+        .map(|offset| [pos[0] + offset[0], pos[1] + offset[1]])
+        .collect();
+    let floors: Vec<bool> = positions.iter()
+        .map(|p| is_open_floor(layers, [p[0], p[1], pos[2]]))
+        .collect();
+    let layer = match layers.get_mut(&pos[2]) {
+        Some(layer) => layer,
+        None => return
+    };
+    for (p, is_floor) in positions.into_iter().zip(floors) {
+        let new_field = match layer.get_field(p).cloned() {
+            Some(field) => match field.get_type() {
+                FieldType::SLOPE(dir) => {
+                    let behind = DIRECTION_VECTOR[&dir.get_opposite()];
+                    if is_cube(layer, [p[0] + behind[0], p[1] + behind[1]]) {
+                        continue;
+                    }
+                    create_slope_field(p, field.get_material(), |q| is_cube(layer, q))
+                },
+                FieldType::CUBE => continue
+            },
+            None if is_floor => match material.and_then(|m| create_slope_field(p, m, |q| is_cube(layer, q))) {
+                Some(field) => Some(field),
+                None => continue
+            },
+            None => continue
+        };
+        layer.set_field(p, new_field);
+    }
+}
+
+/// Empty with a cube below and nothing above, like the fields above the surface the generator puts slopes on.
+fn is_open_floor(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> bool {
+    let get_field = |level: i32| layers.get(&level).and_then(|layer| layer.get_field([pos[0], pos[1]]));
+    get_field(pos[2]).is_none()
+        && get_field(pos[2] + 1).is_none()
+        && get_field(pos[2] - 1).map_or(false, |field| field.get_type() == FieldType::CUBE)
+}
+
+fn is_cube(layer: &Layer, pos: [i32; 2]) -> bool {
+    match layer.get_field(pos) {
+        Some(field) => field.get_type() == FieldType::CUBE,
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use world::{ Layer, Direction };
+    use world::layer::FieldType;
+    use world::navigation::nav_graph::tests::{ create_layers, create_wall };
+    use super::{ apply_designation, DesignationType };
+
+    fn get_type(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> Option<FieldType> {
+        layers[&pos[2]].get_field([pos[0], pos[1]]).map(|field| field.get_type())
+    }
+
+    #[test]
+    fn dug_floor_next_to_cubes_gets_a_slope() {
+        let mut layers = create_layers(&create_wall());
+        apply_designation(&mut layers, [4, 3, 1], DesignationType::Dig);
+        // leaning against the first cube in the order of the generator, the one to the south
+        assert!(get_type(&layers, [4, 3, 1]) == Some(FieldType::SLOPE(Direction::North)));
+        assert!(get_type(&layers, [3, 3, 1]).is_none());
+        assert!(get_type(&layers, [5, 3, 1]).is_none());
+    }
+
+    #[test]
+    fn slope_without_cube_behind_is_turned_or_removed() {
+        let mut layers = create_layers(&[
+            ([2, 2, 1], FieldType::CUBE),
+            ([1, 2, 1], FieldType::SLOPE(Direction::West)),
+            ([3, 2, 1], FieldType::SLOPE(Direction::East)),
+            ([3, 1, 1], FieldType::CUBE)
+        ]);
+        apply_designation(&mut layers, [2, 2, 1], DesignationType::Dig);
+        assert!(get_type(&layers, [1, 2, 1]).is_none());
+        assert!(get_type(&layers, [3, 2, 1]) == Some(FieldType::SLOPE(Direction::South)));
+        assert!(get_type(&layers, [2, 2, 1]).is_none());
+    }
+
+    #[test]
+    fn dug_tunnel_gets_no_slope() {
+        let mut fields = create_wall();
+        fields.push(([4, 3, 2], FieldType::CUBE));
+        let mut layers = create_layers(&fields);
+        apply_designation(&mut layers, [4, 3, 1], DesignationType::Dig);
+        assert!(get_type(&layers, [4, 3, 1]).is_none());
+    }
+
+    #[test]
+    fn channel_keeps_its_hole_above_the_ramp() {
+        let mut layers = create_layers(&create_wall());
+        apply_designation(&mut layers, [4, 3, 1], DesignationType::Channel);
+        assert!(get_type(&layers, [4, 3, 1]).is_none());
+        assert!(get_type(&layers, [4, 3, 0]) == Some(FieldType::SLOPE(Direction::North)));
+        assert!(get_type(&layers, [3, 3, 0]) == Some(FieldType::CUBE));
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 use std::cmp::Ordering;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum Direction {
     North,
     East,
@@ -24,6 +24,19 @@ lazy_static! {
     };
 }
 
+impl Direction {
+    pub fn get_opposite(&self) -> Direction {
+        match *self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up
+        }
+    }
+}
+
 impl Ord for Direction {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self.clone() as u8, other.clone() as u8) {
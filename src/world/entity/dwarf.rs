@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use world::Layer;
+use world::entity::Needs;
+use world::entity::terrain::{ is_cube, is_standable };
+use world::job::{ Job, JobEvent, JobKind, Skill, Skills };
+use world::navigation::{ NavGraph, find_path };
+use world::traits::Worker;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Activity {
+    Idle,
+    Walking,
+    Working,
+    Eating,
+    Drinking,
+    Sleeping
+}
+
+pub struct Dwarf {
+    id: u32,
+    name: String,
+    position: [i32; 3],
+    /// Position before the last step, for interpolation.
+    previous_position: [i32; 3],
+    needs: Needs,
+    skills: Skills,
+    activity: Activity,
+    job: Option<Job>,
+    work_left: u32,
+    /// Hauling dwarfs carry after reaching the target of the job.
+    carrying: bool,
+    /// Remaining positions to walk to, the next one is last.
+    path: Vec<[i32; 3]>,
+    cooldown_steps: u32
+}
+
+const NAMES: [&str; 8] = ["Urist", "Kadol", "Bomrek", "Doren", "Ingish", "Litast", "Mebzuth", "Zefon"];
+
+const NEED_THRESHOLD: f32 = 0.7;
+const NEED_CRITICAL: f32 = 0.9;
+const EAT_PER_STEP: f32 = 0.05;
+const DRINK_PER_STEP: f32 = 0.1;
+const SLEEP_PER_STEP: f32 = 0.005;
+const ABANDON_COOLDOWN: u32 = 50;
+/// Skill levels needed for each additional work unit per step.
+const SKILL_LEVELS_PER_WORK_UNIT: u8 = 5;
+
+impl Dwarf {
+    /// Every dwarf knows every skill, the levels vary by id.
+    pub fn new(id: u32, position: [i32; 3]) -> Self {
+        let mut skills = Skills::default();
+        skills.set_level(Skill::Mining, (id % 3 * 5) as u8);
+        skills.set_level(Skill::Building, (id % 4 * 3) as u8);
+        skills.set_level(Skill::Hauling, 0);
+        Self {
+            id: id,
+            name: NAMES[id as usize % NAMES.len()].to_string(),
+            position: position,
+            previous_position: position,
+            needs: Needs::default(),
+            skills: skills,
+            activity: Activity::Idle,
+            job: None,
+            work_left: 0,
+            carrying: false,
+            path: Vec::new(),
+            cooldown_steps: 0
+        }
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_previous_position(&self) -> [i32; 3] {
+        self.previous_position
+    }
+
+    pub fn get_needs(&self) -> &Needs {
+        &self.needs
+    }
+
+    pub fn set_needs(&mut self, needs: Needs) {
+        self.needs = needs;
+    }
+
+    pub fn get_skills(&self) -> &Skills {
+        &self.skills
+    }
+
+    pub fn get_skills_mut(&mut self) -> &mut Skills {
+        &mut self.skills
+    }
+
+    pub fn get_activity(&self) -> Activity {
+        self.activity
+    }
+
+    pub fn get_job(&self) -> Option<&Job> {
+        self.job.as_ref()
+    }
+
+    pub fn is_carrying(&self) -> bool {
+        self.carrying
+    }
+
+    pub fn get_path(&self) -> &[[i32; 3]] {
+        &self.path
+    }
+
+    /// Advances the dwarf by one simulation step, changes of the current job are reported back to the scheduler.
+    pub fn step(&mut self, layers: &BTreeMap<i32, Layer>, nav_graph: &NavGraph) -> Option<JobEvent> {
+        self.previous_position = self.position;
+        self.needs.step();
+        self.settle(layers);
+
+        if self.satisfy_need() {
+            return None;
+        }
+        if let Some(need_activity) = self.find_urgent_need() {
+            if self.job.is_none() || self.get_highest_need() >= NEED_CRITICAL {
+                debug!("{} ({}) starts {:?}", self.name, self.id, need_activity);
+                self.activity = need_activity;
+                return self.abandon_job(&format!("interrupted by {:?}", need_activity));
+            }
+        }
+        if self.cooldown_steps > 0 {
+            self.cooldown_steps -= 1;
+        }
+        match self.job.take() {
+            Some(job) => self.work_on_job(job, layers, nav_graph),
+            None => {
+                self.activity = Activity::Idle;
+                None
+            }
+        }
+    }
+
+    /// Until there are items to eat, drink and sleep on, needs are satisfied on the spot.
+    fn satisfy_need(&mut self) -> bool {
+        match self.activity {
+            Activity::Eating => self.needs.mod_hunger(-EAT_PER_STEP),
+            Activity::Drinking => self.needs.mod_thirst(-DRINK_PER_STEP),
+            Activity::Sleeping => self.needs.mod_rest(-SLEEP_PER_STEP),
+            _ => return false
+        }
+        let satisfied = match self.activity {
+            Activity::Eating => self.needs.get_hunger() <= 0.,
+            Activity::Drinking => self.needs.get_thirst() <= 0.,
+            _ => self.needs.get_rest() <= 0.
+        };
+        if satisfied {
+            self.activity = Activity::Idle;
+        }
+        true
+    }
+
+    fn find_urgent_need(&self) -> Option<Activity> {
+        let needs = [
+            (self.needs.get_thirst(), Activity::Drinking),
+            (self.needs.get_hunger(), Activity::Eating),
+            (self.needs.get_rest(), Activity::Sleeping)
+        ];
+        needs.iter()
+            .filter(|(value, _)| *value >= NEED_THRESHOLD)
+            .fold(None, |max: Option<(f32, Activity)>, (value, activity)| match max {
+                Some((max_value, _)) if max_value >= *value => max,
+                _ => Some((*value, *activity))
+            })
+            .map(|(_, activity)| activity)
+    }
+
+    fn get_highest_need(&self) -> f32 {
+        self.needs.get_hunger().max(self.needs.get_thirst()).max(self.needs.get_rest())
+    }
+
+    fn set_path(&mut self, mut path: Vec<[i32; 3]>) {
+        path.reverse();
+        self.path = path;
+    }
+
+    /// Drops the current job, the scheduler puts it back into the queue.
+    fn abandon_job(&mut self, reason: &str) -> Option<JobEvent> {
+        let job = self.job.take()?;
+        info!("{} ({}) abandoned job #{}: {}", self.name, self.id, job.get_id(), reason);
+        self.path.clear();
+        self.cooldown_steps = ABANDON_COOLDOWN;
+        Some(JobEvent::Abandoned(job.get_id(), reason.to_string()))
+    }
+
+    fn work_on_job(&mut self, job: Job, layers: &BTreeMap<i32, Layer>, nav_graph: &NavGraph) -> Option<JobEvent> {
+        if !job.is_valid(layers) {
+            debug!("{} ({}) dropped job {}, target changed", self.name, self.id, job);
+            self.path.clear();
+            self.activity = Activity::Idle;
+            return Some(JobEvent::Invalid(job.get_id()));
+        }
+        if job.is_work_position(self.position, self.carrying) {
+            if let (JobKind::Haul { .. }, false) = (job.get_kind(), self.carrying) {
+                self.carrying = true;
+                self.path.clear();
+                self.job = Some(job);
+                return None;
+            }
+            self.activity = Activity::Working;
+            let skill_level = self.skills.get_level(job.get_required_skill()).unwrap_or(0);
+            let work_done = 1 + (skill_level / SKILL_LEVELS_PER_WORK_UNIT) as u32;
+            self.work_left = self.work_left.saturating_sub(work_done);
+            if self.work_left > 0 {
+                self.job = Some(job);
+                return None;
+            }
+            self.activity = Activity::Idle;
+            return Some(JobEvent::Completed(job.get_id()));
+        }
+        self.activity = Activity::Walking;
+        let work_positions = job.get_work_positions(self.carrying);
+        self.job = Some(job);
+        if !self.follow_path(&work_positions, nav_graph) {
+            self.activity = Activity::Idle;
+            return self.abandon_job("no path to the job");
+        }
+        None
+    }
+
+    /// Takes the next step of the path, the path is searched again if the terrain changed under it.
+    fn follow_path(&mut self, goals: &[[i32; 3]], nav_graph: &NavGraph) -> bool {
+        let next_is_linked = match self.path.last() {
+            Some(next) => nav_graph.is_linked(self.position, *next),
+            None => false
+        };
+        if !next_is_linked {
+            match find_path(nav_graph, self.position, goals) {
+                Some(path) => self.set_path(path),
+                None => return false
+            }
+        }
+        match self.path.pop() {
+            Some(next) => {
+                self.position = next;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Moves the dwarf out of solid fields and lets it fall down if there is nothing to stand on.
+    fn settle(&mut self, layers: &BTreeMap<i32, Layer>) {
+        let bottom_level = match layers.keys().next() {
+            Some(level) => *level,
+            None => return
+        };
+        while is_cube(layers, self.position) {
+            self.position[2] += 1;
+        }
+        while !is_standable(layers, self.position) && self.position[2] > bottom_level {
+            self.position[2] -= 1;
+        }
+    }
+}
+
+impl Worker for Dwarf {
+    fn get_worker_id(&self) -> u32 {
+        self.id
+    }
+
+    fn get_position(&self) -> [i32; 3] {
+        self.position
+    }
+
+    fn get_skill_level(&self, skill: Skill) -> Option<u8> {
+        self.skills.get_level(skill)
+    }
+
+    fn is_idle(&self) -> bool {
+        self.job.is_none() && self.cooldown_steps == 0 && self.activity == Activity::Idle && self.find_urgent_need().is_none()
+    }
+
+    fn assign_job(&mut self, job: Job, path: Vec<[i32; 3]>) {
+        self.work_left = job.get_work_amount();
+        self.carrying = false;
+        self.job = Some(job);
+        self.set_path(path);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod dwarf;
+pub mod needs;
+pub mod terrain;
+
+pub use self::dwarf::{ Dwarf, Activity };
+pub use self::needs::Needs;
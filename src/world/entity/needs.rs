@@ -0,0 +1,61 @@
+/// Needs grow from 0 (satisfied) to 1 (desperate) with every simulation step.
+#[derive(Copy, Clone, Debug)]
+pub struct Needs {
+    hunger: f32,
+    thirst: f32,
+    rest: f32
+}
+
+const HUNGER_PER_STEP: f32 = 1. / 3000.;
+const THIRST_PER_STEP: f32 = 1. / 2000.;
+const REST_PER_STEP: f32 = 1. / 6000.;
+
+impl Needs {
+    pub fn new(hunger: f32, thirst: f32, rest: f32) -> Self {
+        Self {
+            hunger: hunger,
+            thirst: thirst,
+            rest: rest
+        }
+    }
+
+    pub fn get_hunger(&self) -> f32 {
+        self.hunger
+    }
+
+    pub fn get_thirst(&self) -> f32 {
+        self.thirst
+    }
+
+    pub fn get_rest(&self) -> f32 {
+        self.rest
+    }
+
+    pub fn mod_hunger(&mut self, offset: f32) {
+        self.hunger = clamp(self.hunger + offset);
+    }
+
+    pub fn mod_thirst(&mut self, offset: f32) {
+        self.thirst = clamp(self.thirst + offset);
+    }
+
+    pub fn mod_rest(&mut self, offset: f32) {
+        self.rest = clamp(self.rest + offset);
+    }
+
+    pub fn step(&mut self) {
+        self.mod_hunger(HUNGER_PER_STEP);
+        self.mod_thirst(THIRST_PER_STEP);
+        self.mod_rest(REST_PER_STEP);
+    }
+}
+
+impl Default for Needs {
+    fn default() -> Self {
+        Self::new(0., 0., 0.)
+    }
+}
+
+fn clamp(value: f32) -> f32 {
+    value.max(0.).min(1.)
+}
@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+use world::Layer;
+use world::layer::{ Field, FieldType };
+
+pub fn get_field(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> Option<&Field> {
+    match layers.get(&pos[2]) {
+        Some(layer) => layer.get_field([pos[0], pos[1]]),
+        None => None
+    }
+}
+
+pub fn is_cube(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> bool {
+    get_field(layers, pos).map_or(false, |f| f.get_type() == FieldType::CUBE)
+}
+
+/// A position can be stood on if it is empty with a cube below, or if it is a slope.
+pub fn is_standable(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> bool {
+    match get_field(layers, pos).map(|f| f.get_type()) {
+        Some(FieldType::SLOPE(_)) => true,
+        Some(FieldType::CUBE) => false,
+        None => is_cube(layers, [pos[0], pos[1], pos[2] - 1])
+    }
+}
@@ -1,15 +1,78 @@
 use std::collections::HashMap;
 
+use world::WorldConfig;
 use world::noise::Noise;
 
 pub type HeightMap = HashMap<[i32; 2], i32>;
 
-pub fn create_height_map(layer_size: [i32; 2], height_noise: &Noise) -> HeightMap {
+pub fn create_height_map(config: &WorldConfig) -> HeightMap {
+    let layer_size = config.get_layer_size();
+    let height_noise = config.create_octaved_noise("height", config.get_height_noise());
     let mut height_map = HeightMap::with_capacity((layer_size[0] * layer_size[1]) as usize);
     for y in 0..layer_size[1] {
-        for x in 0..layer_size[1] {
+        for x in 0..layer_size[0] {
             height_map.insert([x, y], height_noise.get_noise((x as f32, y as f32)) as i32);
         }
     }
     height_map
 }
+
+#[cfg(test)]
+mod tests {
+    use world::{ WorldConfig, world_config::NoiseConfig };
+    use world::layer::{ FieldGenerator, FieldType };
+    use super::create_height_map;
+
+    /// Hashes of the world below, they only change if the generation changes.
+    const HEIGHT_MAP_HASH: u64 = 0xc0c4_3020_0bc7_f5f1;
+    const LAYER_HASH: u64 = 0xc9d6_e30d_1e4a_8e2e;
+
+    fn create_config() -> WorldConfig {
+        WorldConfig::new("regression", [48, 32], 5, NoiseConfig::default())
+    }
+
+    /// FNV-1a over the values in position order, independent of the order of the hash maps.
+    fn hash_values<I: Iterator<Item = i32>>(values: I) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for value in values {
+            for byte in value.to_le_bytes().iter() {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    fn get_positions(size: [i32; 2]) -> Vec<[i32; 2]> {
+        (0..size[1]).flat_map(|y| (0..size[0]).map(move |x| [x, y])).collect()
+    }
+
+    #[test]
+    fn height_map_is_reproducible() {
+        let config = create_config();
+        let height_map = create_height_map(&config);
+        let hash = hash_values(get_positions(config.get_layer_size()).iter().map(|pos| height_map[pos]));
+        assert_eq!(hash, HEIGHT_MAP_HASH, "height map hash is {:#x}", hash);
+    }
+
+    #[test]
+    fn layer_fields_are_reproducible() {
+        let config = create_config();
+        let generator = FieldGenerator::new(&config, create_height_map(&config));
+        let fields = generator.create_field_map(0);
+        let hash = hash_values(get_positions(config.get_layer_size()).iter().flat_map(|pos| {
+            let (field_type, material) = match fields.get(pos) {
+                Some(field) => {
+                    let field_type = match field.get_type() {
+                        FieldType::CUBE => 1,
+                        FieldType::SLOPE(dir) => 2 + dir as i32
+                    };
+                    (field_type, field.get_material() as i32)
+                },
+                None => (0, -1)
+            };
+            vec![field_type, material]
+        }));
+        assert_eq!(hash, LAYER_HASH, "layer hash is {:#x}", hash);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use world::{ Layer, DesignationType, DIRECTION_VECTOR };
+use world::layer::FieldMaterial;
+use world::entity::terrain::{ get_field, is_cube, is_standable };
+use world::job::Skill;
+
+pub type JobId = u32;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum JobKind {
+    /// Carries out a designation on the target cube.
+    Dig(DesignationType),
+    /// Places a cube of the material on the empty target.
+    Build(FieldMaterial),
+    /// Walks to the target and from there to the destination, items to carry come later.
+    Haul { destination: [i32; 3] }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum JobPriority {
+    Low,
+    Normal,
+    High,
+    Urgent
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum JobState {
+    Queued,
+    /// Claimed by the worker with the given id.
+    Assigned(u32)
+}
+
+/// What a worker reports back about its job after a simulation step.
+#[derive(Clone, Debug)]
+pub enum JobEvent {
+    Completed(JobId),
+    Abandoned(JobId, String),
+    /// The target changed, the job can't be done anymore.
+    Invalid(JobId)
+}
+
+#[derive(Clone, Debug)]
+pub struct Job {
+    id: JobId,
+    kind: JobKind,
+    target: [i32; 3],
+    priority: JobPriority,
+    state: JobState,
+    attempts: u32,
+    last_failure: Option<String>,
+    /// Regions no path to the job was found from, valid for the navigation graph version stored with them.
+    unreachable_from: Vec<u32>,
+    unreachable_version: u32
+}
+
+impl Job {
+    pub fn new(id: JobId, kind: JobKind, target: [i32; 3], priority: JobPriority) -> Self {
+        Self {
+            id: id,
+            kind: kind,
+            target: target,
+            priority: priority,
+            state: JobState::Queued,
+            attempts: 0,
+            last_failure: None,
+            unreachable_from: Vec::new(),
+            unreachable_version: 0
+        }
+    }
+
+    pub fn get_id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn get_kind(&self) -> JobKind {
+        self.kind
+    }
+
+    pub fn get_target(&self) -> [i32; 3] {
+        self.target
+    }
+
+    pub fn get_priority(&self) -> JobPriority {
+        self.priority
+    }
+
+    pub fn set_priority(&mut self, priority: JobPriority) {
+        self.priority = priority;
+    }
+
+    pub fn get_state(&self) -> JobState {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: JobState) {
+        self.state = state;
+    }
+
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn get_last_failure(&self) -> Option<&str> {
+        self.last_failure.as_ref().map(|s| s.as_str())
+    }
+
+    pub fn add_failure(&mut self, reason: &str) {
+        self.attempts += 1;
+        self.last_failure = Some(reason.to_string());
+    }
+
+    pub fn is_unreachable_from(&self, region: u32, nav_version: u32) -> bool {
+        self.unreachable_version == nav_version && self.unreachable_from.contains(&region)
+    }
+
+    /// Regions marked for an older version of the navigation graph are forgotten.
+    pub fn set_unreachable_from(&mut self, region: u32, nav_version: u32) {
+        if self.unreachable_version != nav_version {
+            self.unreachable_from.clear();
+            self.unreachable_version = nav_version;
+        }
+        if !self.unreachable_from.contains(&region) {
+            self.unreachable_from.push(region);
+        }
+    }
+
+    pub fn get_required_skill(&self) -> Skill {
+        match self.kind {
+            JobKind::Dig(_) => Skill::Mining,
+            JobKind::Build(_) => Skill::Building,
+            JobKind::Haul { .. } => Skill::Hauling
+        }
+    }
+
+    /// Work units needed at the work position, a worker does one unit per step plus a bonus for its skill level.
+    pub fn get_work_amount(&self) -> u32 {
+        match self.kind {
+            JobKind::Dig(DesignationType::Dig) => 20,
+            JobKind::Dig(DesignationType::Channel) => 25,
+            JobKind::Dig(DesignationType::Ramp) => 15,
+            JobKind::Build(_) => 30,
+            JobKind::Haul { .. } => 1
+        }
+    }
+
+    /// The positions a worker has to stand on to work on the job. Hauling workers first go to the target and then to the destination.
+    pub fn get_work_positions(&self, carrying: bool) -> Vec<[i32; 3]> {
+        let level = match self.kind {
+            JobKind::Dig(DesignationType::Channel) => self.target[2] + 1,
+            JobKind::Haul { destination } if carrying => return vec![destination],
+            JobKind::Haul { .. } => return vec![self.target],
+            _ => self.target[2]
+        };
+        DIRECTION_VECTOR.values()
+            .take(4)
+            .map(|offset| [self.target[0] + offset[0], self.target[1] + offset[1], level])
+            .collect()
+    }
+
+    pub fn is_work_position(&self, pos: [i32; 3], carrying: bool) -> bool {
+        self.get_work_positions(carrying).contains(&pos)
+    }
+
+    /// False if the fields changed in a way that makes the job pointless.
+    pub fn is_valid(&self, layers: &BTreeMap<i32, Layer>) -> bool {
+        match self.kind {
+            JobKind::Dig(_) => is_cube(layers, self.target),
+            JobKind::Build(_) => get_field(layers, self.target).is_none(),
+            JobKind::Haul { destination } => is_standable(layers, self.target) && is_standable(layers, destination)
+        }
+    }
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{} {:?} at {}/{}/{}, priority = {:?}, state = {:?}, attempts = {}",
+            self.id, self.kind, self.target[0], self.target[1], self.target[2], self.priority, self.state, self.attempts)?;
+        if let Some(ref reason) = self.last_failure {
+            write!(f, ", last failure = {}", reason)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{ BTreeMap, HashMap };
+use std::cmp::Reverse;
+use std::fmt;
+
+use world::job::{ Job, JobId, JobKind, JobPriority, JobState };
+use world::navigation::{ NavGraph, find_path };
+use world::traits::Worker;
+
+/// Queued and assigned jobs. Every target field is reserved by at most one job.
+pub struct JobScheduler {
+    jobs: BTreeMap<JobId, Job>,
+    reservations: HashMap<[i32; 3], JobId>,
+    next_id: JobId,
+    completed_count: u32,
+    abandoned_count: u32,
+    /// Path searches that found no path, each job is searched once per region until the navigation graph changes.
+    failed_path_count: u32
+}
+
+impl JobScheduler {
+    /// Returns None if the target is already reserved by another job.
+    pub fn add_job(&mut self, kind: JobKind, target: [i32; 3], priority: JobPriority) -> Option<JobId> {
+        if let Some(id) = self.reservations.get(&target) {
+            trace!("Could not add {:?} at {}/{}/{}: reserved by job #{}", kind, target[0], target[1], target[2], id);
+            return None;
+        }
+        self.next_id += 1;
+        let id = self.next_id;
+        self.jobs.insert(id, Job::new(id, kind, target, priority));
+        self.reservations.insert(target, id);
+        Some(id)
+    }
+
+    /// Adds a job keeping its id, like one read from a save. Returns false if the id or the target is taken.
+    pub fn restore_job(&mut self, job: Job) -> bool {
+        let id = job.get_id();
+        if self.jobs.contains_key(&id) || self.reservations.contains_key(&job.get_target()) {
+            return false;
+        }
+        self.next_id = self.next_id.max(id);
+        self.reservations.insert(job.get_target(), id);
+        self.jobs.insert(id, job);
+        true
+    }
+
+    pub fn remove_job(&mut self, id: JobId) -> Option<Job> {
+        let job = self.jobs.remove(&id)?;
+        self.reservations.remove(&job.get_target());
+        Some(job)
+    }
+
+    /// Removes the job reserving the target, like when its designation is taken back.
+    pub fn cancel_job_at(&mut self, target: [i32; 3]) -> Option<Job> {
+        let id = *self.reservations.get(&target)?;
+        self.remove_job(id)
+    }
+
+    pub fn is_reserved(&self, pos: [i32; 3]) -> bool {
+        self.reservations.contains_key(&pos)
+    }
+
+    pub fn get_job(&self, id: JobId) -> Option<&Job> {
+        self.jobs.get(&id)
+    }
+
+    pub fn get_job_mut(&mut self, id: JobId) -> Option<&mut Job> {
+        self.jobs.get_mut(&id)
+    }
+
+    pub fn get_jobs(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.values()
+    }
+
+    pub fn get_queued_count(&self) -> usize {
+        self.jobs.values().filter(|job| job.get_state() == JobState::Queued).count()
+    }
+
+    pub fn get_assigned_count(&self) -> usize {
+        self.jobs.len() - self.get_queued_count()
+    }
+
+    pub fn get_completed_count(&self) -> u32 {
+        self.completed_count
+    }
+
+    pub fn get_abandoned_count(&self) -> u32 {
+        self.abandoned_count
+    }
+
+    pub fn get_failed_path_count(&self) -> u32 {
+        self.failed_path_count
+    }
+
+    /// Claims the queued job with the highest priority the worker is skilled for, the nearest one first among equal priorities.
+    /// A job the worker can't reach is marked unreachable from its region and skipped until the navigation graph changes.
+    pub fn claim_job<W: Worker + ?Sized>(&mut self, worker: &W, nav_graph: &NavGraph) -> Option<(Job, Vec<[i32; 3]>)> {
+        let pos = worker.get_position();
+        let region = match nav_graph.get_region(pos) {
+            Some(region) => region,
+            None => {
+                trace!("Worker {} can't claim jobs at {}/{}/{}: position not walkable", worker.get_worker_id(), pos[0], pos[1], pos[2]);
+                return None;
+            }
+        };
+        let nav_version = nav_graph.get_version();
+        let mut candidates: Vec<&Job> = self.jobs.values()
+            .filter(|job| job.get_state() == JobState::Queued)
+            .filter(|job| worker.get_skill_level(job.get_required_skill()).is_some())
+            .filter(|job| !job.is_unreachable_from(region, nav_version))
+            .collect();
+        candidates.sort_by_key(|job| {
+            let target = job.get_target();
+            (Reverse(job.get_priority()), (target[0] - pos[0]).abs() + (target[1] - pos[1]).abs() + (target[2] - pos[2]).abs())
+        });
+        let candidates: Vec<JobId> = candidates.iter().map(|job| job.get_id()).collect();
+        let mut claimed = None;
+        for id in candidates {
+            let job = match self.jobs.get_mut(&id) {
+                Some(job) => job,
+                None => continue
+            };
+            match find_path(nav_graph, pos, &job.get_work_positions(false)) {
+                Some(path) => {
+                    claimed = Some((id, path));
+                    break;
+                },
+                None => {
+                    job.add_failure(&format!("no path from region {}", region));
+                    job.set_unreachable_from(region, nav_version);
+                    self.failed_path_count += 1;
+                    warn!("Worker {} found no path to job {}, skipping it until the navigation graph changes", worker.get_worker_id(), job);
+                }
+            }
+        }
+        let (id, path) = claimed?;
+        let job = self.jobs.get_mut(&id)?;
+        job.set_state(JobState::Assigned(worker.get_worker_id()));
+        debug!("Worker {} claimed job {}, path length = {}", worker.get_worker_id(), job, path.len());
+        Some((job.clone(), path))
+    }
+
+    pub fn complete_job(&mut self, id: JobId) -> Option<Job> {
+        let job = self.remove_job(id)?;
+        self.completed_count += 1;
+        debug!("Completed job {}", job);
+        Some(job)
+    }
+
+    /// Puts an assigned job back into the queue, so it can be claimed again.
+    pub fn abandon_job(&mut self, id: JobId, reason: &str) {
+        match self.jobs.get_mut(&id) {
+            Some(job) => {
+                job.set_state(JobState::Queued);
+                job.add_failure(reason);
+                self.abandoned_count += 1;
+                warn!("Requeued abandoned job {}", job);
+            },
+            None => { warn!("Could not abandon job #{}: job not existing", id); }
+        }
+    }
+}
+
+impl Default for JobScheduler {
+    fn default() -> Self {
+        Self {
+            jobs: BTreeMap::new(),
+            reservations: HashMap::new(),
+            next_id: 0,
+            completed_count: 0,
+            abandoned_count: 0,
+            failed_path_count: 0
+        }
+    }
+}
+
+impl fmt::Display for JobScheduler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "jobs = {} (queued = {}, assigned = {}), completed = {}, abandoned = {}, failed path searches = {}",
+            self.jobs.len(), self.get_queued_count(), self.get_assigned_count(), self.completed_count, self.abandoned_count, self.failed_path_count)?;
+        for job in self.jobs.values() {
+            write!(f, "\n  {}", job)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use world::DesignationType;
+    use world::layer::{ Field, FieldType };
+    use world::entity::Dwarf;
+    use world::job::{ JobKind, JobPriority };
+    use world::navigation::nav_graph::tests::{ create_layers, create_wall, build };
+    use super::JobScheduler;
+
+    #[test]
+    fn unreachable_job_is_skipped_until_the_graph_changes() {
+        let mut fields = create_wall();
+        fields.push(([6, 3, 1], FieldType::CUBE));
+        let mut layers = create_layers(&fields);
+        let mut graph = build(&layers);
+        let mut scheduler = JobScheduler::default();
+        let id = scheduler.add_job(JobKind::Dig(DesignationType::Dig), [6, 3, 1], JobPriority::Normal).unwrap();
+        let dwarf = Dwarf::new(0, [0, 3, 1]);
+
+        assert!(scheduler.claim_job(&dwarf, &graph).is_none());
+        assert!(scheduler.claim_job(&dwarf, &graph).is_none());
+        assert_eq!(scheduler.get_failed_path_count(), 1);
+        assert_eq!(scheduler.get_job(id).unwrap().get_attempts(), 1);
+
+        layers.get_mut(&1).unwrap().set_field([4, 3], None);
+        graph.update_area(&layers, [3, 2, 0], [5, 4, 2]);
+        let (job, path) = scheduler.claim_job(&dwarf, &graph).unwrap();
+        assert_eq!(job.get_id(), id);
+        assert!(job.is_work_position(*path.last().unwrap(), false));
+        assert_eq!(scheduler.get_failed_path_count(), 1);
+    }
+
+    #[test]
+    fn cancelled_job_frees_its_target() {
+        let mut scheduler = JobScheduler::default();
+        let kind = JobKind::Build(Field::default().get_material());
+        let id = scheduler.add_job(kind, [1, 1, 1], JobPriority::Normal).unwrap();
+        assert!(scheduler.add_job(kind, [1, 1, 1], JobPriority::High).is_none());
+        assert_eq!(scheduler.cancel_job_at([1, 1, 1]).map(|job| job.get_id()), Some(id));
+        assert!(!scheduler.is_reserved([1, 1, 1]));
+        assert!(scheduler.add_job(kind, [1, 1, 1], JobPriority::High).is_some());
+    }
+}
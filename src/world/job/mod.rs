@@ -0,0 +1,7 @@
+pub mod job;
+pub mod skill;
+pub mod job_scheduler;
+
+pub use self::job::{ Job, JobId, JobKind, JobPriority, JobState, JobEvent };
+pub use self::skill::{ Skill, Skills };
+pub use self::job_scheduler::JobScheduler;
@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Skill {
+    Mining,
+    Building,
+    Hauling
+}
+
+/// Skill levels of a worker, a worker without a skill can't do jobs requiring it.
+#[derive(Clone, Debug)]
+pub struct Skills {
+    levels: BTreeMap<Skill, u8>
+}
+
+impl Skills {
+    pub fn set_level(&mut self, skill: Skill, level: u8) {
+        self.levels.insert(skill, level);
+    }
+
+    pub fn get_level(&self, skill: Skill) -> Option<u8> {
+        self.levels.get(&skill).cloned()
+    }
+
+    pub fn remove(&mut self, skill: Skill) {
+        self.levels.remove(&skill);
+    }
+}
+
+impl Default for Skills {
+    fn default() -> Self {
+        Self {
+            levels: BTreeMap::new()
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use super::Field;
+use super::layer::FieldMap;
+
+/// Chunks span a single level, since every layer is meshed on its own.
+pub const CHUNK_SIZE: i32 = 16;
+
+#[derive(Clone)]
+pub struct Chunk {
+    fields: Vec<Option<Field>>,
+    field_count: usize,
+    dirty: bool
+}
+
+#[derive(Clone)]
+pub struct ChunkMap {
+    chunks: BTreeMap<[i32; 2], Chunk>
+}
+
+pub fn get_chunk_index(pos: [i32; 2]) -> [i32; 2] {
+    [pos[0].div_euclid(CHUNK_SIZE), pos[1].div_euclid(CHUNK_SIZE)]
+}
+
+pub fn get_chunk_area(chunk_index: [i32; 2]) -> [[i32; 2]; 2] {
+    let origin = [chunk_index[0] * CHUNK_SIZE, chunk_index[1] * CHUNK_SIZE];
+    [origin, [origin[0] + CHUNK_SIZE, origin[1] + CHUNK_SIZE]]
+}
+
+fn get_local_index(pos: [i32; 2]) -> usize {
+    let local = [pos[0].rem_euclid(CHUNK_SIZE), pos[1].rem_euclid(CHUNK_SIZE)];
+    (local[1] * CHUNK_SIZE + local[0]) as usize
+}
+
+impl Chunk {
+    /// Creates a clean chunk from all of its fields, ordered by row.
+    pub fn from_fields(fields: Vec<Option<Field>>) -> Self {
+        debug_assert!(fields.len() == (CHUNK_SIZE * CHUNK_SIZE) as usize);
+        Self {
+            field_count: fields.iter().filter(|f| f.is_some()).count(),
+            fields: fields,
+            dirty: false
+        }
+    }
+
+    pub fn get_fields(&self) -> &[Option<Field>] {
+        &self.fields
+    }
+
+    pub fn get_field(&self, pos: [i32; 2]) -> Option<&Field> {
+        self.fields[get_local_index(pos)].as_ref()
+    }
+
+    pub fn get_field_mut(&mut self, pos: [i32; 2]) -> Option<&mut Field> {
+        self.fields[get_local_index(pos)].as_mut()
+    }
+
+    pub fn set_field(&mut self, pos: [i32; 2], field: Option<Field>) {
+        let entry = &mut self.fields[get_local_index(pos)];
+        match (entry.is_some(), field.is_some()) {
+            (false, true) => self.field_count += 1,
+            (true, false) => self.field_count -= 1,
+            _ => {}
+        }
+        *entry = field;
+        self.dirty = true;
+    }
+
+    pub fn get_field_count(&self) -> usize {
+        self.field_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.field_count == 0
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn set_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self {
+            fields: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+            field_count: 0,
+            dirty: false
+        }
+    }
+}
+
+impl ChunkMap {
+    pub fn from_fields(fields: FieldMap) -> Self {
+        let mut chunk_map = Self::default();
+        for (pos, field) in fields {
+            chunk_map.chunks.entry(get_chunk_index(pos))
+                .or_insert_with(Chunk::default)
+                .set_field(pos, Some(field));
+        }
+        chunk_map.chunks.values_mut().for_each(|c| c.clear_dirty());
+        chunk_map
+    }
+
+    pub fn get_field(&self, pos: [i32; 2]) -> Option<&Field> {
+        match self.chunks.get(&get_chunk_index(pos)) {
+            Some(chunk) => chunk.get_field(pos),
+            None => None
+        }
+    }
+
+    pub fn get_chunk(&self, chunk_index: [i32; 2]) -> Option<&Chunk> {
+        self.chunks.get(&chunk_index)
+    }
+
+    /// Marks the chunk of the field dirty, and also its neighbours if the field is on the chunk border,
+    /// since their faces facing the field and the occlusion of their vertices may change.
+    pub fn set_field(&mut self, pos: [i32; 2], field: Option<Field>) {
+        let chunk_index = get_chunk_index(pos);
+        self.chunks.entry(chunk_index)
+            .or_insert_with(Chunk::default)
+            .set_field(pos, field);
+        for offset in [[-1, 0], [1, 0], [0, -1], [0, 1], [-1, -1], [1, -1], [1, 1], [-1, 1]].iter() {
+            let nb_index = get_chunk_index([pos[0] + offset[0], pos[1] + offset[1]]);
+            if nb_index != chunk_index {
+                if let Some(nb_chunk) = self.chunks.get_mut(&nb_index) {
+                    nb_chunk.set_dirty();
+                }
+            }
+        }
+    }
+
+    /// Replaces a whole chunk, without marking it or its neighbours dirty.
+    pub fn insert_chunk(&mut self, chunk_index: [i32; 2], chunk: Chunk) {
+        self.chunks.insert(chunk_index, chunk);
+    }
+
+    pub fn get_chunk_indices(&self) -> Vec<[i32; 2]> {
+        self.chunks.keys().cloned().collect()
+    }
+
+    pub fn get_dirty_chunk_indices(&self) -> Vec<[i32; 2]> {
+        self.chunks.iter()
+            .filter(|(_, c)| c.is_dirty())
+            .map(|(i, _)| *i)
+            .collect()
+    }
+
+    pub fn clear_dirty(&mut self, chunk_index: [i32; 2]) {
+        if let Some(chunk) = self.chunks.get_mut(&chunk_index) {
+            chunk.clear_dirty();
+        }
+    }
+
+    pub fn get_field_count(&self) -> usize {
+        self.chunks.values().map(|c| c.get_field_count()).sum()
+    }
+}
+
+impl Default for ChunkMap {
+    fn default() -> Self {
+        Self {
+            chunks: BTreeMap::new()
+        }
+    }
+}
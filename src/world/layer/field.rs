@@ -1,5 +1,6 @@
 use super::{ FieldType, FieldMaterial };
 
+#[derive(Copy, Clone)]
 pub struct Field {
     field_type: FieldType,    
     field_material: FieldMaterial
@@ -0,0 +1,171 @@
+use world::{ Direction, DIRECTION_VECTOR, SimplexNoise, OctavedNoise, WorldConfig };
+use world::world_config::GeologyConfig;
+use world::noise::Noise3;
+use world::height_map::HeightMap;
+use super::{ Field, FieldType, FieldMaterial };
+use super::layer::FieldMap;
+
+/// Fields closer to the surface than this are soil, and never carved by caves.
+const SOIL_DEPTH: i32 = 3;
+
+/// Levels are stretched, so caves extend further horizontally than vertically.
+const VERTICAL_STRETCH: f32 = 2.;
+
+const STRATA_SCALE: f32 = 5e-2;
+const STRATA_AMPLITUDE: f32 = 1.5;
+
+/// Rock strata from top to bottom, the levels they reach down to are configured in `GeologyConfig`.
+const STRATA: [FieldMaterial; 3] = [FieldMaterial::SANDSTONE, FieldMaterial::STONE, FieldMaterial::GRANITE];
+
+const SLOPE_DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West
+];
+
+const ORE_SCALE: f32 = 2e-1;
+
+/// Ores with the rocks they appear in, in the order of their thresholds in `GeologyConfig`.
+const ORES: [(FieldMaterial, [FieldMaterial; 2]); 3] = [
+    (FieldMaterial::COAL, [FieldMaterial::SANDSTONE, FieldMaterial::STONE]),
+    (FieldMaterial::IRON, [FieldMaterial::STONE, FieldMaterial::GRANITE]),
+    (FieldMaterial::GOLD, [FieldMaterial::GRANITE, FieldMaterial::GRANITE])
+];
+
+/// Creates the fields of a layer from the height map and 3d noise for caves, strata and ore veins.
+pub struct FieldGenerator {
+    layer_size: [i32; 2],
+    height_map: HeightMap,
+    geology: GeologyConfig,
+    cave_noise: OctavedNoise,
+    strata_noise: SimplexNoise,
+    ore_noise: SimplexNoise
+}
+
+impl FieldGenerator {
+    pub fn new(config: &WorldConfig, height_map: HeightMap) -> Self {
+        Self {
+            layer_size: config.get_layer_size(),
+            height_map: height_map,
+            geology: config.get_geology().clone(),
+            cave_noise: config.create_octaved_noise("caves", config.get_geology().get_cave_noise()),
+            strata_noise: config.create_simplex_noise("strata"),
+            ore_noise: config.create_simplex_noise("ores")
+        }
+    }
+
+    pub fn get_layer_size(&self) -> [i32; 2] {
+        self.layer_size
+    }
+
+    pub fn create_field_map(&self, level: i32) -> FieldMap {
+        let mut fields = FieldMap::new();
+        for (pos, height) in self.height_map.iter() {
+            if let Some(field) = self.create_field([pos[0], pos[1], level], *height) {
+                fields.insert(*pos, field);
+            }
+        }
+        if level >= 0 {
+            let slope_fields = self.create_slope_fields(&fields, level);
+            fields.extend(slope_fields);
+        }
+        fields
+    }
+
+    fn create_field(&self, pos: [i32; 3], height: i32) -> Option<Field> {
+        let depth = height - pos[2];
+        if depth <= 0 {
+            return None;
+        }
+        let mut field = Field::default();
+        match depth {
+            1 => field.set_material(FieldMaterial::GRASS),
+            d if d <= SOIL_DEPTH => field.set_material(FieldMaterial::MUD),
+            _ if self.is_cave(pos) => return None,
+            _ => {
+                let rock = self.get_rock_material(pos);
+                field.set_material(self.get_ore_material(pos, rock).unwrap_or(rock));
+            }
+        }
+        Some(field)
+    }
+
+    /// Slopes are only placed on the surface, not inside caves.
+    fn create_slope_fields(&self, fields: &FieldMap, level: i32) -> FieldMap {
+        let is_cube = |pos: [i32; 2]| match fields.get(&pos) {
+            Some(field) => field.get_type() == FieldType::CUBE,
+            None => false
+        };
+        let mut slope_fields = FieldMap::new();
+        for pos in fields.keys() {
+            for offset in DIRECTION_VECTOR.values().take(4) {
+                let nb_pos = [pos[0] + offset[0], pos[1] + offset[1]];
+                let above_surface = match self.height_map.get(&nb_pos) {
+                    Some(height) => level >= *height,
+                    None => false
+                };
+                if !above_surface || fields.contains_key(&nb_pos) || slope_fields.contains_key(&nb_pos) {
+                    continue;
+                }
+                if let Some(field) = create_slope_field(nb_pos, FieldMaterial::GRASS, &is_cube) {
+                    slope_fields.insert(nb_pos, field);
+                }
+            }
+        }
+        slope_fields
+    }
+
+    fn is_cave(&self, pos: [i32; 3]) -> bool {
+        self.cave_noise.get_noise_3d(get_noise_point(pos, 1.)) > self.geology.get_cave_threshold()
+    }
+
+    fn get_rock_material(&self, pos: [i32; 3]) -> FieldMaterial {
+        let offset = self.strata_noise.get_noise_3d((pos[0] as f32 * STRATA_SCALE, pos[1] as f32 * STRATA_SCALE, 0.)) * STRATA_AMPLITUDE;
+        let stratum_level = pos[2] as f32 + offset;
+        let bottoms = self.geology.get_strata_bottoms();
+        match bottoms.iter().position(|bottom| stratum_level >= *bottom) {
+            Some(i) => STRATA[i],
+            None => STRATA[STRATA.len() - 1]
+        }
+    }
+
+    fn get_ore_material(&self, pos: [i32; 3], rock: FieldMaterial) -> Option<FieldMaterial> {
+        let p = get_noise_point(pos, ORE_SCALE);
+        let thresholds = self.geology.get_ore_thresholds();
+        ORES.iter()
+            .enumerate()
+            .filter(|(_, (_, hosts))| hosts.contains(&rock))
+            .find(|(i, _)| {
+                // every ore samples its own region of the noise, so veins of different ores don't overlap
+                let ore_offset = 100. * (*i as f32 + 1.);
+                self.ore_noise.get_noise_3d((p.0 + ore_offset, p.1, p.2)) > thresholds[*i]
+            })
+            .map(|(_, (ore, _))| *ore)
+    }
+}
+
+/// Slopes rise towards the cube behind them, directions are tried in a fixed order.
+pub fn find_slope_direction<F: Fn([i32; 2]) -> bool>(pos: [i32; 2], is_cube: F) -> Option<Direction> {
+    SLOPE_DIRECTIONS.iter()
+        .find(|dir| {
+            let offset = DIRECTION_VECTOR[&dir.get_opposite()];
+            is_cube([pos[0] + offset[0], pos[1] + offset[1]])
+        })
+        .cloned()
+}
+
+/// The slope for an empty position next to cubes, used for generated layers and after fields changed.
+/// Returns None if there is no cube to lean against.
+pub fn create_slope_field<F: Fn([i32; 2]) -> bool>(pos: [i32; 2], material: FieldMaterial, is_cube: F) -> Option<Field> {
+    find_slope_direction(pos, is_cube).map(|dir| {
+        let mut field = Field::default();
+        field.set_type(FieldType::SLOPE(dir));
+        field.set_material(material);
+        field
+    })
+}
+
+fn get_noise_point(pos: [i32; 3], scale: f32) -> (f32, f32, f32) {
+    (pos[0] as f32 * scale, pos[1] as f32 * scale, pos[2] as f32 * scale * VERTICAL_STRETCH)
+}
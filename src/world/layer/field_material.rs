@@ -1,5 +1,11 @@
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum FieldMaterial {
     MUD,
-    GRASS
+    GRASS,
+    STONE,
+    SANDSTONE,
+    GRANITE,
+    COAL,
+    IRON,
+    GOLD
 }
@@ -1,6 +1,6 @@
 use world::Direction;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum FieldType {
     CUBE,
     SLOPE(Direction)
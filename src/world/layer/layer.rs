@@ -1,81 +1,70 @@
-use std::rc::Rc;
 use std::cmp::Ordering;
-use std::collections::{ HashMap, hash_map::Entry };
-use std::time;
+use std::collections::HashMap;
 
-use glm::Vector3;
+use super::{ Field, ChunkMap };
 
-use application::ApplicationError;
-
-use graphics::{ Mesh, MeshManager, Triangle, ShaderProgram, GraphicsError };
-use world::{ Direction, Camera, Object, Noise, WorldError, traits::Renderable, DIRECTION_VECTOR };
-use world::height_map::HeightMap;
-use utility::traits::{ Translatable, Rotatable, Scalable };
-use utility::Float;
-use super::{ Field, FieldType, create_mesh };
-
-type FieldMap = HashMap<[i32; 2], Field>;
+pub type FieldMap = HashMap<[i32; 2], Field>;
 
 pub struct Layer {
-    object: Object,
     level: i32,
     size: [i32; 2],
-    fields: FieldMap,
+    chunks: ChunkMap
 }
 
 impl Layer {
-    pub fn new(level: i32, size: [i32; 2], height_map: &HeightMap, mesh_manager: &MeshManager, camera_direction: Vector3<Float>) -> Result<Self, WorldError> {
+    pub fn new(level: i32, size: [i32; 2], chunks: ChunkMap) -> Self {
         trace!("Creating new layer, level = {}, size = {}x{}", level, size[0], size[1]);
         debug_assert!(size[0]>= 0 && size[1] >= 0);
 
-        let fields = create_default_field_map(level, size, height_map);
-        let mesh = create_mesh(&fields, mesh_manager, camera_direction)?;
-        trace!("Layer mesh vertex count: {}", mesh.get_vertex_count());
-        let mut object = Object::new(Rc::new(mesh));
-        object.set_translation(Vector3::new(0., 0., level as Float));
-        Ok(Self {
-            object: object,
+        Self {
             level: level,
             size: size,
-            fields: fields
-        })
+            chunks: chunks
+        }
     }
-}
 
-impl Renderable for Layer {
-    fn render(&self, camera: &Camera, shader: &ShaderProgram) -> Result<(), GraphicsError> {
-        self.object.render(camera, shader)
+    pub fn get_level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn get_chunks(&self) -> &ChunkMap {
+        &self.chunks
+    }
+
+    pub fn is_inside(&self, pos: [i32; 2]) -> bool {
+        pos[0] >= 0 && pos[1] >= 0 && pos[0] < self.size[0] && pos[1] < self.size[1]
     }
-}
 
-fn create_default_field_map(level: i32, size: [i32; 2], height_map: &HeightMap) -> FieldMap {
-    let mut fields = FieldMap::new();
-    for (pos, h) in height_map.iter() {
-        if level < *h {
-            fields.insert(*pos, Field::default());
+    /// Positions outside the layer have no field.
+    pub fn get_field(&self, pos: [i32; 2]) -> Option<&Field> {
+        if !self.is_inside(pos) {
+            return None;
         }
+        self.chunks.get_field(pos)
     }
-    if level >= 0 {
-        let mut slope_fields = FieldMap::new();
-        for (pos, _field) in fields.iter() {
-            for (dir, offset) in DIRECTION_VECTOR.iter().take(4) {
-                let nb_pos = [pos[0] + offset[0], pos[1] + offset[1]];
-                match fields.get(&nb_pos) {
-                    None if nb_pos[0] >= 0 && nb_pos[0] < size[0] && nb_pos[1] >= 0 && nb_pos[1] < size[1] => {
-                        let mut field = Field::default();
-                        field.set_type(FieldType::SLOPE(*dir));
-                        slope_fields.insert(nb_pos, field);
-                    },
-                    _ => {}
-                }
-            }
+
+    /// Positions outside the layer are ignored, so no chunks are created beyond its border.
+    pub fn set_field(&mut self, pos: [i32; 2], field: Option<Field>) {
+        if !self.is_inside(pos) {
+            warn!("Could not set field {}/{}/{}: outside of the layer", pos[0], pos[1], self.level);
+            return;
         }
-        fields.extend(slope_fields);
+        self.chunks.set_field(pos, field);
     }
-    fields
-}
 
+    pub fn has_dirty_chunks(&self) -> bool {
+        !self.chunks.get_dirty_chunk_indices().is_empty()
+    }
 
+    /// Returns the chunks changed since the last call, so they can be remeshed.
+    pub fn take_dirty_chunks(&mut self) -> Vec<[i32; 2]> {
+        let dirty_chunks = self.chunks.get_dirty_chunk_indices();
+        for chunk_index in dirty_chunks.iter() {
+            self.chunks.clear_dirty(*chunk_index);
+        }
+        dirty_chunks
+    }
+}
 
 impl Ord for Layer {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -95,8 +84,25 @@ impl PartialOrd for Layer {
 
 impl PartialEq for Layer {
     fn eq(&self, other: &Self) -> bool {
-        self == other
+        self.level == other.level
     }
 }
 
-impl Eq for Layer {}
\ No newline at end of file
+impl Eq for Layer {}
+#[cfg(test)]
+mod tests {
+    use world::layer::{ ChunkMap, Field };
+    use super::Layer;
+
+    #[test]
+    fn fields_outside_the_layer_are_ignored() {
+        let mut layer = Layer::new(0, [4, 4], ChunkMap::default());
+        layer.set_field([3, 3], Some(Field::default()));
+        layer.set_field([4, 0], Some(Field::default()));
+        layer.set_field([-1, 2], Some(Field::default()));
+        assert!(layer.get_field([3, 3]).is_some());
+        assert!(layer.get_field([4, 0]).is_none());
+        assert!(layer.get_field([-1, 2]).is_none());
+        assert_eq!(layer.get_chunks().get_field_count(), 1);
+    }
+}
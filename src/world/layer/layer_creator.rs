@@ -1,45 +1,197 @@
-use std::collections::{ VecDeque, BTreeSet };
-use glm::Vector3;
+use std::collections::BTreeSet;
+use std::sync::{ Arc, Mutex, Condvar, mpsc };
+use std::thread;
 
-use graphics::{ Mesh, MeshManager };
-use world::{ WorldError, height_map::HeightMap };
-use utility::Float;
-use super::Layer;
+use world::WorldError;
+use super::{ Layer, ChunkMap, FieldGenerator };
+
+const WORKER_COUNT: usize = 4;
 
 pub struct LayerCreator {
     layer_size: [i32; 2],
-    height_map: HeightMap,
-    mesh_manager: MeshManager,
-    camera_direction: Vector3<Float>,
-    request_queue: VecDeque<i32>
+    shared_state: Arc<SharedState>,
+    result_receiver: mpsc::Receiver<WorkerResult>,
+    workers: Vec<thread::JoinHandle<()>>
+}
+
+struct SharedState {
+    queue: Mutex<RequestQueue>,
+    request_available: Condvar
+}
+
+struct RequestQueue {
+    pending: BTreeSet<i32>,
+    in_progress: BTreeSet<i32>,
+    cancelled: BTreeSet<i32>,
+    focus_level: i32,
+    shutdown: bool
 }
 
-fn load_terrain_meshes() -> Result<MeshManager, WorldError> {
-    let mut mesh_manager = MeshManager::default();
-    mesh_manager.add_mesh(Mesh::from_obj("resources/obj/cube.obj")?, "cube");
-    mesh_manager.add_mesh(Mesh::from_obj("resources/obj/slope.obj")?, "slope");
-    Ok(mesh_manager)
+struct WorkerResult {
+    level: i32,
+    chunks: ChunkMap
 }
 
 impl LayerCreator {
-    pub fn new(layer_size: [i32; 2], height_map: HeightMap, camera_direction: Vector3<Float>) -> Result<Self, WorldError> {
+    pub fn new(generator: FieldGenerator) -> Result<Self, WorldError> {
+        let layer_size = generator.get_layer_size();
+        let generator = Arc::new(generator);
+        let shared_state = Arc::new(SharedState {
+            queue: Mutex::new(RequestQueue::default()),
+            request_available: Condvar::new()
+        });
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let mut workers = Vec::with_capacity(WORKER_COUNT);
+        for i in 0..WORKER_COUNT {
+            let generator = generator.clone();
+            let shared_state = shared_state.clone();
+            let result_sender = result_sender.clone();
+            let handle = thread::Builder::new()
+                .name(format!("layer worker {}", i))
+                .spawn(move || run_worker(generator, shared_state, result_sender))
+                .map_err(WorldError::WorkerCreation)?;
+            workers.push(handle);
+        }
+        debug!("Started {} layer workers", workers.len());
+
         let creator = Self {
             layer_size: layer_size,
-            height_map: height_map,
-            mesh_manager: load_terrain_meshes()?,
-            camera_direction: camera_direction,
-            request_queue: VecDeque::new()
+            shared_state: shared_state,
+            result_receiver: result_receiver,
+            workers: workers
         };
         Ok(creator)
     }
 
     pub fn request_layer(&mut self, level: i32) {
-        self.request_queue.push_back(level);
+        let mut queue = self.shared_state.lock_queue();
+        queue.cancelled.remove(&level);
+        if queue.pending.insert(level) {
+            trace!("Requested layer creation, level = {}", level);
+            self.shared_state.request_available.notify_one();
+        }
+    }
+
+    /// Removes a pending request. Layers already being built are discarded when finished,
+    /// layers already finished are not affected.
+    pub fn cancel_layer(&mut self, level: i32) {
+        let mut queue = self.shared_state.lock_queue();
+        if queue.pending.remove(&level) {
+            trace!("Cancelled pending layer creation, level = {}", level);
+        } else if queue.in_progress.contains(&level) {
+            trace!("Cancelled layer creation in progress, level = {}", level);
+            queue.cancelled.insert(level);
+        }
+    }
+
+    /// Pending requests are handled in order of their distance to the focus level.
+    pub fn set_focus_level(&mut self, level: i32) {
+        self.shared_state.lock_queue().focus_level = level;
     }
 
-    pub fn get_finished_layers(&mut self) -> BTreeSet<Layer> {
-        let finished_layers = BTreeSet::new();
+    pub fn get_pending_count(&self) -> usize {
+        let queue = self.shared_state.lock_queue();
+        queue.pending.len() + queue.in_progress.len()
+    }
 
+    pub fn get_finished_layers(&mut self) -> Vec<Layer> {
+        let mut finished_layers = Vec::new();
+        while let Ok(result) = self.result_receiver.try_recv() {
+            finished_layers.push(Layer::new(result.level, self.layer_size, result.chunks));
+        }
         finished_layers
     }
 }
+
+impl Drop for LayerCreator {
+    fn drop(&mut self) {
+        {
+            let mut queue = self.shared_state.lock_queue();
+            queue.shutdown = true;
+            queue.pending.clear();
+        }
+        self.shared_state.request_available.notify_all();
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                error!("Layer worker panicked");
+            }
+        }
+        debug!("Stopped layer workers");
+    }
+}
+
+impl SharedState {
+    fn lock_queue(&self) -> ::std::sync::MutexGuard<RequestQueue> {
+        match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner()
+        }
+    }
+
+    fn wait_for_request(&self) -> Option<i32> {
+        let mut queue = self.lock_queue();
+        loop {
+            if queue.shutdown {
+                return None;
+            }
+            if let Some(level) = queue.pop_next() {
+                return Some(level);
+            }
+            queue = match self.request_available.wait(queue) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner()
+            };
+        }
+    }
+
+    fn finish_request(&self, level: i32, sender: &mpsc::Sender<WorkerResult>, finished: WorkerResult) -> bool {
+        let mut queue = self.lock_queue();
+        queue.in_progress.remove(&level);
+        if queue.cancelled.remove(&level) {
+            trace!("Discarding cancelled layer, level = {}", level);
+            return true;
+        }
+        sender.send(finished).is_ok()
+    }
+}
+
+impl RequestQueue {
+    fn pop_next(&mut self) -> Option<i32> {
+        let focus_level = self.focus_level;
+        let next = self.pending.iter()
+            .cloned()
+            .min_by_key(|level| ((level - focus_level).abs(), -level));
+        if let Some(level) = next {
+            self.pending.remove(&level);
+            self.in_progress.insert(level);
+        }
+        next
+    }
+}
+
+impl Default for RequestQueue {
+    fn default() -> Self {
+        Self {
+            pending: BTreeSet::new(),
+            in_progress: BTreeSet::new(),
+            cancelled: BTreeSet::new(),
+            focus_level: 0,
+            shutdown: false
+        }
+    }
+}
+
+fn run_worker(generator: Arc<FieldGenerator>, shared_state: Arc<SharedState>, sender: mpsc::Sender<WorkerResult>) {
+    while let Some(level) = shared_state.wait_for_request() {
+        trace!("Creating layer, level = {}", level);
+        let fields = generator.create_field_map(level);
+        let finished = WorkerResult {
+            level: level,
+            chunks: ChunkMap::from_fields(fields)
+        };
+        if !shared_state.finish_request(level, &sender, finished) {
+            break;
+        }
+    }
+}
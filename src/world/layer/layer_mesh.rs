@@ -0,0 +1,74 @@
+use std::rc::Rc;
+use std::collections::BTreeMap;
+
+use glm::Vector3;
+
+use graphics::{ ShaderProgram, GraphicsError, BoundingBox, Frustum, mesh::Buffer };
+use world::{ Camera, Object, RenderStats, WorldError, traits::Renderable };
+use utility::traits::Translatable;
+use utility::Float;
+use super::upload_mesh;
+
+/// The uploaded chunk meshes of a layer.
+pub struct LayerMesh {
+    level: i32,
+    chunk_objects: BTreeMap<[i32; 2], Object>,
+    /// Bounds of all chunks in world space.
+    bounding_box: Option<BoundingBox>
+}
+
+impl LayerMesh {
+    pub fn new(level: i32) -> Self {
+        Self {
+            level: level,
+            chunk_objects: BTreeMap::new(),
+            bounding_box: None
+        }
+    }
+
+    /// Replaces the mesh of the chunk, empty buffers remove it.
+    pub fn set_chunk_mesh(&mut self, chunk_index: [i32; 2], buffer: Buffer) -> Result<(), WorldError> {
+        if buffer.is_empty() {
+            self.chunk_objects.remove(&chunk_index);
+        } else {
+            let mesh = upload_mesh(buffer)?;
+            let mut object = Object::new(Rc::new(mesh));
+            object.set_translation(Vector3::new(0., 0., self.level as Float));
+            self.chunk_objects.insert(chunk_index, object);
+        }
+        self.bounding_box = self.chunk_objects.values()
+            .filter_map(|o| o.get_bounding_box())
+            .fold(None, |bounds: Option<BoundingBox>, b| Some(bounds.map_or(b, |bounds| bounds.union(&b))));
+        Ok(())
+    }
+
+    pub fn get_chunk_count(&self) -> usize {
+        self.chunk_objects.len()
+    }
+
+    pub fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.bounding_box
+    }
+
+    /// Renders only the chunks inside the frustum, counting them as visible or culled.
+    pub fn render_visible(&self, camera: &Camera, shader: &ShaderProgram, frustum: &Frustum, stats: &mut RenderStats) -> Result<(), GraphicsError> {
+        for object in self.chunk_objects.values() {
+            if object.is_visible(frustum) {
+                object.render(camera, shader)?;
+                stats.visible_chunks += 1;
+            } else {
+                stats.culled_chunks += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Renderable for LayerMesh {
+    fn render(&self, camera: &Camera, shader: &ShaderProgram) -> Result<(), GraphicsError> {
+        for object in self.chunk_objects.values() {
+            object.render(camera, shader)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::sync::{ Arc, Mutex, mpsc };
+use std::thread;
+
+use graphics::mesh::Buffer;
+use world::WorldError;
+use super::{ ChunkMap, LayerMesher, AdjacentLayers, MeshStats };
+
+const WORKER_COUNT: usize = 2;
+
+/// Creates the chunk buffers of whole layers in the background, from copies of the layers
+/// and their neighbours taken by the renderer.
+pub struct LayerMeshWorker {
+    request_sender: Option<mpsc::Sender<MeshRequest>>,
+    result_receiver: mpsc::Receiver<MeshResult>,
+    /// Latest request of every level with a result outstanding, results of older requests are dropped.
+    pending: BTreeMap<i32, u64>,
+    next_id: u64,
+    workers: Vec<thread::JoinHandle<()>>
+}
+
+struct MeshRequest {
+    id: u64,
+    level: i32,
+    chunks: ChunkMap,
+    below: Option<ChunkMap>,
+    above: Option<ChunkMap>
+}
+
+struct MeshResult {
+    id: u64,
+    level: i32,
+    chunk_buffers: Vec<([i32; 2], Buffer)>,
+    stats: MeshStats
+}
+
+impl LayerMeshWorker {
+    pub fn new(mesher: Arc<LayerMesher>) -> Result<Self, WorldError> {
+        let (request_sender, request_receiver) = mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let mut workers = Vec::with_capacity(WORKER_COUNT);
+        for i in 0..WORKER_COUNT {
+            let mesher = mesher.clone();
+            let request_receiver = request_receiver.clone();
+            let result_sender = result_sender.clone();
+            let handle = thread::Builder::new()
+                .name(format!("mesh worker {}", i))
+                .spawn(move || run_worker(mesher, request_receiver, result_sender))
+                .map_err(WorldError::WorkerCreation)?;
+            workers.push(handle);
+        }
+        debug!("Started {} mesh workers", workers.len());
+
+        let worker = Self {
+            request_sender: Some(request_sender),
+            result_receiver: result_receiver,
+            pending: BTreeMap::new(),
+            next_id: 0,
+            workers: workers
+        };
+        Ok(worker)
+    }
+
+    /// Replaces any request of the level still in progress.
+    pub fn request_layer(&mut self, level: i32, chunks: ChunkMap, below: Option<ChunkMap>, above: Option<ChunkMap>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = MeshRequest {
+            id: id,
+            level: level,
+            chunks: chunks,
+            below: below,
+            above: above
+        };
+        match self.request_sender.as_ref().map(|sender| sender.send(request)) {
+            Some(Ok(_)) => {
+                trace!("Requested layer mesh, level = {}", level);
+                self.pending.insert(level, id);
+            },
+            _ => { error!("Could not request layer mesh, level = {}: mesh workers stopped", level); }
+        }
+    }
+
+    pub fn is_pending(&self, level: i32) -> bool {
+        self.pending.contains_key(&level)
+    }
+
+    pub fn get_pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the chunk buffers of the layers finished since the last call, by level.
+    pub fn get_finished_layers(&mut self) -> Vec<(i32, Vec<([i32; 2], Buffer)>)> {
+        let mut finished_layers = Vec::new();
+        while let Ok(result) = self.result_receiver.try_recv() {
+            if self.pending.get(&result.level) != Some(&result.id) {
+                trace!("Discarding outdated layer mesh, level = {}", result.level);
+                continue;
+            }
+            self.pending.remove(&result.level);
+            debug!("Layer mesh stats, level = {}: {}", result.level, result.stats);
+            finished_layers.push((result.level, result.chunk_buffers));
+        }
+        finished_layers
+    }
+}
+
+impl Drop for LayerMeshWorker {
+    fn drop(&mut self) {
+        // workers stop once the channel is closed and the requests left are done
+        self.request_sender = None;
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                error!("Mesh worker panicked");
+            }
+        }
+        debug!("Stopped mesh workers");
+    }
+}
+
+fn run_worker(mesher: Arc<LayerMesher>, receiver: Arc<Mutex<mpsc::Receiver<MeshRequest>>>, sender: mpsc::Sender<MeshResult>) {
+    loop {
+        let request = {
+            let receiver = match receiver.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner()
+            };
+            match receiver.recv() {
+                Ok(request) => request,
+                Err(_) => break
+            }
+        };
+        trace!("Meshing layer, level = {}", request.level);
+        let adjacent = AdjacentLayers::new(request.below.as_ref(), request.above.as_ref());
+        let (chunk_buffers, stats) = mesher.create_layer_buffers(&request.chunks, adjacent);
+        let result = MeshResult {
+            id: request.id,
+            level: request.level,
+            chunk_buffers: chunk_buffers,
+            stats: stats
+        };
+        if sender.send(result).is_err() {
+            break;
+        }
+    }
+}
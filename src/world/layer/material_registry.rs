@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use world::Direction;
+use super::FieldMaterial;
+
+/// Texture array layers used for the faces of a material.
+#[derive(Copy, Clone)]
+pub struct MaterialTextures {
+    top: u32,
+    side: u32,
+    bottom: u32
+}
+
+/// Maps field materials to their texture array layers.
+/// Materials without an entry are rendered with the fallback textures.
+pub struct MaterialRegistry {
+    materials: HashMap<FieldMaterial, MaterialTextures>,
+    fallback: MaterialTextures
+}
+
+impl MaterialTextures {
+    pub fn new(top: u32, side: u32, bottom: u32) -> Self {
+        Self {
+            top: top,
+            side: side,
+            bottom: bottom
+        }
+    }
+
+    pub fn uniform(layer: u32) -> Self {
+        Self::new(layer, layer, layer)
+    }
+
+    pub fn get_top(&self) -> u32 {
+        self.top
+    }
+    pub fn get_side(&self) -> u32 {
+        self.side
+    }
+    pub fn get_bottom(&self) -> u32 {
+        self.bottom
+    }
+
+    pub fn get_layer(&self, dir: Direction) -> u32 {
+        match dir {
+            Direction::Up => self.top,
+            Direction::Down => self.bottom,
+            _ => self.side
+        }
+    }
+}
+
+impl MaterialRegistry {
+    pub fn register(&mut self, material: FieldMaterial, textures: MaterialTextures) {
+        if self.materials.insert(material, textures).is_some() {
+            warn!("Replaced textures of material {:?}", material);
+        }
+    }
+
+    pub fn set_fallback(&mut self, textures: MaterialTextures) {
+        self.fallback = textures;
+    }
+
+    pub fn get_textures(&self, material: FieldMaterial) -> &MaterialTextures {
+        match self.materials.get(&material) {
+            Some(textures) => textures,
+            None => &self.fallback
+        }
+    }
+
+    pub fn get_layer(&self, material: FieldMaterial, dir: Direction) -> u32 {
+        self.get_textures(material).get_layer(dir)
+    }
+}
+
+impl Default for MaterialRegistry {
+    fn default() -> Self {
+        Self {
+            materials: HashMap::new(),
+            fallback: MaterialTextures::uniform(0)
+        }
+    }
+}
@@ -1,144 +1,693 @@
-use std::collections::{ HashMap, BTreeSet };
 use std::convert::TryFrom;
-use std::cmp::Ordering;
-use std::ops::{ Sub };
 use std::time;
+use std::fmt;
 
-use glm::{ Vector3, GenNum, builtin::{ dot, normalize } };
+use glm::{ Vector3, builtin::dot };
 
-use utility::{ Float, cmp_vec, traits::{ Translatable, Rotatable, Scalable } };
-use graphics::{  GraphicsError, mesh::{ Buffer, Vertex, VAO, MeshError, Node, Mesh, MeshManager, Triangle } };
-use world::{ WorldError, Direction, DIRECTION_VECTOR };
-use super::{ Field, FieldType, FieldMaterial };
+use utility::{ Float, FileError, read_obj, traits::{ Translatable, Rotatable } };
+use graphics::mesh::{ Buffer, Vertex, VAO, MeshError, Node, Mesh, Triangle };
+use world::{ Direction, DIRECTION_VECTOR };
+use super::{ Field, FieldType, FieldMaterial, ChunkMap, MaterialRegistry };
+use super::chunk::get_chunk_area;
 
-pub fn create_mesh(fields: &HashMap<[i32; 2], Field>, mesh_manager: &MeshManager, camera_direction: Vector3<Float>) -> Result<Mesh, MeshError> {
-    let start_time = time::Instant::now();
+const FACE_DIRECTIONS: [Direction; 6] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::Up,
+    Direction::Down
+];
 
-    let mut mesh = Mesh::default();
-    for (pos, field) in fields {
-        let mut node = Node::default();
-
-        node.set_translation(Vector3::new(pos[0] as Float, pos[1] as Float, 0.));
-
-        let triangles = match field.get_type() {
-            FieldType::CUBE => mesh_manager.get_mesh("cube")?.copy_triangles(),
-            FieldType::SLOPE(dir) => {
-                match dir {
-                    Direction::North => { node.set_rotation(Vector3::new(0., 0., 180f32.to_radians())) },
-                    Direction::East => { node.set_rotation(Vector3::new(0., 0., 270f32.to_radians())) },
-                    Direction::South => { },
-                    Direction::West => { node.set_rotation(Vector3::new(0., 0., 90f32.to_radians()))},
-                    _ => { warn!("Slope with invalid direction supplied to mesh_create"); }
-                }
-                mesh_manager.get_mesh("slope")?.copy_triangles()
-            }
-        };
+/// Tangent axis signs of the four corners of a face, in the order the occlusion of a face is stored.
+const CORNER_SIGNS: [[i32; 2]; 4] = [[-1, -1], [1, -1], [1, 1], [-1, 1]];
+/// Occlusion a vertex gets per blocking neighbour, three of them block all ambient light.
+const OCCLUSION_PER_NEIGHBOUR: Float = 1. / 3.;
 
-        node.add_triangles(triangles);
-        mesh.add_node(node);
+pub struct FieldTemplates {
+    slope: Vec<Triangle>
+}
+
+impl FieldTemplates {
+    pub fn new(slope: Vec<Triangle>) -> Self {
+        Self {
+            slope: slope
+        }
     }
 
-    let triangles = mesh.copy_triangles();
-    trace!("Unfilteded triangle count = {}", triangles.len());
-    let filtered_triangles = remove_incident_triangles(triangles);
-    trace!("After incident removal = {}", filtered_triangles.len());
-    let filtered_triangles = remove_triangles_by_direction(filtered_triangles, camera_direction);
-    trace!("After directional removal = {}", filtered_triangles.len());
-    let buffer = Buffer::from(filtered_triangles);
-    if !buffer.is_empty() {
-        let vao = VAO::try_from(buffer)?;
-        mesh.set_vao(vao);
+    pub fn from_obj(slope_path: &str) -> Result<Self, FileError> {
+        Ok(Self::new(read_obj(slope_path)?))
     }
+}
 
-    let creation_time = start_time.elapsed().as_secs() as u32 * 1000 + start_time.elapsed().subsec_millis();
-    debug!("Layer mesh stats: vertices = {}, creation time = {}ms", mesh.get_vertex_count(), creation_time);
+pub struct MeshStats {
+    field_count: usize,
+    unmerged_triangles: usize,
+    triangles: usize,
+    vertex_count: usize,
+    index_count: usize,
+    creation_time: time::Duration
+}
 
-    Ok(mesh)
+impl MeshStats {
+    pub fn get_field_count(&self) -> usize {
+        self.field_count
+    }
+    pub fn get_unmerged_triangle_count(&self) -> usize {
+        self.unmerged_triangles
+    }
+    pub fn get_triangle_count(&self) -> usize {
+        self.triangles
+    }
+    pub fn get_vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+    pub fn get_index_count(&self) -> usize {
+        self.index_count
+    }
+    pub fn get_creation_time(&self) -> time::Duration {
+        self.creation_time
+    }
+
+    pub fn accumulate(&mut self, other: &MeshStats) {
+        self.field_count += other.field_count;
+        self.unmerged_triangles += other.unmerged_triangles;
+        self.triangles += other.triangles;
+        self.vertex_count += other.vertex_count;
+        self.index_count += other.index_count;
+        self.creation_time += other.creation_time;
+    }
 }
 
+impl Default for MeshStats {
+    fn default() -> Self {
+        Self {
+            field_count: 0,
+            unmerged_triangles: 0,
+            triangles: 0,
+            vertex_count: 0,
+            index_count: 0,
+            creation_time: time::Duration::from_millis(0)
+        }
+    }
+}
 
-fn remove_incident_triangles(triangles: Vec<Triangle>) -> Vec<Triangle> {
-    let mut triangle_set: BTreeSet<TriangleEntry> = BTreeSet::new();
-    for t in triangles.into_iter() {
-        let new_entry = TriangleEntry::new(t);
-        let updated_entry = match triangle_set.take(&new_entry) {
-            Some(mut existing_entry) => {
-                existing_entry.set_invisible();
-                existing_entry
-            },
-            None => new_entry
-        };
-        triangle_set.insert(updated_entry);
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let creation_time = self.creation_time.as_secs() as u32 * 1000 + self.creation_time.subsec_millis();
+        write!(f, "fields = {}, triangles = {} ({} before merging), vertices = {}, indices = {}, creation time = {}ms",
+            self.field_count,
+            self.triangles, self.unmerged_triangles,
+            self.vertex_count, self.index_count,
+            creation_time)
     }
+}
 
-    let mut visible_triangles = Vec::new();
-    for entry in triangle_set {
-        if let Some(t) = entry.into_triangle() {
-            visible_triangles.push(t);
+/// The layers below and above the meshed one, their fields occlude the vertices next to them.
+/// Missing layers block no light.
+#[derive(Copy, Clone, Default)]
+pub struct AdjacentLayers<'a> {
+    below: Option<&'a ChunkMap>,
+    above: Option<&'a ChunkMap>
+}
+
+impl<'a> AdjacentLayers<'a> {
+    pub fn new(below: Option<&'a ChunkMap>, above: Option<&'a ChunkMap>) -> Self {
+        Self {
+            below: below,
+            above: above
         }
     }
-    visible_triangles
 }
 
-fn remove_triangles_by_direction(triangles: Vec<Triangle>, dir_vec: Vector3<Float>) -> Vec<Triangle> {
-    triangles.into_iter().filter(|t| dot(t.get_normal(), dir_vec) <= 0.).collect()
+#[derive(Copy, Clone, PartialEq)]
+enum FaceShape {
+    Empty,
+    Full,
+    SlopeSide(Direction)
 }
 
-struct TriangleEntry {
-    triangle: Triangle,
-    sorted_vertices: [Vertex; 3],
-    visible: bool
+/// Faces are only merged if they look the same, which includes the occlusion of their corners.
+#[derive(Copy, Clone, PartialEq)]
+struct FaceKey {
+    material: FieldMaterial,
+    occlusion: [u8; 4]
 }
 
-impl TriangleEntry {
-    pub fn new(triangle: Triangle) -> Self {
-        let sorted_vertices = triangle.get_sorted_vertices();
+struct FaceRect {
+    origin: [usize; 2],
+    size: [usize; 2],
+    key: FaceKey
+}
+
+impl FaceKey {
+    /// Faces with differently occluded corners are shaded across their area, so they can't be merged
+    /// without the shading being stretched over all of the merged faces.
+    fn is_uniform(&self) -> bool {
+        self.occlusion.iter().all(|o| *o == self.occlusion[0])
+    }
+}
+
+/// Creates the visible triangles of the fields inside the given area, without needing an opengl context.
+/// Faces covered by a neighbouring field are removed, as are faces pointing away from the camera.
+/// Visible cube faces of the same material are merged into larger quads, with the texture tiled across them.
+/// Texture layers are taken from the material registry, depending on the material and face direction.
+/// Fields bordering the area, also in the adjacent layers, are only used for removing covered faces and for the
+/// ambient occlusion of cube vertices.
+pub fn create_triangles(chunks: &ChunkMap, adjacent: AdjacentLayers, area: [[i32; 2]; 2], templates: &FieldTemplates, materials: &MaterialRegistry, camera_direction: Vector3<Float>) -> (Vec<Triangle>, MeshStats) {
+    let start_time = time::Instant::now();
+    let mut stats = MeshStats::default();
+
+    let mut triangles = Vec::new();
+    for dir in FACE_DIRECTIONS.iter().filter(|d| faces_camera(**d, camera_direction)) {
+        triangles.extend(create_cube_faces(chunks, adjacent, area, *dir, materials, &mut stats));
+    }
+    for y in area[0][1]..area[1][1] {
+        for x in area[0][0]..area[1][0] {
+            if let Some(field) = chunks.get_field([x, y]) {
+                stats.field_count += 1;
+                if let FieldType::SLOPE(_) = field.get_type() {
+                    let slope_triangles = create_slope_triangles(chunks, [x, y], field, templates, materials, camera_direction);
+                    stats.unmerged_triangles += slope_triangles.len();
+                    triangles.extend(slope_triangles);
+                }
+            }
+        }
+    }
+    stats.triangles = triangles.len();
+    stats.creation_time = start_time.elapsed();
+
+    (triangles, stats)
+}
+
+/// Creates the vertex buffer of the fields inside the given area, ready to be uploaded by `upload_mesh`.
+pub fn create_buffer(chunks: &ChunkMap, adjacent: AdjacentLayers, area: [[i32; 2]; 2], templates: &FieldTemplates, materials: &MaterialRegistry, camera_direction: Vector3<Float>) -> (Buffer, MeshStats) {
+    let start_time = time::Instant::now();
+    let (triangles, mut stats) = create_triangles(chunks, adjacent, area, templates, materials, camera_direction);
+    let buffer = Buffer::from(triangles);
+    stats.vertex_count = buffer.get_vertex_count();
+    stats.index_count = buffer.get_index_count();
+    stats.creation_time = start_time.elapsed();
+    (buffer, stats)
+}
+
+/// The meshing setup shared between the layer workers and the renderer.
+pub struct LayerMesher {
+    templates: FieldTemplates,
+    materials: MaterialRegistry,
+    camera_direction: Vector3<Float>
+}
+
+impl LayerMesher {
+    pub fn new(templates: FieldTemplates, materials: MaterialRegistry, camera_direction: Vector3<Float>) -> Self {
         Self {
-            triangle: triangle,
-            sorted_vertices: sorted_vertices,
-            visible: true
+            templates: templates,
+            materials: materials,
+            camera_direction: camera_direction
         }
     }
-    pub fn set_invisible(&mut self) {
-        self.visible = false;
+
+    pub fn create_chunk_buffer(&self, chunks: &ChunkMap, adjacent: AdjacentLayers, chunk_index: [i32; 2]) -> (Buffer, MeshStats) {
+        create_buffer(chunks, adjacent, get_chunk_area(chunk_index), &self.templates, &self.materials, self.camera_direction)
+    }
+
+    pub fn create_layer_buffers(&self, chunks: &ChunkMap, adjacent: AdjacentLayers) -> (Vec<([i32; 2], Buffer)>, MeshStats) {
+        let mut stats = MeshStats::default();
+        let mut chunk_buffers = Vec::new();
+        for chunk_index in chunks.get_chunk_indices() {
+            let (buffer, chunk_stats) = self.create_chunk_buffer(chunks, adjacent, chunk_index);
+            stats.accumulate(&chunk_stats);
+            chunk_buffers.push((chunk_index, buffer));
+        }
+        (chunk_buffers, stats)
+    }
+}
+
+pub fn upload_mesh(buffer: Buffer) -> Result<Mesh, MeshError> {
+    let mut mesh = Mesh::default();
+    mesh.set_bounding_box(buffer.get_bounding_box());
+    if !buffer.is_empty() {
+        let vao = VAO::try_from(buffer)?;
+        mesh.set_vao(vao);
+    }
+    Ok(mesh)
+}
+
+fn faces_camera(dir: Direction, camera_direction: Vector3<Float>) -> bool {
+    dot(get_direction_normal(dir), camera_direction) <= 0.
+}
+
+fn get_direction_normal(dir: Direction) -> Vector3<Float> {
+    let offset = DIRECTION_VECTOR[&dir];
+    Vector3::new(offset[0] as Float, offset[1] as Float, offset[2] as Float)
+}
+
+fn get_face_shape(field: Option<&Field>, dir: Direction) -> FaceShape {
+    match field.map(|f| f.get_type()) {
+        None => FaceShape::Empty,
+        Some(FieldType::CUBE) => FaceShape::Full,
+        Some(FieldType::SLOPE(slope_dir)) => match dir {
+            Direction::Down => FaceShape::Full,
+            Direction::Up => FaceShape::Empty,
+            d if d == slope_dir => FaceShape::Empty,
+            d if d == slope_dir.get_opposite() => FaceShape::Full,
+            _ => FaceShape::SlopeSide(slope_dir)
+        }
     }
-    pub fn get_triangle(&self) -> &Triangle {
-        &self.triangle
+}
+
+/// Layers are meshed on their own, so only horizontal neighbours can cover a face.
+fn is_face_covered(chunks: &ChunkMap, pos: [i32; 2], field: &Field, dir: Direction) -> bool {
+    match dir {
+        Direction::Up | Direction::Down => false,
+        _ => {
+            let offset = DIRECTION_VECTOR[&dir];
+            let neighbour = chunks.get_field([pos[0] + offset[0], pos[1] + offset[1]]);
+            match (get_face_shape(Some(field), dir), get_face_shape(neighbour, dir.get_opposite())) {
+                (_, FaceShape::Full) => true,
+                (FaceShape::SlopeSide(lhs), FaceShape::SlopeSide(rhs)) => lhs == rhs,
+                _ => false
+            }
+        }
     }
-    pub fn into_triangle(self) -> Option<Triangle> {
-        if self.visible {
-            Some(self.triangle)
-        } else {
-            None
+}
+
+fn create_cube_faces(chunks: &ChunkMap, adjacent: AdjacentLayers, area: [[i32; 2]; 2], dir: Direction, materials: &MaterialRegistry, stats: &mut MeshStats) -> Vec<Triangle> {
+    let area_size = [(area[1][0] - area[0][0]) as usize, (area[1][1] - area[0][1]) as usize];
+    let (slice_count, mask_size) = match dir {
+        Direction::Up | Direction::Down => (1, area_size),
+        Direction::East | Direction::West => (area_size[0], [area_size[1], 1]),
+        Direction::North | Direction::South => (area_size[1], [area_size[0], 1])
+    };
+    let to_pos = |slice: usize, a: usize, b: usize| -> [i32; 2] {
+        match dir {
+            Direction::Up | Direction::Down => [area[0][0] + a as i32, area[0][1] + b as i32],
+            Direction::East | Direction::West => [area[0][0] + slice as i32, area[0][1] + a as i32],
+            Direction::North | Direction::South => [area[0][0] + a as i32, area[0][1] + slice as i32]
+        }
+    };
+
+    let mut triangles = Vec::new();
+    let mut mask: Vec<Option<FaceKey>> = vec![None; mask_size[0] * mask_size[1]];
+    for slice in 0..slice_count {
+        for b in 0..mask_size[1] {
+            for a in 0..mask_size[0] {
+                let pos = to_pos(slice, a, b);
+                mask[b * mask_size[0] + a] = match chunks.get_field(pos) {
+                    Some(field) => match field.get_type() {
+                        FieldType::CUBE if !is_face_covered(chunks, pos, field, dir) => {
+                            stats.unmerged_triangles += 2;
+                            Some(FaceKey {
+                                material: field.get_material(),
+                                occlusion: get_face_occlusion(chunks, adjacent, pos, dir)
+                            })
+                        },
+                        _ => None
+                    },
+                    None => None
+                };
+            }
+        }
+        for rect in merge_faces(&mut mask, mask_size) {
+            let first = to_pos(slice, rect.origin[0], rect.origin[1]);
+            let last = to_pos(slice, rect.origin[0] + rect.size[0] - 1, rect.origin[1] + rect.size[1] - 1);
+            let min = Vector3::new(first[0].min(last[0]) as Float - 0.5, first[1].min(last[1]) as Float - 0.5, -0.5);
+            let max = Vector3::new(first[0].max(last[0]) as Float + 0.5, first[1].max(last[1]) as Float + 0.5, 0.5);
+            let center = (min + max) * 0.5;
+            let occlusion = rect.key.occlusion;
+            let occlusion_at = |corner: Vector3<Float>| {
+                let axes = get_tangent_axes(dir);
+                let sign = |axis: usize| if corner[axis] > center[axis] { 1 } else { -1 };
+                let signs = [sign(axes[0]), sign(axes[1])];
+                match CORNER_SIGNS.iter().position(|s| *s == signs) {
+                    Some(i) => occlusion[i] as Float * OCCLUSION_PER_NEIGHBOUR,
+                    None => 0.
+                }
+            };
+            triangles.extend(create_box_face(min, max, dir, materials.get_layer(rect.key.material, dir), occlusion_at).iter());
         }
     }
+    triangles
 }
 
-impl Ord for TriangleEntry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        let iter = self.sorted_vertices.iter()
-        .zip(other.sorted_vertices.iter());
-        for (lhs, rhs) in iter {
-            let result = cmp_vec(&lhs.get_pos(), &rhs.get_pos());
-            if result != Ordering::Equal {
-                return result;
+/// Greedily merges equal neighbouring mask entries into rectangles, consuming the mask.
+/// Entries with unevenly occluded corners are never merged.
+fn merge_faces(mask: &mut [Option<FaceKey>], size: [usize; 2]) -> Vec<FaceRect> {
+    let mut rects = Vec::new();
+    for b in 0..size[1] {
+        let mut a = 0;
+        while a < size[0] {
+            let key = match mask[b * size[0] + a] {
+                Some(key) => key,
+                None => {
+                    a += 1;
+                    continue;
+                }
+            };
+            let mergeable = key.is_uniform();
+            let mut width = 1;
+            while mergeable && a + width < size[0] && mask[b * size[0] + a + width] == Some(key) {
+                width += 1;
+            }
+            let mut height = 1;
+            while mergeable && b + height < size[1] &&
+                  (a..a + width).all(|i| mask[(b + height) * size[0] + i] == Some(key)) {
+                height += 1;
             }
+            for j in b..b + height {
+                for i in a..a + width {
+                    mask[j * size[0] + i] = None;
+                }
+            }
+            rects.push(FaceRect {
+                origin: [a, b],
+                size: [width, height],
+                key: key
+            });
+            a += width;
         }
-        Ordering::Equal
     }
+    rects
+}
+
+/// The two axes along a face pointing into the given direction.
+fn get_tangent_axes(dir: Direction) -> [usize; 2] {
+    match dir {
+        Direction::Up | Direction::Down => [0, 1],
+        Direction::East | Direction::West => [1, 2],
+        Direction::North | Direction::South => [0, 2]
+    }
+}
+
+/// Fields are looked up relative to the meshed layer, a z of 1 is the layer above.
+fn is_occluder(chunks: &ChunkMap, adjacent: AdjacentLayers, pos: [i32; 3]) -> bool {
+    let layer = match pos[2] {
+        -1 => adjacent.below,
+        0 => Some(chunks),
+        1 => adjacent.above,
+        _ => None
+    };
+    layer.and_then(|l| l.get_field([pos[0], pos[1]])).is_some()
 }
 
-impl PartialOrd for TriangleEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// Occluding neighbours of the four corners of the cube face at the position, in the order of `CORNER_SIGNS`.
+/// A corner is blocked by the fields in front of the face touching it: the two sharing an edge with it and the diagonal one.
+/// If both edge neighbours are there, the corner counts as fully blocked, whether the diagonal field is there or not.
+fn get_face_occlusion(chunks: &ChunkMap, adjacent: AdjacentLayers, pos: [i32; 2], dir: Direction) -> [u8; 4] {
+    let normal = DIRECTION_VECTOR[&dir];
+    let front = [pos[0] + normal[0], pos[1] + normal[1], normal[2]];
+    let axes = get_tangent_axes(dir);
+    let mut occlusion = [0; 4];
+    for (corner, signs) in CORNER_SIGNS.iter().enumerate() {
+        let neighbour = |offsets: [i32; 2]| {
+            let mut neighbour_pos = front;
+            neighbour_pos[axes[0]] += offsets[0];
+            neighbour_pos[axes[1]] += offsets[1];
+            is_occluder(chunks, adjacent, neighbour_pos)
+        };
+        let side_a = neighbour([signs[0], 0]);
+        let side_b = neighbour([0, signs[1]]);
+        occlusion[corner] = match (side_a, side_b) {
+            (true, true) => 3,
+            _ => side_a as u8 + side_b as u8 + neighbour(*signs) as u8
+        };
+    }
+    occlusion
+}
+
+/// Creates the face of a box pointing into the given direction, counter clockwise from the outside.
+/// Uvs are given in field units, so the texture repeats once per field. The quad is split along the
+/// less occluded diagonal, so the occlusion is interpolated the same way regardless of the face orientation.
+fn create_box_face<F: Fn(Vector3<Float>) -> Float>(min: Vector3<Float>, max: Vector3<Float>, dir: Direction, uv_layer: u32, occlusion_at: F) -> [Triangle; 2] {
+    let (corners, uvs): ([[Float; 3]; 4], [[Float; 2]; 4]) = match dir {
+        Direction::Up => (
+            [[min.x, min.y, max.z], [max.x, min.y, max.z], [max.x, max.y, max.z], [min.x, max.y, max.z]],
+            [[0., 0.], [max.x - min.x, 0.], [max.x - min.x, max.y - min.y], [0., max.y - min.y]]
+        ),
+        Direction::Down => (
+            [[min.x, min.y, min.z], [min.x, max.y, min.z], [max.x, max.y, min.z], [max.x, min.y, min.z]],
+            [[0., 0.], [0., max.y - min.y], [max.x - min.x, max.y - min.y], [max.x - min.x, 0.]]
+        ),
+        Direction::East => (
+            [[max.x, min.y, min.z], [max.x, max.y, min.z], [max.x, max.y, max.z], [max.x, min.y, max.z]],
+            [[0., 0.], [max.y - min.y, 0.], [max.y - min.y, max.z - min.z], [0., max.z - min.z]]
+        ),
+        Direction::West => (
+            [[min.x, min.y, min.z], [min.x, min.y, max.z], [min.x, max.y, max.z], [min.x, max.y, min.z]],
+            [[max.y - min.y, 0.], [max.y - min.y, max.z - min.z], [0., max.z - min.z], [0., 0.]]
+        ),
+        Direction::South => (
+            [[min.x, max.y, min.z], [min.x, max.y, max.z], [max.x, max.y, max.z], [max.x, max.y, min.z]],
+            [[max.x - min.x, 0.], [max.x - min.x, max.z - min.z], [0., max.z - min.z], [0., 0.]]
+        ),
+        Direction::North => (
+            [[min.x, min.y, min.z], [max.x, min.y, min.z], [max.x, min.y, max.z], [min.x, min.y, max.z]],
+            [[0., 0.], [max.x - min.x, 0.], [max.x - min.x, max.z - min.z], [0., max.z - min.z]]
+        )
+    };
+    let normal = get_direction_normal(dir);
+    let mut vertices = [Vertex::default(); 4];
+    for (i, vertex) in vertices.iter_mut().enumerate() {
+        vertex.set_pos(Vector3::new(corners[i][0], corners[i][1], corners[i][2]));
+        vertex.set_uv(Vector3::new(uvs[i][0], uvs[i][1], uv_layer as Float));
+        vertex.set_normal(normal);
+        vertex.set_occlusion(occlusion_at(vertex.get_pos()));
+    }
+    let occlusion = |i: usize| vertices[i].get_occlusion();
+    if occlusion(0) + occlusion(2) > occlusion(1) + occlusion(3) {
+        [Triangle::new([vertices[0], vertices[1], vertices[3]]),
+         Triangle::new([vertices[1], vertices[2], vertices[3]])]
+    } else {
+        [Triangle::new([vertices[0], vertices[1], vertices[2]]),
+         Triangle::new([vertices[0], vertices[2], vertices[3]])]
     }
 }
 
-impl PartialEq for TriangleEntry {
-    fn eq(&self, other: &Self) -> bool {
-        self == other
+fn create_slope_triangles(chunks: &ChunkMap, pos: [i32; 2], field: &Field, templates: &FieldTemplates, materials: &MaterialRegistry, camera_direction: Vector3<Float>) -> Vec<Triangle> {
+    let mut node = Node::default();
+    node.set_translation(Vector3::new(pos[0] as Float, pos[1] as Float, 0.));
+    match field.get_type() {
+        FieldType::SLOPE(Direction::North) => { node.set_rotation(Vector3::new(0., 0., 180f32.to_radians())) },
+        FieldType::SLOPE(Direction::East) => { node.set_rotation(Vector3::new(0., 0., 270f32.to_radians())) },
+        FieldType::SLOPE(Direction::South) => { },
+        FieldType::SLOPE(Direction::West) => { node.set_rotation(Vector3::new(0., 0., 90f32.to_radians()))},
+        _ => { warn!("Slope with invalid direction supplied to create_slope_triangles"); }
     }
+    node.add_triangles(templates.slope.clone());
+
+    let textures = materials.get_textures(field.get_material());
+    node.create_transformed_triangles()
+        .into_iter()
+        .filter(|t| dot(t.get_normal(), camera_direction) <= 0.)
+        .filter(|t| {
+            let normal = t.get_normal();
+            match FACE_DIRECTIONS.iter().find(|d| dot(normal, get_direction_normal(**d)) > 0.99) {
+                Some(dir) => !is_face_covered(chunks, pos, field, *dir),
+                None => true
+            }
+        })
+        .map(|mut t| {
+            // the slanted face counts as top face, so grass slopes look like grass
+            let normal_z = t.get_normal().z;
+            if normal_z > 0.01 {
+                t.set_uv_layer(textures.get_top());
+            } else if normal_z < -0.99 {
+                t.set_uv_layer(textures.get_bottom());
+            } else {
+                t.set_uv_layer(textures.get_side());
+            }
+            t
+        })
+        .collect()
 }
 
-impl Eq for TriangleEntry {}
+#[cfg(test)]
+mod tests {
+    use glm::{ Vector3, builtin::{ dot, cross, length } };
+
+    use utility::Float;
+    use graphics::mesh::{ Vertex, Triangle };
+    use world::{ Direction, layer::{ Field, FieldType, ChunkMap, MaterialRegistry } };
+    use super::{ create_triangles, get_direction_normal, AdjacentLayers, FieldTemplates };
+    use super::super::chunk::get_chunk_area;
+
+    /// Looking along no direction, so no face points away from the camera.
+    const NO_CAMERA: [Float; 3] = [0., 0., 0.];
+    const SIDES: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
 
+    /// The slope of resources/obj/slope.obj, rising towards the north.
+    fn create_slope_template() -> Vec<Triangle> {
+        let p = [[0.5, -0.5, -0.5], [-0.5, -0.5, -0.5], [-0.5, 0.5, -0.5], [0.5, 0.5, -0.5], [0.5, -0.5, 0.5], [-0.5, -0.5, 0.5]];
+        let slant = (0.5 as Float).sqrt();
+        let faces: [([usize; 3], [Float; 3]); 8] = [
+            ([2, 0, 1], [0., 0., -1.]),
+            ([2, 3, 0], [0., 0., -1.]),
+            ([5, 3, 2], [0., slant, slant]),
+            ([5, 4, 3], [0., slant, slant]),
+            ([3, 4, 0], [1., 0., 0.]),
+            ([4, 1, 0], [0., -1., 0.]),
+            ([4, 5, 1], [0., -1., 0.]),
+            ([2, 1, 5], [-1., 0., 0.])
+        ];
+        faces.iter().map(|(indices, normal)| {
+            let mut vertices = [Vertex::default(); 3];
+            for (vertex, index) in vertices.iter_mut().zip(indices.iter()) {
+                let pos = p[*index];
+                vertex.set_pos(Vector3::new(pos[0], pos[1], pos[2]));
+                vertex.set_normal(Vector3::new(normal[0], normal[1], normal[2]));
+            }
+            Triangle::new(vertices)
+        }).collect()
+    }
+
+    fn create_field(field_type: FieldType) -> Field {
+        let mut field = Field::default();
+        field.set_type(field_type);
+        field
+    }
+
+    fn create_chunks(fields: &[([i32; 2], FieldType)]) -> ChunkMap {
+        let mut chunks = ChunkMap::default();
+        for (pos, field_type) in fields.iter() {
+            chunks.set_field(*pos, Some(create_field(*field_type)));
+        }
+        chunks
+    }
+
+    fn mesh(chunks: &ChunkMap, adjacent: AdjacentLayers, area: [[i32; 2]; 2], camera: [Float; 3]) -> Vec<Triangle> {
+        let templates = FieldTemplates::new(create_slope_template());
+        let camera_direction = Vector3::new(camera[0], camera[1], camera[2]);
+        create_triangles(chunks, adjacent, area, &templates, &MaterialRegistry::default(), camera_direction).0
+    }
+
+    fn get_area(triangle: &Triangle) -> Float {
+        let v = triangle.as_vertices();
+        length(cross(v[1].get_pos() - v[0].get_pos(), v[2].get_pos() - v[0].get_pos())) / 2.
+    }
+
+    fn points_to(triangle: &Triangle, dir: Direction) -> bool {
+        dot(triangle.get_normal(), get_direction_normal(dir)) > 0.99
+    }
+
+    /// Area of all faces pointing into the direction, merged faces included.
+    fn get_face_area(triangles: &[Triangle], dir: Direction) -> Float {
+        triangles.iter()
+            .filter(|t| points_to(t, dir))
+            .map(get_area)
+            .sum()
+    }
+
+    /// Area of the faces of a single field pointing into the direction, for fields whose faces are not merged.
+    fn get_field_face_area(triangles: &[Triangle], pos: [i32; 2], dir: Direction) -> Float {
+        triangles.iter()
+            .filter(|t| points_to(t, dir))
+            .filter(|t| {
+                let v = t.as_vertices();
+                let inside = (v[0].get_pos() + v[1].get_pos() + v[2].get_pos()) / 3. - get_direction_normal(dir) * 0.25;
+                inside.x.round() as i32 == pos[0] && inside.y.round() as i32 == pos[1]
+            })
+            .map(get_area)
+            .sum()
+    }
+
+    fn assert_area(actual: Float, expected: Float) {
+        assert!((actual - expected).abs() < 1e-4, "area is {}, expected {}", actual, expected);
+    }
+
+    #[test]
+    fn single_cube_keeps_all_faces() {
+        let chunks = create_chunks(&[([0, 0], FieldType::CUBE)]);
+        let triangles = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), NO_CAMERA);
+        for dir in SIDES.iter().chain([Direction::Up, Direction::Down].iter()) {
+            assert_area(get_face_area(&triangles, *dir), 1.);
+        }
+    }
+
+    #[test]
+    fn covered_cube_faces_are_dropped() {
+        let chunks = create_chunks(&[([0, 0], FieldType::CUBE), ([1, 0], FieldType::CUBE)]);
+        let triangles = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), NO_CAMERA);
+        assert_area(get_face_area(&triangles, Direction::East), 1.);
+        assert_area(get_face_area(&triangles, Direction::West), 1.);
+        assert_area(get_face_area(&triangles, Direction::North), 2.);
+        assert_area(get_face_area(&triangles, Direction::South), 2.);
+        assert_area(get_face_area(&triangles, Direction::Up), 2.);
+    }
+
+    #[test]
+    fn cube_faces_are_covered_across_chunk_borders() {
+        let chunks = create_chunks(&[([15, 0], FieldType::CUBE), ([16, 0], FieldType::CUBE)]);
+        let left = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), NO_CAMERA);
+        assert_area(get_face_area(&left, Direction::East), 0.);
+        assert_area(get_face_area(&left, Direction::West), 1.);
+        assert_area(get_face_area(&left, Direction::North), 1.);
+        let right = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([1, 0]), NO_CAMERA);
+        assert_area(get_face_area(&right, Direction::West), 0.);
+        assert_area(get_face_area(&right, Direction::East), 1.);
+    }
+
+    #[test]
+    fn adjacent_layers_occlude_but_do_not_cover() {
+        let chunks = create_chunks(&[([0, 0], FieldType::CUBE)]);
+        let above = create_chunks(&[([0, 0], FieldType::CUBE), ([1, 0], FieldType::CUBE)]);
+        let below = create_chunks(&[([0, 0], FieldType::CUBE)]);
+        let triangles = mesh(&chunks, AdjacentLayers::new(Some(&below), Some(&above)), get_chunk_area([0, 0]), NO_CAMERA);
+        assert_area(get_face_area(&triangles, Direction::Up), 1.);
+        assert_area(get_face_area(&triangles, Direction::Down), 1.);
+        for vertex in triangles.iter().filter(|t| points_to(t, Direction::Up)).flat_map(|t| t.as_vertices().iter()) {
+            let expected = if vertex.get_pos().x > 0. { 1. / 3. } else { 0. };
+            assert!((vertex.get_occlusion() - expected).abs() < 1e-4);
+        }
+        for vertex in triangles.iter().filter(|t| points_to(t, Direction::Down)).flat_map(|t| t.as_vertices().iter()) {
+            assert_eq!(vertex.get_occlusion(), 0.);
+        }
+    }
+
+    #[test]
+    fn slope_sides_are_culled_against_cubes() {
+        let chunks = create_chunks(&[([0, 0], FieldType::SLOPE(Direction::South)), ([1, 0], FieldType::CUBE)]);
+        let triangles = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), NO_CAMERA);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::East), 0.);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::West), 0.5);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::North), 1.);
+        // the slope side only covers half of the cube face
+        assert_area(get_field_face_area(&triangles, [1, 0], Direction::West), 1.);
+    }
+
+    #[test]
+    fn slope_backs_and_cubes_cover_each_other() {
+        let chunks = create_chunks(&[([0, 0], FieldType::SLOPE(Direction::South)), ([0, -1], FieldType::CUBE)]);
+        let triangles = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([-1, -1]), NO_CAMERA)
+            .into_iter()
+            .chain(mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), NO_CAMERA))
+            .collect::<Vec<_>>();
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::North), 0.);
+        assert_area(get_field_face_area(&triangles, [0, -1], Direction::South), 0.);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::East), 0.5);
+    }
+
+    #[test]
+    fn matching_slope_sides_cover_each_other() {
+        let chunks = create_chunks(&[([0, 0], FieldType::SLOPE(Direction::South)), ([1, 0], FieldType::SLOPE(Direction::South))]);
+        let triangles = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), NO_CAMERA);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::East), 0.);
+        assert_area(get_field_face_area(&triangles, [1, 0], Direction::West), 0.);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::West), 0.5);
+        assert_area(get_field_face_area(&triangles, [1, 0], Direction::East), 0.5);
+    }
+
+    #[test]
+    fn faces_pointing_away_from_the_camera_are_skipped() {
+        let camera = [1., 1., -1.];
+        let chunks = create_chunks(&[([0, 0], FieldType::CUBE), ([3, 0], FieldType::SLOPE(Direction::South))]);
+        let triangles = mesh(&chunks, AdjacentLayers::default(), get_chunk_area([0, 0]), camera);
+        let camera_direction = Vector3::new(camera[0], camera[1], camera[2]);
+        assert!(triangles.iter().all(|t| dot(t.get_normal(), camera_direction) <= 0.));
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::North), 1.);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::West), 1.);
+        assert_area(get_field_face_area(&triangles, [0, 0], Direction::Up), 1.);
+        assert_area(get_field_face_area(&triangles, [3, 0], Direction::West), 0.5);
+        assert_area(get_face_area(&triangles, Direction::East), 0.);
+        assert_area(get_face_area(&triangles, Direction::Down), 0.);
+    }
+}
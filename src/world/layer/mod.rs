@@ -3,6 +3,11 @@ pub mod field_type;
 pub mod field_material;
 pub mod layer;
 pub mod layer_creator;
+pub mod chunk;
+pub mod material_registry;
+pub mod field_generator;
+pub mod layer_mesh;
+pub mod layer_mesh_worker;
 mod mesh_creation;
 
 
@@ -10,5 +15,10 @@ pub use self::field::Field;
 pub use self::field_type::FieldType;
 pub use self::field_material::FieldMaterial;
 pub use self::layer::Layer;
-pub use self::mesh_creation::create_mesh;
+pub use self::chunk::{ Chunk, ChunkMap, CHUNK_SIZE };
+pub use self::material_registry::{ MaterialRegistry, MaterialTextures };
+pub use self::field_generator::FieldGenerator;
+pub use self::layer_mesh::LayerMesh;
+pub use self::layer_mesh_worker::LayerMeshWorker;
+pub use self::mesh_creation::{ upload_mesh, FieldTemplates, MeshStats, LayerMesher, AdjacentLayers };
 
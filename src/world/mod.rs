@@ -1,23 +1,47 @@
 pub mod world;
 pub mod traits;
 pub mod world_error;
+pub mod world_config;
 mod model;
 mod camera;
 mod object;
+mod object_batch;
 mod layer;
 mod noise;
 mod height_map;
 mod direction;
+mod save;
+pub mod designation;
+pub mod picking;
+pub mod entity;
+pub mod navigation;
+pub mod job;
+pub mod sim_clock;
+pub mod world_stats;
+pub mod render_stats;
+pub mod world_renderer;
 
-pub use self::world::World;
+pub use self::world::{ World, SIM_STEP };
 pub use self::model::Model;
 pub use self::camera::Camera;
 pub use self::object::Object;
+pub use self::object_batch::ObjectBatch;
 pub use self::layer::Layer;
 pub use self::layer::layer_creator::LayerCreator;
 pub use self::noise::Noise;
 pub use self::noise::OctavedNoise;
 pub use self::noise::SimplexNoise;
 pub use self::world_error::WorldError;
+pub use self::world_config::WorldConfig;
 pub use self::direction::Direction;
+pub use self::designation::{ Designations, DesignationType };
+pub use self::picking::{ Ray, PickResult };
+pub use self::entity::Dwarf;
+pub use self::navigation::NavGraph;
+pub use self::job::JobScheduler;
+pub use self::sim_clock::{ SimClock, SimSpeed };
+pub use self::world_stats::WorldStats;
+pub use self::render_stats::RenderStats;
+pub use self::save::SavedCamera;
+pub use self::world_renderer::WorldRenderer;
 pub use self::direction::DIRECTION_VECTOR;
@@ -0,0 +1,5 @@
+pub mod nav_graph;
+pub mod path_finding;
+
+pub use self::nav_graph::{ NavGraph, NavLink };
+pub use self::path_finding::find_path;
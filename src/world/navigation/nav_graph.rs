@@ -0,0 +1,388 @@
+use std::collections::{ BTreeMap, HashMap, HashSet, VecDeque };
+
+use world::{ Layer, Direction, DIRECTION_VECTOR };
+use world::layer::FieldType;
+use world::entity::terrain::{ get_field, is_cube, is_standable };
+
+/// Walking to a horizontal neighbour or taking the stairs costs 1, a ramp moves one field and one level and costs 2.
+const WALK_COST: u32 = 1;
+const RAMP_COST: u32 = 2;
+const STAIRS_COST: u32 = 1;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct NavLink {
+    target: [i32; 3],
+    cost: u32
+}
+
+struct NavNode {
+    links: Vec<NavLink>,
+    region: u32
+}
+
+/// Walkable positions and the moves between them. Cube tops are floors, horizontal slopes are ramps to the
+/// level above the cube behind them, `SLOPE(Up)` and `SLOPE(Down)` are stairs. Nodes connected by any path
+/// share a region, so unreachable targets can be rejected without a search.
+pub struct NavGraph {
+    nodes: HashMap<[i32; 3], NavNode>,
+    next_region: u32,
+    /// Increased by every update, anything derived from the links or regions is outdated once it changed.
+    version: u32
+}
+
+impl NavLink {
+    pub fn get_target(&self) -> [i32; 3] {
+        self.target
+    }
+
+    pub fn get_cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+impl NavGraph {
+    /// Builds the graph for all positions between the corners, both inclusive.
+    pub fn build(layers: &BTreeMap<i32, Layer>, min: [i32; 3], max: [i32; 3]) -> Self {
+        let mut graph = Self::default();
+        for_each_pos(min, max, |pos| {
+            if let Some(links) = compute_links(layers, pos) {
+                graph.nodes.insert(pos, NavNode { links: links, region: 0 });
+            }
+        });
+        graph.update_regions();
+        info!("Built navigation graph, nodes = {}, regions = {}", graph.nodes.len(), graph.next_region);
+        graph
+    }
+
+    pub fn is_walkable(&self, pos: [i32; 3]) -> bool {
+        self.nodes.contains_key(&pos)
+    }
+
+    pub fn get_links(&self, pos: [i32; 3]) -> &[NavLink] {
+        match self.nodes.get(&pos) {
+            Some(node) => &node.links,
+            None => &[]
+        }
+    }
+
+    pub fn is_linked(&self, from: [i32; 3], to: [i32; 3]) -> bool {
+        self.get_links(from).iter().any(|link| link.target == to)
+    }
+
+    pub fn get_region(&self, pos: [i32; 3]) -> Option<u32> {
+        self.nodes.get(&pos).map(|node| node.region)
+    }
+
+    /// True if a path between the positions exists, without searching for it.
+    pub fn is_reachable(&self, from: [i32; 3], to: [i32; 3]) -> bool {
+        match (self.get_region(from), self.get_region(to)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false
+        }
+    }
+
+    pub fn get_node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    /// Recomputes the nodes between the corners after the fields there changed.
+    /// Only the regions of the changed nodes and of their old neighbours are flooded again.
+    pub fn update_area(&mut self, layers: &BTreeMap<i32, Layer>, min: [i32; 3], max: [i32; 3]) {
+        let mut seeds = Vec::new();
+        for_each_pos(min, max, |pos| {
+            let (old_links, old_region) = self.nodes.remove(&pos).map_or((Vec::new(), 0), |node| (node.links, node.region));
+            if let Some(links) = compute_links(layers, pos) {
+                self.nodes.insert(pos, NavNode { links: links, region: old_region });
+                seeds.push(pos);
+            }
+            for link in old_links {
+                if !is_inside(link.target, min, max) {
+                    if let Some(node) = self.nodes.get_mut(&link.target) {
+                        node.links.retain(|l| l.target != pos);
+                        seeds.push(link.target);
+                    }
+                }
+            }
+        });
+        for pos in seeds.iter().filter(|pos| is_inside(**pos, min, max)) {
+            let links = self.nodes[pos].links.clone();
+            for link in links.iter().filter(|l| !is_inside(l.target, min, max)) {
+                if let Some(node) = self.nodes.get_mut(&link.target) {
+                    node.links.push(NavLink { target: *pos, cost: link.cost });
+                }
+            }
+        }
+        self.update_regions_around(&seeds);
+        self.version += 1;
+        trace!("Updated navigation graph between {:?} and {:?}, nodes = {}", min, max, self.nodes.len());
+    }
+
+    fn create_region(&mut self) -> u32 {
+        self.next_region += 1;
+        self.next_region
+    }
+
+    fn update_regions(&mut self) {
+        self.next_region = 0;
+        let positions: Vec<[i32; 3]> = self.nodes.keys().cloned().collect();
+        let mut reached = HashSet::new();
+        for pos in positions {
+            if !reached.contains(&pos) {
+                let region = self.create_region();
+                self.flood_region(pos, region, &mut reached);
+            }
+        }
+    }
+
+    /// Floods the regions containing the positions. Every region changed by an update contains one of them,
+    /// since links are only gained or lost at the updated nodes and their neighbours. The first flood of a region
+    /// keeps its id, so a region reached completely stays as it is, and a part it no longer reaches gets a new one.
+    /// New nodes have no region yet, they are flooded last to join the region of an old node if they can.
+    fn update_regions_around(&mut self, positions: &[[i32; 3]]) {
+        let mut positions = positions.to_vec();
+        positions.sort_by_key(|pos| self.nodes.get(pos).map_or(true, |node| node.region == 0));
+        let mut reached = HashSet::new();
+        let mut kept_regions = HashSet::new();
+        for pos in positions.iter() {
+            let old_region = match self.nodes.get(pos) {
+                Some(node) if !reached.contains(pos) => node.region,
+                _ => continue
+            };
+            let region = match old_region {
+                0 => self.create_region(),
+                _ if kept_regions.insert(old_region) => old_region,
+                _ => {
+                    let region = self.create_region();
+                    debug!("Split navigation region {}, new region = {}", old_region, region);
+                    region
+                }
+            };
+            self.flood_region(*pos, region, &mut reached);
+        }
+    }
+
+    /// Gives every node connected to the start the region, the reached positions are collected.
+    fn flood_region(&mut self, start: [i32; 3], region: u32, reached: &mut HashSet<[i32; 3]>) {
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(pos) = queue.pop_front() {
+            if !reached.insert(pos) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get_mut(&pos) {
+                node.region = region;
+                queue.extend(node.links.iter().map(|link| link.target));
+            }
+        }
+    }
+}
+
+impl Default for NavGraph {
+    fn default() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            next_region: 0,
+            version: 0
+        }
+    }
+}
+
+/// Returns None if the position can't be stood on. Links are symmetric, so every move can be taken back.
+fn compute_links(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> Option<Vec<NavLink>> {
+    if !is_standable(layers, pos) {
+        return None;
+    }
+    let mut links = Vec::new();
+    let field_type = get_field(layers, pos).map(|f| f.get_type());
+    for (dir, offset) in DIRECTION_VECTOR.iter().take(4) {
+        let nb_pos = add(pos, *offset);
+        if is_standable(layers, nb_pos) {
+            links.push(NavLink { target: nb_pos, cost: WALK_COST });
+        }
+        // Going up a ramp, the slope rises towards the cube behind it.
+        if field_type == Some(FieldType::SLOPE(dir.get_opposite())) {
+            let top = add(nb_pos, [0, 0, 1]);
+            if is_cube(layers, nb_pos) && is_standable(layers, top) {
+                links.push(NavLink { target: top, cost: RAMP_COST });
+            }
+        }
+        // Going down a ramp which rises towards the cube below the position.
+        let slope_pos = add(nb_pos, [0, 0, -1]);
+        let below = add(pos, [0, 0, -1]);
+        let is_ramp_down = get_field(layers, slope_pos).map_or(false, |f| f.get_type() == FieldType::SLOPE(*dir));
+        if is_ramp_down && is_cube(layers, below) {
+            links.push(NavLink { target: slope_pos, cost: RAMP_COST });
+        }
+    }
+    let above = add(pos, [0, 0, 1]);
+    let below = add(pos, [0, 0, -1]);
+    if is_stairs_up(layers, pos) && is_stairs(layers, above) {
+        links.push(NavLink { target: above, cost: STAIRS_COST });
+    }
+    if is_stairs(layers, pos) && is_stairs_up(layers, below) {
+        links.push(NavLink { target: below, cost: STAIRS_COST });
+    }
+    Some(links)
+}
+
+fn is_stairs_up(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> bool {
+    get_field(layers, pos).map_or(false, |f| f.get_type() == FieldType::SLOPE(Direction::Up))
+}
+
+fn is_stairs(layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) -> bool {
+    match get_field(layers, pos).map(|f| f.get_type()) {
+        Some(FieldType::SLOPE(Direction::Up)) | Some(FieldType::SLOPE(Direction::Down)) => true,
+        _ => false
+    }
+}
+
+fn add(pos: [i32; 3], offset: [i32; 3]) -> [i32; 3] {
+    [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]]
+}
+
+fn is_inside(pos: [i32; 3], min: [i32; 3], max: [i32; 3]) -> bool {
+    (0..3).all(|i| pos[i] >= min[i] && pos[i] <= max[i])
+}
+
+fn for_each_pos<F: FnMut([i32; 3])>(min: [i32; 3], max: [i32; 3], mut f: F) {
+    for z in min[2]..=max[2] {
+        for y in min[1]..=max[1] {
+            for x in min[0]..=max[0] {
+                f([x, y, z]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::BTreeMap;
+
+    use world::{ Layer, Direction };
+    use world::layer::{ ChunkMap, Field, FieldType };
+    use world::layer::layer::FieldMap;
+    use super::NavGraph;
+
+    pub const SIZE: [i32; 2] = [8, 8];
+
+    /// A floor of cubes on level 0, so level 1 can be walked on, with the given fields on top of it.
+    pub fn create_layers(fields: &[([i32; 3], FieldType)]) -> BTreeMap<i32, Layer> {
+        let mut layers = BTreeMap::new();
+        for level in 0..4 {
+            let mut field_map = FieldMap::new();
+            if level == 0 {
+                for y in 0..SIZE[1] {
+                    for x in 0..SIZE[0] {
+                        field_map.insert([x, y], Field::default());
+                    }
+                }
+            }
+            for (pos, field_type) in fields.iter().filter(|(pos, _)| pos[2] == level) {
+                let mut field = Field::default();
+                field.set_type(*field_type);
+                field_map.insert([pos[0], pos[1]], field);
+            }
+            layers.insert(level, Layer::new(level, SIZE, ChunkMap::from_fields(field_map)));
+        }
+        layers
+    }
+
+    /// Cubes on level 1 along x = 4, they split the floor into a west and an east part.
+    pub fn create_wall() -> Vec<([i32; 3], FieldType)> {
+        (0..SIZE[1]).map(|y| ([4, y, 1], FieldType::CUBE)).collect()
+    }
+
+    pub fn build(layers: &BTreeMap<i32, Layer>) -> NavGraph {
+        NavGraph::build(layers, [0, 0, 0], [SIZE[0] - 1, SIZE[1] - 1, 3])
+    }
+
+    fn set_field(layers: &mut BTreeMap<i32, Layer>, pos: [i32; 3], field: Option<Field>) {
+        layers.get_mut(&pos[2]).unwrap().set_field([pos[0], pos[1]], field);
+    }
+
+    fn update_around(graph: &mut NavGraph, layers: &BTreeMap<i32, Layer>, pos: [i32; 3]) {
+        graph.update_area(layers, [pos[0] - 1, pos[1] - 1, pos[2] - 1], [pos[0] + 1, pos[1] + 1, pos[2] + 1]);
+    }
+
+    /// The regions of an updated graph must connect the same nodes as the ones of a new graph.
+    fn assert_regions_match(graph: &NavGraph, layers: &BTreeMap<i32, Layer>) {
+        let expected = build(layers);
+        assert_eq!(graph.get_node_count(), expected.get_node_count());
+        let positions: Vec<[i32; 3]> = expected.nodes.keys().cloned().collect();
+        for a in positions.iter() {
+            for b in positions.iter() {
+                assert_eq!(graph.is_reachable(*a, *b), expected.is_reachable(*a, *b), "{:?} and {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn wall_splits_regions() {
+        let layers = create_layers(&create_wall());
+        let graph = build(&layers);
+        assert!(graph.is_reachable([0, 0, 1], [3, 7, 1]));
+        assert!(graph.is_reachable([5, 0, 1], [7, 7, 1]));
+        assert!(!graph.is_reachable([0, 0, 1], [7, 0, 1]));
+        assert!(!graph.is_reachable([0, 0, 1], [4, 0, 2]));
+        assert!(!graph.is_walkable([4, 0, 1]));
+    }
+
+    #[test]
+    fn digging_through_a_wall_merges_regions() {
+        let mut layers = create_layers(&create_wall());
+        let mut graph = build(&layers);
+        set_field(&mut layers, [4, 3, 1], None);
+        update_around(&mut graph, &layers, [4, 3, 1]);
+        assert!(graph.is_reachable([0, 0, 1], [7, 0, 1]));
+        assert!(graph.is_reachable([4, 3, 1], [0, 0, 1]));
+        assert_regions_match(&graph, &layers);
+    }
+
+    #[test]
+    fn closing_a_wall_splits_regions() {
+        let mut wall = create_wall();
+        wall.retain(|(pos, _)| pos[1] != 3);
+        let mut layers = create_layers(&wall);
+        let mut graph = build(&layers);
+        assert!(graph.is_reachable([0, 0, 1], [7, 0, 1]));
+        set_field(&mut layers, [4, 3, 1], Some(Field::default()));
+        update_around(&mut graph, &layers, [4, 3, 1]);
+        assert!(!graph.is_reachable([0, 0, 1], [7, 0, 1]));
+        assert_regions_match(&graph, &layers);
+    }
+
+    #[test]
+    fn region_reached_completely_is_kept() {
+        let mut layers = create_layers(&create_wall());
+        let mut graph = build(&layers);
+        let west = graph.get_region([0, 0, 1]);
+        let east = graph.get_region([7, 0, 1]);
+        // the hole can be walked around, so the west part stays one region
+        set_field(&mut layers, [1, 1, 0], None);
+        update_around(&mut graph, &layers, [1, 1, 0]);
+        assert!(!graph.is_walkable([1, 1, 1]));
+        assert_eq!(graph.get_region([0, 0, 1]), west);
+        assert_eq!(graph.get_region([3, 7, 1]), west);
+        assert_eq!(graph.get_region([7, 0, 1]), east);
+        assert_regions_match(&graph, &layers);
+    }
+
+    #[test]
+    fn ramp_dug_into_a_wall_connects_its_top() {
+        let mut layers = create_layers(&create_wall());
+        let mut graph = build(&layers);
+        set_field(&mut layers, [3, 0, 1], Some({
+            let mut field = Field::default();
+            field.set_type(FieldType::SLOPE(Direction::West));
+            field
+        }));
+        update_around(&mut graph, &layers, [3, 0, 1]);
+        assert!(graph.is_reachable([0, 0, 1], [4, 5, 2]));
+        assert!(!graph.is_reachable([0, 0, 1], [7, 0, 1]));
+        assert_regions_match(&graph, &layers);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{ BinaryHeap, HashMap };
+use std::cmp::Reverse;
+
+use world::navigation::NavGraph;
+
+/// A* search from the start to the nearest of the goals. The returned path excludes the start and ends at a goal.
+/// Goals outside the region of the start are dropped up front, if none is left the search fails without expanding any node.
+pub fn find_path(graph: &NavGraph, start: [i32; 3], goals: &[[i32; 3]]) -> Option<Vec<[i32; 3]>> {
+    let goals: Vec<[i32; 3]> = goals.iter()
+        .filter(|goal| graph.is_reachable(start, **goal))
+        .cloned()
+        .collect();
+    if goals.is_empty() {
+        return None;
+    }
+    if goals.contains(&start) {
+        return Some(Vec::new());
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<[i32; 3], [i32; 3]> = HashMap::new();
+    let mut costs: HashMap<[i32; 3], u32> = HashMap::new();
+    costs.insert(start, 0);
+    open.push(Reverse((estimate_cost(start, &goals), 0, start)));
+
+    while let Some(Reverse((_, cost, pos))) = open.pop() {
+        if goals.contains(&pos) {
+            return Some(create_path(&came_from, start, pos));
+        }
+        if cost > costs[&pos] {
+            continue;
+        }
+        for link in graph.get_links(pos) {
+            let target = link.get_target();
+            let new_cost = cost + link.get_cost();
+            if costs.get(&target).map_or(true, |c| new_cost < *c) {
+                costs.insert(target, new_cost);
+                came_from.insert(target, pos);
+                open.push(Reverse((new_cost + estimate_cost(target, &goals), new_cost, target)));
+            }
+        }
+    }
+    warn!("No path from {:?} to {:?} although they share a region", start, goals);
+    None
+}
+
+/// Manhattan distance to the nearest goal, never more than the real cost since every move costs at least the distance it covers.
+fn estimate_cost(pos: [i32; 3], goals: &[[i32; 3]]) -> u32 {
+    goals.iter()
+        .map(|goal| ((goal[0] - pos[0]).abs() + (goal[1] - pos[1]).abs() + (goal[2] - pos[2]).abs()) as u32)
+        .min()
+        .unwrap_or(0)
+}
+
+fn create_path(came_from: &HashMap<[i32; 3], [i32; 3]>, start: [i32; 3], end: [i32; 3]) -> Vec<[i32; 3]> {
+    let mut path = vec![end];
+    let mut pos = end;
+    while let Some(prev) = came_from.get(&pos) {
+        if *prev == start {
+            break;
+        }
+        path.push(*prev);
+        pos = *prev;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use world::Direction;
+    use world::layer::FieldType;
+    use world::navigation::nav_graph::tests::{ create_layers, create_wall, build };
+    use super::find_path;
+
+    /// Cubes on level 1 for x >= 4, so their tops on level 2 form a raised platform.
+    fn create_platform() -> Vec<([i32; 3], FieldType)> {
+        create_wall().into_iter()
+            .flat_map(|(pos, field_type)| (4..8).map(move |x| ([x, pos[1], pos[2]], field_type)))
+            .collect()
+    }
+
+    #[test]
+    fn path_to_reachable_goal_is_shortest() {
+        let layers = create_layers(&[]);
+        let graph = build(&layers);
+        let path = find_path(&graph, [0, 0, 1], &[[5, 3, 1]]).unwrap();
+        assert_eq!(path.len(), 8);
+        assert_eq!(path.last(), Some(&[5, 3, 1]));
+        assert!(graph.is_linked([0, 0, 1], path[0]));
+        assert!(path.windows(2).all(|step| graph.is_linked(step[0], step[1])));
+    }
+
+    #[test]
+    fn path_ends_at_nearest_goal() {
+        let layers = create_layers(&[]);
+        let graph = build(&layers);
+        let path = find_path(&graph, [0, 0, 1], &[[7, 7, 1], [2, 0, 1]]).unwrap();
+        assert_eq!(path, vec![[1, 0, 1], [2, 0, 1]]);
+        assert_eq!(find_path(&graph, [2, 0, 1], &[[2, 0, 1]]), Some(Vec::new()));
+    }
+
+    #[test]
+    fn unreachable_goal_has_no_path() {
+        let layers = create_layers(&create_wall());
+        let graph = build(&layers);
+        assert_eq!(find_path(&graph, [0, 0, 1], &[[7, 0, 1]]), None);
+        assert_eq!(find_path(&graph, [0, 0, 1], &[[4, 0, 2]]), None);
+        assert_eq!(find_path(&graph, [0, 0, 1], &[[4, 0, 1]]), None);
+    }
+
+    #[test]
+    fn path_goes_up_and_down_ramps() {
+        let mut fields = create_wall();
+        fields.push(([3, 0, 1], FieldType::SLOPE(Direction::West)));
+        fields.push(([5, 7, 1], FieldType::SLOPE(Direction::East)));
+        let layers = create_layers(&fields);
+        let graph = build(&layers);
+        let path = find_path(&graph, [0, 0, 1], &[[7, 7, 1]]).unwrap();
+        let up = path.iter().position(|pos| *pos == [3, 0, 1]).unwrap();
+        assert_eq!(path[up + 1], [4, 0, 2]);
+        let down = path.iter().position(|pos| *pos == [5, 7, 1]).unwrap();
+        assert_eq!(path[down - 1], [4, 7, 2]);
+    }
+
+    #[test]
+    fn path_goes_up_stairs() {
+        let mut fields = create_platform();
+        fields.push(([3, 2, 1], FieldType::SLOPE(Direction::Up)));
+        fields.push(([3, 2, 2], FieldType::SLOPE(Direction::Down)));
+        let layers = create_layers(&fields);
+        let graph = build(&layers);
+        let path = find_path(&graph, [0, 2, 1], &[[6, 2, 2]]).unwrap();
+        assert_eq!(path, vec![[1, 2, 1], [2, 2, 1], [3, 2, 1], [3, 2, 2], [4, 2, 2], [5, 2, 2], [6, 2, 2]]);
+        let back = find_path(&graph, [6, 2, 2], &[[0, 2, 1]]).unwrap();
+        assert!(back.contains(&[3, 2, 1]));
+    }
+
+    #[test]
+    fn platform_without_ramps_is_unreachable() {
+        let layers = create_layers(&create_platform());
+        let graph = build(&layers);
+        assert_eq!(find_path(&graph, [0, 0, 1], &[[6, 0, 2]]), None);
+    }
+}
@@ -2,6 +2,6 @@ pub mod noise;
 pub mod octaved_noise;
 pub mod simplex_noise;
 
-pub use self::noise::Noise;
+pub use self::noise::{ Noise, Noise3, Noise4 };
 pub use self::octaved_noise::OctavedNoise;
 pub use self::simplex_noise::SimplexNoise;
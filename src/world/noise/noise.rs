@@ -2,3 +2,11 @@ pub trait Noise {
     fn get_noise(&self, point: (f32, f32)) -> f32;
     fn get_range(&self) -> (f32, f32);
 }
+
+pub trait Noise3: Noise {
+    fn get_noise_3d(&self, point: (f32, f32, f32)) -> f32;
+}
+
+pub trait Noise4: Noise3 {
+    fn get_noise_4d(&self, point: (f32, f32, f32, f32)) -> f32;
+}
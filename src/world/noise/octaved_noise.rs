@@ -1,22 +1,22 @@
-
-use super::{ Noise, SimplexNoise };
+use super::{ Noise, Noise3, Noise4, SimplexNoise };
 
 const DEFAULT_OCTAVES: u8 = 4;
 const DEFAULT_ROUGHNESS: f32 = 0.5;
 const DEFAULT_SCALE: f32 = 2.5e-1;
 const DEFAULT_RANGE: (f32, f32) = (-1., 1.);
 
-pub struct OctavedNoise {
-    noise: Box<Noise>,
+/// Sums octaves of the base noise, in as many dimensions as the base noise supports.
+pub struct OctavedNoise<N: Noise = SimplexNoise> {
+    noise: N,
     octaves: u8,
     roughness: f32,
     scale: f32,
     range: (f32, f32)
 }
 
-impl OctavedNoise {
+impl<N: Noise> OctavedNoise<N> {
 
-    pub fn new(octaves: u8, roughness: f32, scale: f32, range: (f32, f32), noise: Box<Noise>) -> Self {
+    pub fn new(octaves: u8, roughness: f32, scale: f32, range: (f32, f32), noise: N) -> Self {
         Self {
             noise: noise,
             octaves: octaves,
@@ -41,22 +41,21 @@ impl OctavedNoise {
     pub fn set_range(&mut self, new_range: (f32, f32)) {
         self.range = new_range;
     }
-}
 
-/*
-    Octave calculation based on code by
-    matheus23 @ http://www.java-gaming.org/index.php?topic=31637.0
-*/
+    /*
+        Octave calculation based on code by
+        matheus23 @ http://www.java-gaming.org/index.php?topic=31637.0
+    */
 
-impl Noise for OctavedNoise {
-    fn get_noise(&self, p: (f32, f32)) -> f32 {
+    /// Sums the octaves, sample is called with the frequency of each octave.
+    fn sum_octaves<F: Fn(f32) -> f32>(&self, sample: F) -> f32 {
         let mut sum: f32 = 0.;
         let mut freq = self.scale;
         let mut weight: f32 = 1.;
         let mut weight_sum: f32 = 0.;
 
         for _oct in 0..self.octaves {
-            sum += self.noise.get_noise((p.0 * freq, p.1 * freq)) * weight;
+            sum += sample(freq) * weight;
             weight_sum += weight;
             freq *= 2.;
             weight *= self.roughness;
@@ -66,16 +65,34 @@ impl Noise for OctavedNoise {
         debug_assert!(normalized >= 0. && normalized <= 1.);
         self.range.0 + (self.range.1 - self.range.0) * normalized
     }
+}
+
+impl<N: Noise> Noise for OctavedNoise<N> {
+    fn get_noise(&self, p: (f32, f32)) -> f32 {
+        self.sum_octaves(|freq| self.noise.get_noise((p.0 * freq, p.1 * freq)))
+    }
 
     fn get_range(&self) -> (f32, f32) {
         self.range
     }
 }
 
-impl Default for OctavedNoise {
+impl<N: Noise3> Noise3 for OctavedNoise<N> {
+    fn get_noise_3d(&self, p: (f32, f32, f32)) -> f32 {
+        self.sum_octaves(|freq| self.noise.get_noise_3d((p.0 * freq, p.1 * freq, p.2 * freq)))
+    }
+}
+
+impl<N: Noise4> Noise4 for OctavedNoise<N> {
+    fn get_noise_4d(&self, p: (f32, f32, f32, f32)) -> f32 {
+        self.sum_octaves(|freq| self.noise.get_noise_4d((p.0 * freq, p.1 * freq, p.2 * freq, p.3 * freq)))
+    }
+}
+
+impl Default for OctavedNoise<SimplexNoise> {
     fn default() -> Self {
         Self {
-            noise: Box::new(SimplexNoise::default()),
+            noise: SimplexNoise::default(),
             octaves: DEFAULT_OCTAVES,
             roughness: DEFAULT_ROUGHNESS,
             scale: DEFAULT_SCALE,
@@ -1,10 +1,9 @@
 use std::iter;
 use rand;
-use rand::Rng;
-use rand::prelude::{ SmallRng, SliceRandom };
-use rand::{ SeedableRng };
+use rand::{ Rng, RngCore, SeedableRng };
+use rand_xorshift::XorShiftRng;
 
-use super::Noise;
+use super::{ Noise, Noise3, Noise4 };
 
 /*
     Noise calculation based on code by
@@ -17,21 +16,51 @@ lazy_static! {
     static ref G2: f32 = (3. - f32::sqrt(3.)) / 6.;
 }
 
+const F3: f32 = 1. / 3.;
+const G3: f32 = 1. / 6.;
+
+lazy_static! {
+    static ref F4: f32 = (f32::sqrt(5.) - 1.) / 4.;
+    static ref G4: f32 = (5. - f32::sqrt(5.)) / 20.;
+}
+
 const GRADIENTS: [(i32, i32); 12] = [
     (1, 1), (-1, 1), (1, -1), (-1, -1),
     (1, 0), (-1, 0), (1, 0), (-1, 0),
     (0, 1), (0, -1), (0, 1), (0, -1)
 ];
 
+const GRADIENTS_3D: [(i32, i32, i32); 12] = [
+    (1, 1, 0), (-1, 1, 0), (1, -1, 0), (-1, -1, 0),
+    (1, 0, 1), (-1, 0, 1), (1, 0, -1), (-1, 0, -1),
+    (0, 1, 1), (0, -1, 1), (0, 1, -1), (0, -1, -1)
+];
+
+const GRADIENTS_4D: [(i32, i32, i32, i32); 32] = [
+    (0, 1, 1, 1), (0, 1, 1, -1), (0, 1, -1, 1), (0, 1, -1, -1),
+    (0, -1, 1, 1), (0, -1, 1, -1), (0, -1, -1, 1), (0, -1, -1, -1),
+    (1, 0, 1, 1), (1, 0, 1, -1), (1, 0, -1, 1), (1, 0, -1, -1),
+    (-1, 0, 1, 1), (-1, 0, 1, -1), (-1, 0, -1, 1), (-1, 0, -1, -1),
+    (1, 1, 0, 1), (1, 1, 0, -1), (1, -1, 0, 1), (1, -1, 0, -1),
+    (-1, 1, 0, 1), (-1, 1, 0, -1), (-1, -1, 0, 1), (-1, -1, 0, -1),
+    (1, 1, 1, 0), (1, 1, -1, 0), (1, -1, 1, 0), (1, -1, -1, 0),
+    (-1, 1, 1, 0), (-1, 1, -1, 0), (-1, -1, 1, 0), (-1, -1, -1, 0)
+];
+
 pub struct SimplexNoise {
     seed: [u8; 16],
     permutation_table: Vec<u8>,
 }
 
+/// Shuffles with Fisher-Yates on a xorshift stream, both fixed algorithms, so a seed gives the same table
+/// on every platform and with every version of rand.
 fn create_permutation_table(seed: [u8; 16]) -> Vec<u8> {
-    let mut rng = SmallRng::from_seed(seed);
+    let mut rng = XorShiftRng::from_seed(seed);
     let mut permutation: Vec<u8> = (0u8..255).chain(iter::once(255u8)).collect();
-    permutation.shuffle(&mut rng);
+    for i in (1..permutation.len()).rev() {
+        let j = (rng.next_u32() % (i as u32 + 1)) as usize;
+        permutation.swap(i, j);
+    }
     let perm_clone = permutation.clone();
     permutation.extend(perm_clone);
     for v in permutation.iter_mut().skip(256) {
@@ -94,6 +123,62 @@ impl Noise for SimplexNoise {
     }
 }
 
+impl Noise3 for SimplexNoise {
+    fn get_noise_3d(&self, p: (f32, f32, f32)) -> f32 {
+        let skew = (p.0 + p.1 + p.2) * F3;
+        let skew_coord: (i32, i32, i32) = ((p.0 + skew).floor() as i32,
+                                           (p.1 + skew).floor() as i32,
+                                           (p.2 + skew).floor() as i32);
+        let unskew = (skew_coord.0 + skew_coord.1 + skew_coord.2) as f32 * G3;
+        let first_corner: (f32, f32, f32) = (p.0 - (skew_coord.0 as f32 - unskew),
+                                             p.1 - (skew_coord.1 as f32 - unskew),
+                                             p.2 - (skew_coord.2 as f32 - unskew));
+
+        let (second_offset, third_offset) = get_corner_offsets_3d(first_corner);
+        let table_offset: [(i32, i32, i32); 4] = [(0, 0, 0), second_offset, third_offset, (1, 1, 1)];
+        let table_base_index: (i32, i32, i32) = (skew_coord.0 & 0xFF, skew_coord.1 & 0xFF, skew_coord.2 & 0xFF);
+        let mut contrib_sum: f32 = 0.;
+        for (i, offset) in table_offset.iter().enumerate() {
+            let corner = (first_corner.0 - offset.0 as f32 + i as f32 * G3,
+                          first_corner.1 - offset.1 as f32 + i as f32 * G3,
+                          first_corner.2 - offset.2 as f32 + i as f32 * G3);
+            let grad_index = calculate_gradient_index_3d(table_base_index, *offset, &self.permutation_table);
+            contrib_sum += calculate_corner_contribution_3d(grad_index, corner);
+        }
+        debug_assert!((32. * contrib_sum).abs() <= 1.);
+        32. * contrib_sum
+    }
+}
+
+impl Noise4 for SimplexNoise {
+    fn get_noise_4d(&self, p: (f32, f32, f32, f32)) -> f32 {
+        let skew = (p.0 + p.1 + p.2 + p.3) * *F4;
+        let skew_coord: [i32; 4] = [(p.0 + skew).floor() as i32,
+                                    (p.1 + skew).floor() as i32,
+                                    (p.2 + skew).floor() as i32,
+                                    (p.3 + skew).floor() as i32];
+        let unskew = (skew_coord[0] + skew_coord[1] + skew_coord[2] + skew_coord[3]) as f32 * *G4;
+        let point = [p.0, p.1, p.2, p.3];
+        let mut first_corner = [0.; 4];
+        for i in 0..4 {
+            first_corner[i] = point[i] - (skew_coord[i] as f32 - unskew);
+        }
+
+        let table_base_index: [i32; 4] = [skew_coord[0] & 0xFF, skew_coord[1] & 0xFF, skew_coord[2] & 0xFF, skew_coord[3] & 0xFF];
+        let mut contrib_sum: f32 = 0.;
+        for (i, offset) in get_corner_offsets_4d(first_corner).iter().enumerate() {
+            let mut corner = [0.; 4];
+            for d in 0..4 {
+                corner[d] = first_corner[d] - offset[d] as f32 + i as f32 * *G4;
+            }
+            let grad_index = calculate_gradient_index_4d(table_base_index, *offset, &self.permutation_table);
+            contrib_sum += calculate_corner_contribution_4d(grad_index, corner);
+        }
+        debug_assert!((27. * contrib_sum).abs() <= 1.);
+        27. * contrib_sum
+    }
+}
+
 fn calculate_corners(p: (f32, f32), cell_origin: (f32, f32))  -> [(f32, f32); 3] {
     let mut corner = [(0., 0.); 3];
     
@@ -132,3 +217,130 @@ fn calculate_corner_contribution(grad_index: u8, corner_offset: (f32, f32)) -> f
 fn dot(grad: (i32, i32), p: (f32, f32)) -> f32 {
     grad.0 as f32 * p.0 + grad.1 as f32 * p.1
 }
+
+fn get_corner_offsets_3d(c: (f32, f32, f32)) -> ((i32, i32, i32), (i32, i32, i32)) {
+    if c.0 >= c.1 {
+        if c.1 >= c.2 {
+            ((1, 0, 0), (1, 1, 0))
+        } else if c.0 >= c.2 {
+            ((1, 0, 0), (1, 0, 1))
+        } else {
+            ((0, 0, 1), (1, 0, 1))
+        }
+    } else {
+        if c.1 < c.2 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if c.0 < c.2 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        }
+    }
+}
+
+fn calculate_gradient_index_3d(base: (i32, i32, i32), off: (i32, i32, i32), table: &[u8]) -> u8 {
+    let k = table[(base.2 + off.2) as usize % 256] as i32;
+    let j = table[(base.1 + off.1 + k) as usize % 256] as i32;
+    table[256usize + (base.0 + off.0 + j) as usize % 256]
+}
+
+fn calculate_corner_contribution_3d(grad_index: u8, corner_offset: (f32, f32, f32)) -> f32 {
+    let t: f32 = 0.6 - corner_offset.0.powf(2.) - corner_offset.1.powf(2.) - corner_offset.2.powf(2.);
+    if t < 0. {
+        0.
+    } else {
+        let grad = GRADIENTS_3D[grad_index as usize];
+        t.powf(4.) * (grad.0 as f32 * corner_offset.0 + grad.1 as f32 * corner_offset.1 + grad.2 as f32 * corner_offset.2)
+    }
+}
+
+/// The simplex corners are found by ranking the coordinates of the first corner by magnitude.
+fn get_corner_offsets_4d(c: [f32; 4]) -> [[i32; 4]; 5] {
+    let mut rank = [0; 4];
+    for i in 0..4 {
+        for j in i + 1..4 {
+            if c[i] > c[j] {
+                rank[i] += 1;
+            } else {
+                rank[j] += 1;
+            }
+        }
+    }
+    let mut offsets = [[0; 4]; 5];
+    for (step, offset) in offsets.iter_mut().enumerate() {
+        for d in 0..4 {
+            offset[d] = if rank[d] >= 4 - step as i32 { 1 } else { 0 };
+        }
+    }
+    offsets
+}
+
+fn calculate_gradient_index_4d(base: [i32; 4], off: [i32; 4], table: &[u8]) -> u8 {
+    let l = table[(base[3] + off[3]) as usize % 256] as i32;
+    let k = table[(base[2] + off[2] + l) as usize % 256] as i32;
+    let j = table[(base[1] + off[1] + k) as usize % 256] as i32;
+    table[(base[0] + off[0] + j) as usize % 256] % 32
+}
+
+fn calculate_corner_contribution_4d(grad_index: u8, corner_offset: [f32; 4]) -> f32 {
+    let t: f32 = 0.6 - corner_offset.iter().map(|c| c.powf(2.)).sum::<f32>();
+    if t < 0. {
+        0.
+    } else {
+        let grad = GRADIENTS_4D[grad_index as usize];
+        t.powf(4.) * (grad.0 as f32 * corner_offset[0] + grad.1 as f32 * corner_offset[1] +
+                      grad.2 as f32 * corner_offset[2] + grad.3 as f32 * corner_offset[3])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use world::noise::{ Noise3, Noise4 };
+    use super::SimplexNoise;
+
+    const SAMPLE_COUNT: usize = 200_000;
+
+    /// Points spread irregularly over several hundred cells, including negative coordinates.
+    fn get_points() -> Vec<(f32, f32, f32, f32)> {
+        (0..SAMPLE_COUNT)
+            .map(|i| {
+                let t = i as f32;
+                ((t * 0.1234).sin() * 300., (t * 0.0567).cos() * 300., t * 3.1e-3 - 300., (t * 0.0291).sin() * 50.)
+            })
+            .collect()
+    }
+
+    /// Returns the minimum and the maximum, all values must be inside the range of the noise.
+    fn get_extremes<F: Fn((f32, f32, f32, f32)) -> f32>(noise: F) -> (f32, f32) {
+        get_points().into_iter().map(noise).fold((0., 0.), |(min, max), value| {
+            assert!(value >= -1. && value <= 1., "value {} out of range", value);
+            (min.min(value), max.max(value))
+        })
+    }
+
+    #[test]
+    fn noise_3d_stays_in_range() {
+        let noise = SimplexNoise::from_str_seed("range");
+        let (min, max) = get_extremes(|p| noise.get_noise_3d((p.0, p.1, p.2)));
+        // the range is used, not just a small part of it
+        assert!(min < -0.7 && max > 0.7, "extremes {} and {}", min, max);
+    }
+
+    #[test]
+    fn noise_4d_stays_in_range() {
+        let noise = SimplexNoise::from_str_seed("range");
+        let (min, max) = get_extremes(|p| noise.get_noise_4d(p));
+        assert!(min < -0.6 && max > 0.6, "extremes {} and {}", min, max);
+    }
+
+    #[test]
+    fn same_seed_gives_same_values() {
+        let (a, b) = (SimplexNoise::from_str_seed("seed"), SimplexNoise::from_str_seed("seed"));
+        let other = SimplexNoise::from_str_seed("other seed");
+        let points = get_points();
+        assert!(points.iter().all(|p| a.get_noise_3d((p.0, p.1, p.2)) == b.get_noise_3d((p.0, p.1, p.2))));
+        assert!(points.iter().all(|p| a.get_noise_4d(*p) == b.get_noise_4d(*p)));
+        assert!(points.iter().any(|p| a.get_noise_3d((p.0, p.1, p.2)) != other.get_noise_3d((p.0, p.1, p.2))));
+        assert!(points.iter().any(|p| a.get_noise_4d(*p) != other.get_noise_4d(*p)));
+    }
+}
@@ -1,7 +1,7 @@
 use std::rc::Rc;
 use glm::{ Vector3 };
 
-use graphics::{ Mesh, ShaderProgram, GraphicsError };
+use graphics::{ Mesh, ShaderProgram, GraphicsError, Animator, BoundingBox, Frustum, mesh::MeshInstance };
 use world::{ Camera, Model, traits::Renderable };
 use utility::traits::{ Rotatable, Translatable, Scalable };
 use utility::Float;
@@ -9,6 +9,7 @@ use utility::Float;
 pub struct Object {
     model: Model,
     mesh: Rc<Mesh>,
+    animator: Animator
 }
 
 impl Object {
@@ -16,15 +17,49 @@ impl Object {
         Object {
             model: Model::default(),
             mesh: mesh,
+            animator: Animator::default()
         }
     }
+
+    /// Blends over to the clip of the mesh within the seconds of blend_duration, unless it is already playing.
+    pub fn play_animation(&mut self, clip: &str, blend_duration: Float) {
+        self.animator.play(&self.mesh, clip, blend_duration);
+    }
+
+    /// Advances the animation by one simulation step of the given seconds.
+    pub fn tick_animation(&mut self, step: Float) {
+        self.animator.tick(step);
+    }
+
+    /// Poses the mesh at alpha between the last two simulation steps.
+    pub fn update_animation(&mut self, alpha: Float) {
+        self.animator.update_pose(&self.mesh, alpha);
+    }
+
+    pub fn get_mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    /// The bounds of the mesh in world space, None for meshes without triangles.
+    pub fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.mesh.get_bounding_box().map(|b| b.transform(&self.model.get_matrix()))
+    }
+
+    /// Objects without bounds count as visible.
+    pub fn is_visible(&self, frustum: &Frustum) -> bool {
+        self.get_bounding_box().map_or(true, |b| frustum.intersects(&b))
+    }
+
+    /// The placement and pose of the object for drawing it instanced, see `ObjectBatch`.
+    pub fn create_instance(&self) -> MeshInstance<'_> {
+        MeshInstance::new(self.model.get_matrix(), self.animator.get_pose())
+    }
 }
 
 impl Renderable for Object {
     fn render(&self, camera: &Camera, shader: &ShaderProgram) -> Result<(), GraphicsError> {
         let mvp = camera.create_mvp_matrix(&self.model);
-        shader.set_mvp_matrix(&mvp)?;
-        self.mesh.render()?;
+        self.mesh.render(shader, &mvp, self.animator.get_pose())?;
         Ok(()) 
     }
 }
@@ -0,0 +1,41 @@
+use std::ptr;
+
+use graphics::{ Mesh, ShaderProgram, GraphicsError, mesh::{ MeshInstance, InstanceBuffer } };
+use world::{ Camera, Object };
+
+/// The objects of one frame grouped by their mesh, every part of a mesh is drawn once for all objects sharing it.
+/// Meshes keep the order they were first added in, so frames render the same way every time.
+pub struct ObjectBatch<'a> {
+    groups: Vec<(&'a Mesh, Vec<MeshInstance<'a>>)>
+}
+
+impl<'a> ObjectBatch<'a> {
+    pub fn add(&mut self, object: &'a Object) {
+        let mesh: &'a Mesh = object.get_mesh();
+        let instance = object.create_instance();
+        match self.groups.iter_mut().find(|(m, _)| ptr::eq(*m, mesh)) {
+            Some((_, instances)) => instances.push(instance),
+            None => self.groups.push((mesh, vec![instance]))
+        }
+    }
+
+    pub fn get_mesh_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn render(&self, camera: &Camera, shader: &ShaderProgram, instance_buffer: &InstanceBuffer) -> Result<(), GraphicsError> {
+        let view_projection = camera.create_view_projection_matrix();
+        for (mesh, instances) in self.groups.iter() {
+            mesh.render_instanced(shader, &view_projection, instances, instance_buffer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Default for ObjectBatch<'a> {
+    fn default() -> Self {
+        Self {
+            groups: Vec::new()
+        }
+    }
+}
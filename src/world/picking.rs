@@ -0,0 +1,156 @@
+use glm::Vector3;
+
+use world::Direction;
+use utility::Float;
+
+/// Ray in world coordinates, the direction is normalized.
+#[derive(Copy, Clone)]
+pub struct Ray {
+    origin: Vector3<Float>,
+    direction: Vector3<Float>
+}
+
+/// A field hit by a ray, with the face the ray entered through.
+#[derive(Copy, Clone)]
+pub struct PickResult {
+    pos: [i32; 3],
+    face: Direction,
+    distance: Float
+}
+
+impl Ray {
+    pub fn new(origin: Vector3<Float>, direction: Vector3<Float>) -> Self {
+        Self {
+            origin: origin,
+            direction: direction
+        }
+    }
+
+    pub fn get_origin(&self) -> Vector3<Float> {
+        self.origin
+    }
+
+    pub fn get_direction(&self) -> Vector3<Float> {
+        self.direction
+    }
+
+    pub fn get_point(&self, distance: Float) -> Vector3<Float> {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the distances the ray enters and leaves the box, if it hits it.
+    pub fn intersect_box(&self, min: Vector3<Float>, max: Vector3<Float>) -> Option<(Float, Float)> {
+        let mut t_min: Float = 0.;
+        let mut t_max = Float::INFINITY;
+        for axis in 0..3 {
+            if self.direction[axis].abs() < 1e-6 {
+                if self.origin[axis] < min[axis] || self.origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min[axis] - self.origin[axis]) / self.direction[axis];
+            let t1 = (max[axis] - self.origin[axis]) / self.direction[axis];
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+        match t_min <= t_max {
+            true => Some((t_min, t_max)),
+            false => None
+        }
+    }
+}
+
+impl PickResult {
+    pub fn get_pos(&self) -> [i32; 3] {
+        self.pos
+    }
+
+    pub fn get_face(&self) -> Direction {
+        self.face
+    }
+
+    pub fn get_distance(&self) -> Float {
+        self.distance
+    }
+}
+
+/// Walks the fields along the ray (Amanatides & Woo), inside the given box of field positions.
+/// Fields are centered on their position, so a field spans half a unit in every direction.
+pub fn cast_ray<F: Fn([i32; 3]) -> bool>(ray: &Ray, bounds: [[i32; 3]; 2], is_solid: F) -> Option<PickResult> {
+    let min = Vector3::new(bounds[0][0] as Float - 0.5, bounds[0][1] as Float - 0.5, bounds[0][2] as Float - 0.5);
+    let max = Vector3::new(bounds[1][0] as Float + 0.5, bounds[1][1] as Float + 0.5, bounds[1][2] as Float + 0.5);
+    let (t_enter, t_exit) = ray.intersect_box(min, max)?;
+
+    let start = ray.get_point(t_enter);
+    let mut pos = [0i32; 3];
+    let mut step = [0i32; 3];
+    let mut t_next = [Float::INFINITY; 3];
+    let mut t_delta = [Float::INFINITY; 3];
+    for axis in 0..3 {
+        pos[axis] = (start[axis].round() as i32).max(bounds[0][axis]).min(bounds[1][axis]);
+        let dir = ray.get_direction()[axis];
+        if dir > 0. {
+            step[axis] = 1;
+            t_delta[axis] = 1. / dir;
+            t_next[axis] = t_enter + (pos[axis] as Float + 0.5 - start[axis]) / dir;
+        } else if dir < 0. {
+            step[axis] = -1;
+            t_delta[axis] = -1. / dir;
+            t_next[axis] = t_enter + (pos[axis] as Float - 0.5 - start[axis]) / dir;
+        }
+    }
+
+    // the first field is entered through the face of the box the ray hits first
+    let mut face = get_entry_face(start, min, max, ray.get_direction());
+    let mut t = t_enter;
+    while t <= t_exit {
+        if is_solid(pos) {
+            return Some(PickResult {
+                pos: pos,
+                face: face,
+                distance: t
+            });
+        }
+        let axis = match (t_next[0] < t_next[1], t_next[0] < t_next[2], t_next[1] < t_next[2]) {
+            (true, true, _) => 0,
+            (false, _, true) => 1,
+            _ => 2
+        };
+        t = t_next[axis];
+        t_next[axis] += t_delta[axis];
+        pos[axis] += step[axis];
+        face = get_face(axis, step[axis]);
+        if pos[axis] < bounds[0][axis] || pos[axis] > bounds[1][axis] {
+            break;
+        }
+    }
+    None
+}
+
+/// The face of the entered field, which points against the step.
+fn get_face(axis: usize, step: i32) -> Direction {
+    match (axis, step > 0) {
+        (0, true) => Direction::West,
+        (0, false) => Direction::East,
+        (1, true) => Direction::North,
+        (1, false) => Direction::South,
+        (_, true) => Direction::Down,
+        (_, false) => Direction::Up
+    }
+}
+
+fn get_entry_face(point: Vector3<Float>, min: Vector3<Float>, max: Vector3<Float>, direction: Vector3<Float>) -> Direction {
+    let mut best_axis = 2;
+    let mut best_distance = Float::INFINITY;
+    for axis in 0..3 {
+        let plane = if direction[axis] > 0. { min[axis] } else { max[axis] };
+        let distance = (point[axis] - plane).abs();
+        if direction[axis] != 0. && distance < best_distance {
+            best_distance = distance;
+            best_axis = axis;
+        }
+    }
+    let step = if direction[best_axis] > 0. { 1 } else { -1 };
+    get_face(best_axis, step)
+}
@@ -0,0 +1,24 @@
+use std::fmt;
+
+/// Counts of the last rendered frame, for profiling the frustum culling.
+/// Layers above the top visible level are not counted, they are cut off rather than culled.
+#[derive(Clone, Debug, Default)]
+pub struct RenderStats {
+    pub visible_layers: usize,
+    pub culled_layers: usize,
+    /// Chunks of culled layers count as culled as well.
+    pub visible_chunks: usize,
+    pub culled_chunks: usize,
+    pub visible_objects: usize,
+    pub culled_objects: usize,
+    /// Meshes the visible objects were batched into, each costs one instanced draw per part.
+    pub object_meshes: usize
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "layers: visible = {}, culled = {}", self.visible_layers, self.culled_layers)?;
+        writeln!(f, "chunks: visible = {}, culled = {}", self.visible_chunks, self.culled_chunks)?;
+        write!(f, "objects: visible = {}, culled = {}, meshes = {}", self.visible_objects, self.culled_objects, self.object_meshes)
+    }
+}
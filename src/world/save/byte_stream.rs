@@ -0,0 +1,111 @@
+use world::WorldError;
+
+/// Writes values in little endian byte order.
+pub struct ByteWriter {
+    bytes: Vec<u8>
+}
+
+/// Reads values written by a `ByteWriter`, running past the end is an invalid save.
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl ByteWriter {
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        for i in 0..4 {
+            self.bytes.push((value >> (i * 8)) as u8);
+        }
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Writes the length first, so the reader knows where the data ends.
+    pub fn write_sized_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.write_bytes(bytes);
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        self.write_sized_bytes(value.as_bytes());
+    }
+
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl Default for ByteWriter {
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new()
+        }
+    }
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes: bytes,
+            pos: 0
+        }
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, WorldError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, WorldError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(bytes.iter().enumerate().fold(0, |value, (i, b)| value | (*b as u32) << (i * 8)))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, WorldError> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, WorldError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], WorldError> {
+        if self.bytes.len() - self.pos < count {
+            return Err(WorldError::InvalidSave(format!("unexpected end of data at byte {}", self.pos)));
+        }
+        let bytes = &self.bytes[self.pos..self.pos + count];
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    pub fn read_sized_bytes(&mut self) -> Result<&'a [u8], WorldError> {
+        let count = self.read_u32()? as usize;
+        self.read_bytes(count)
+    }
+
+    pub fn read_string(&mut self) -> Result<String, WorldError> {
+        let bytes = self.read_sized_bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| WorldError::InvalidSave("string not utf-8".to_string()))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
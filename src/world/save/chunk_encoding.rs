@@ -0,0 +1,126 @@
+use world::{ Direction, WorldError };
+use world::layer::{ Chunk, Field, FieldType, FieldMaterial, CHUNK_SIZE };
+use super::{ ByteWriter, ByteReader };
+
+const MAX_RUN_LENGTH: usize = 255;
+
+/// Field codes are written as pairs of type and material code, the type code 0 marks an empty field.
+type FieldCode = (u8, u8);
+
+const EMPTY_FIELD: FieldCode = (0, 0);
+
+/// Run length encodes the fields of a chunk as triples of run length, type code and material code.
+pub fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+    let mut writer = ByteWriter::default();
+    let mut run: Option<(FieldCode, usize)> = None;
+    for field in chunk.get_fields() {
+        let code = encode_field(field.as_ref());
+        run = match run {
+            Some((run_code, length)) if run_code == code && length < MAX_RUN_LENGTH => Some((run_code, length + 1)),
+            Some((run_code, length)) => {
+                write_run(&mut writer, run_code, length);
+                Some((code, 1))
+            },
+            None => Some((code, 1))
+        };
+    }
+    if let Some((run_code, length)) = run {
+        write_run(&mut writer, run_code, length);
+    }
+    writer.into_bytes()
+}
+
+pub fn decode_chunk(bytes: &[u8]) -> Result<Chunk, WorldError> {
+    let field_count = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+    let mut reader = ByteReader::new(bytes);
+    let mut fields = Vec::with_capacity(field_count);
+    while !reader.is_finished() {
+        let length = reader.read_u8()? as usize;
+        let code = (reader.read_u8()?, reader.read_u8()?);
+        if length == 0 || fields.len() + length > field_count {
+            return Err(WorldError::InvalidSave(format!("invalid chunk run length {}", length)));
+        }
+        let field = decode_field(code)?;
+        fields.extend((0..length).map(|_| field));
+    }
+    if fields.len() != field_count {
+        return Err(WorldError::InvalidSave(format!("chunk has {} fields instead of {}", fields.len(), field_count)));
+    }
+    Ok(Chunk::from_fields(fields))
+}
+
+fn write_run(writer: &mut ByteWriter, code: FieldCode, length: usize) {
+    writer.write_u8(length as u8);
+    writer.write_u8(code.0);
+    writer.write_u8(code.1);
+}
+
+fn encode_field(field: Option<&Field>) -> FieldCode {
+    match field {
+        Some(field) => (encode_type(field.get_type()), encode_material(field.get_material())),
+        None => EMPTY_FIELD
+    }
+}
+
+fn decode_field(code: FieldCode) -> Result<Option<Field>, WorldError> {
+    if code == EMPTY_FIELD {
+        return Ok(None);
+    }
+    let mut field = Field::default();
+    field.set_type(decode_type(code.0)?);
+    field.set_material(decode_material(code.1)?);
+    Ok(Some(field))
+}
+
+/// Codes are fixed, so reordering the enums does not break existing saves.
+fn encode_type(field_type: FieldType) -> u8 {
+    match field_type {
+        FieldType::CUBE => 1,
+        FieldType::SLOPE(Direction::North) => 2,
+        FieldType::SLOPE(Direction::East) => 3,
+        FieldType::SLOPE(Direction::South) => 4,
+        FieldType::SLOPE(Direction::West) => 5,
+        FieldType::SLOPE(Direction::Up) => 6,
+        FieldType::SLOPE(Direction::Down) => 7
+    }
+}
+
+fn decode_type(code: u8) -> Result<FieldType, WorldError> {
+    match code {
+        1 => Ok(FieldType::CUBE),
+        2 => Ok(FieldType::SLOPE(Direction::North)),
+        3 => Ok(FieldType::SLOPE(Direction::East)),
+        4 => Ok(FieldType::SLOPE(Direction::South)),
+        5 => Ok(FieldType::SLOPE(Direction::West)),
+        6 => Ok(FieldType::SLOPE(Direction::Up)),
+        7 => Ok(FieldType::SLOPE(Direction::Down)),
+        _ => Err(WorldError::InvalidSave(format!("unknown field type code {}", code)))
+    }
+}
+
+pub fn encode_material(material: FieldMaterial) -> u8 {
+    match material {
+        FieldMaterial::MUD => 0,
+        FieldMaterial::GRASS => 1,
+        FieldMaterial::STONE => 2,
+        FieldMaterial::SANDSTONE => 3,
+        FieldMaterial::GRANITE => 4,
+        FieldMaterial::COAL => 5,
+        FieldMaterial::IRON => 6,
+        FieldMaterial::GOLD => 7
+    }
+}
+
+pub fn decode_material(code: u8) -> Result<FieldMaterial, WorldError> {
+    match code {
+        0 => Ok(FieldMaterial::MUD),
+        1 => Ok(FieldMaterial::GRASS),
+        2 => Ok(FieldMaterial::STONE),
+        3 => Ok(FieldMaterial::SANDSTONE),
+        4 => Ok(FieldMaterial::GRANITE),
+        5 => Ok(FieldMaterial::COAL),
+        6 => Ok(FieldMaterial::IRON),
+        7 => Ok(FieldMaterial::GOLD),
+        _ => Err(WorldError::InvalidSave(format!("unknown field material code {}", code)))
+    }
+}
@@ -0,0 +1,178 @@
+use world::{ WorldError, Designations, DesignationType };
+use world::entity::{ Dwarf, Needs };
+use world::job::{ Job, JobKind, JobPriority, JobScheduler, Skill, Skills };
+use world::traits::Worker;
+use super::{ ByteWriter, ByteReader };
+use super::chunk_encoding::{ encode_material, decode_material };
+
+const SKILLS: [Skill; 3] = [Skill::Mining, Skill::Building, Skill::Hauling];
+
+/// The entities of a save. Jobs come back queued, so the dwarfs claim them again.
+pub struct SavedEntities {
+    pub dwarfs: Vec<Dwarf>,
+    pub designations: Designations,
+    pub jobs: Vec<Job>
+}
+
+/// Layout: dwarfs with id, position, needs and skills, designations with position and type,
+/// jobs with id, kind, target and priority.
+pub fn encode_entities(dwarfs: &[Dwarf], designations: &Designations, job_scheduler: &JobScheduler) -> Vec<u8> {
+    let mut writer = ByteWriter::default();
+    writer.write_u32(dwarfs.len() as u32);
+    for dwarf in dwarfs {
+        writer.write_u32(dwarf.get_id());
+        write_position(&mut writer, dwarf.get_position());
+        let needs = dwarf.get_needs();
+        writer.write_f32(needs.get_hunger());
+        writer.write_f32(needs.get_thirst());
+        writer.write_f32(needs.get_rest());
+        let skills: Vec<(Skill, u8)> = SKILLS.iter()
+            .filter_map(|skill| dwarf.get_skills().get_level(*skill).map(|level| (*skill, level)))
+            .collect();
+        writer.write_u8(skills.len() as u8);
+        for (skill, level) in skills {
+            writer.write_u8(encode_skill(skill));
+            writer.write_u8(level);
+        }
+    }
+
+    writer.write_u32(designations.len() as u32);
+    for (pos, designation_type) in designations.iter() {
+        write_position(&mut writer, pos);
+        writer.write_u8(encode_designation_type(designation_type));
+    }
+
+    let jobs: Vec<&Job> = job_scheduler.get_jobs().collect();
+    writer.write_u32(jobs.len() as u32);
+    for job in jobs {
+        writer.write_u32(job.get_id());
+        match job.get_kind() {
+            JobKind::Dig(designation_type) => {
+                writer.write_u8(0);
+                writer.write_u8(encode_designation_type(designation_type));
+            },
+            JobKind::Build(material) => {
+                writer.write_u8(1);
+                writer.write_u8(encode_material(material));
+            },
+            JobKind::Haul { destination } => {
+                writer.write_u8(2);
+                write_position(&mut writer, destination);
+            }
+        }
+        write_position(&mut writer, job.get_target());
+        writer.write_u8(encode_priority(job.get_priority()));
+    }
+    writer.into_bytes()
+}
+
+pub fn decode_entities(bytes: &[u8]) -> Result<SavedEntities, WorldError> {
+    let mut reader = ByteReader::new(bytes);
+    let dwarf_count = reader.read_u32()?;
+    let mut dwarfs = Vec::new();
+    for _ in 0..dwarf_count {
+        let id = reader.read_u32()?;
+        let mut dwarf = Dwarf::new(id, read_position(&mut reader)?);
+        dwarf.set_needs(Needs::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?));
+        let mut skills = Skills::default();
+        for _ in 0..reader.read_u8()? {
+            let skill = decode_skill(reader.read_u8()?)?;
+            skills.set_level(skill, reader.read_u8()?);
+        }
+        *dwarf.get_skills_mut() = skills;
+        dwarfs.push(dwarf);
+    }
+
+    let designation_count = reader.read_u32()?;
+    let mut designations = Designations::default();
+    for _ in 0..designation_count {
+        let pos = read_position(&mut reader)?;
+        designations.designate(pos, decode_designation_type(reader.read_u8()?)?);
+    }
+
+    let job_count = reader.read_u32()?;
+    let mut jobs = Vec::new();
+    for _ in 0..job_count {
+        let id = reader.read_u32()?;
+        let kind = match reader.read_u8()? {
+            0 => JobKind::Dig(decode_designation_type(reader.read_u8()?)?),
+            1 => JobKind::Build(decode_material(reader.read_u8()?)?),
+            2 => JobKind::Haul { destination: read_position(&mut reader)? },
+            code => return Err(WorldError::InvalidSave(format!("unknown job kind code {}", code)))
+        };
+        let target = read_position(&mut reader)?;
+        let priority = decode_priority(reader.read_u8()?)?;
+        jobs.push(Job::new(id, kind, target, priority));
+    }
+    if !reader.is_finished() {
+        return Err(WorldError::InvalidSave("trailing entity data".to_string()));
+    }
+
+    Ok(SavedEntities {
+        dwarfs: dwarfs,
+        designations: designations,
+        jobs: jobs
+    })
+}
+
+fn write_position(writer: &mut ByteWriter, pos: [i32; 3]) {
+    pos.iter().for_each(|v| writer.write_i32(*v));
+}
+
+fn read_position(reader: &mut ByteReader) -> Result<[i32; 3], WorldError> {
+    Ok([reader.read_i32()?, reader.read_i32()?, reader.read_i32()?])
+}
+
+/// Codes are fixed like the field codes, see `encode_type`.
+fn encode_skill(skill: Skill) -> u8 {
+    match skill {
+        Skill::Mining => 0,
+        Skill::Building => 1,
+        Skill::Hauling => 2
+    }
+}
+
+fn decode_skill(code: u8) -> Result<Skill, WorldError> {
+    match code {
+        0 => Ok(Skill::Mining),
+        1 => Ok(Skill::Building),
+        2 => Ok(Skill::Hauling),
+        _ => Err(WorldError::InvalidSave(format!("unknown skill code {}", code)))
+    }
+}
+
+fn encode_designation_type(designation_type: DesignationType) -> u8 {
+    match designation_type {
+        DesignationType::Dig => 0,
+        DesignationType::Channel => 1,
+        DesignationType::Ramp => 2
+    }
+}
+
+fn decode_designation_type(code: u8) -> Result<DesignationType, WorldError> {
+    match code {
+        0 => Ok(DesignationType::Dig),
+        1 => Ok(DesignationType::Channel),
+        2 => Ok(DesignationType::Ramp),
+        _ => Err(WorldError::InvalidSave(format!("unknown designation type code {}", code)))
+    }
+}
+
+fn encode_priority(priority: JobPriority) -> u8 {
+    match priority {
+        JobPriority::Low => 0,
+        JobPriority::Normal => 1,
+        JobPriority::High => 2,
+        JobPriority::Urgent => 3
+    }
+}
+
+fn decode_priority(code: u8) -> Result<JobPriority, WorldError> {
+    match code {
+        0 => Ok(JobPriority::Low),
+        1 => Ok(JobPriority::Normal),
+        2 => Ok(JobPriority::High),
+        3 => Ok(JobPriority::Urgent),
+        _ => Err(WorldError::InvalidSave(format!("unknown job priority code {}", code)))
+    }
+}
@@ -0,0 +1,9 @@
+pub mod byte_stream;
+pub mod chunk_encoding;
+pub mod entity_encoding;
+pub mod save_file;
+
+pub use self::byte_stream::{ ByteWriter, ByteReader };
+pub use self::chunk_encoding::{ encode_chunk, decode_chunk };
+pub use self::entity_encoding::{ encode_entities, decode_entities };
+pub use self::save_file::{ SaveData, SavedCamera, write_save, read_save, SAVE_VERSION };
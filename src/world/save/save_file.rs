@@ -0,0 +1,321 @@
+use std::fs;
+use glm::Vector3;
+
+use graphics::Projection;
+use world::{ Camera, WorldError, WorldConfig };
+use world::world_config::{ NoiseConfig, GeologyConfig };
+use world::layer::ChunkMap;
+use utility::{ Float, FileError, traits::{ Translatable, Rotatable } };
+use super::{ ByteWriter, ByteReader, encode_chunk, decode_chunk };
+
+const SAVE_MAGIC: &[u8; 4] = b"DWSV";
+pub const SAVE_VERSION: u32 = 3;
+
+const PROJECTION_PERSPECTIVE: u8 = 0;
+const PROJECTION_ORTHOGRAPHIC: u8 = 1;
+
+lazy_static! {
+    static ref CRC_TABLE: [u32; 256] = create_crc_table();
+}
+
+pub struct SavedCamera {
+    pub translation: Vector3<Float>,
+    pub rotation: Vector3<Float>,
+    pub projection: Projection
+}
+
+impl SavedCamera {
+    pub fn from_camera(camera: &Camera) -> Self {
+        Self {
+            translation: camera.get_translation(),
+            rotation: camera.get_rotation(),
+            projection: camera.get_projection()
+        }
+    }
+
+    pub fn apply_to(&self, camera: &mut Camera) {
+        camera.set_translation(self.translation);
+        camera.set_rotation(self.rotation);
+        camera.set_projection(self.projection);
+    }
+}
+
+pub struct SaveData {
+    pub config: WorldConfig,
+    pub camera: SavedCamera,
+    pub layers: Vec<(i32, ChunkMap)>,
+    /// Encoded by `encode_entities`.
+    pub entities: Vec<u8>
+}
+
+/// Layout: magic, version, config, camera, layers with their run length encoded chunks,
+/// entities and a crc32 checksum of everything before it.
+pub fn write_save(save_path: &str, config: &WorldConfig, camera: &SavedCamera, layers: &[(i32, &ChunkMap)], entities: &[u8]) -> Result<(), WorldError> {
+    let mut writer = ByteWriter::default();
+    writer.write_bytes(SAVE_MAGIC);
+    writer.write_u32(SAVE_VERSION);
+    write_config(&mut writer, config);
+    write_camera(&mut writer, camera);
+
+    writer.write_u32(layers.len() as u32);
+    for (level, chunks) in layers.iter() {
+        let chunk_indices = chunks.get_chunk_indices();
+        writer.write_i32(*level);
+        writer.write_u32(chunk_indices.len() as u32);
+        for chunk_index in chunk_indices {
+            if let Some(chunk) = chunks.get_chunk(chunk_index) {
+                writer.write_i32(chunk_index[0]);
+                writer.write_i32(chunk_index[1]);
+                writer.write_sized_bytes(&encode_chunk(chunk));
+            }
+        }
+    }
+    writer.write_sized_bytes(entities);
+
+    let checksum = calculate_crc32(writer.get_bytes());
+    writer.write_u32(checksum);
+    let bytes = writer.into_bytes();
+    fs::write(save_path, &bytes).map_err(FileError::from)?;
+    info!("Saved world to '{}', layers = {}, size = {} bytes", save_path, layers.len(), bytes.len());
+    Ok(())
+}
+
+pub fn read_save(save_path: &str) -> Result<SaveData, WorldError> {
+    let bytes = fs::read(save_path).map_err(FileError::from)?;
+    if bytes.len() < SAVE_MAGIC.len() + 8 || &bytes[..SAVE_MAGIC.len()] != SAVE_MAGIC {
+        return Err(WorldError::InvalidSave(format!("'{}' is no save file", save_path)));
+    }
+    let (content, checksum_bytes) = bytes.split_at(bytes.len() - 4);
+    let checksum = ByteReader::new(checksum_bytes).read_u32()?;
+    if calculate_crc32(content) != checksum {
+        return Err(WorldError::SaveChecksumMismatch);
+    }
+
+    let mut reader = ByteReader::new(&content[SAVE_MAGIC.len()..]);
+    let version = reader.read_u32()?;
+    if version != SAVE_VERSION {
+        return Err(WorldError::UnsupportedSaveVersion(version));
+    }
+    let config = read_config(&mut reader)?;
+    let camera = read_camera(&mut reader)?;
+
+    let layer_count = reader.read_u32()?;
+    let mut layers = Vec::new();
+    for _ in 0..layer_count {
+        let level = reader.read_i32()?;
+        let chunk_count = reader.read_u32()?;
+        let mut chunks = ChunkMap::default();
+        for _ in 0..chunk_count {
+            let chunk_index = [reader.read_i32()?, reader.read_i32()?];
+            chunks.insert_chunk(chunk_index, decode_chunk(reader.read_sized_bytes()?)?);
+        }
+        layers.push((level, chunks));
+    }
+    let entities = reader.read_sized_bytes()?.to_vec();
+    if !reader.is_finished() {
+        return Err(WorldError::InvalidSave("trailing data".to_string()));
+    }
+    info!("Read save '{}', version = {}, layers = {}", save_path, version, layers.len());
+
+    Ok(SaveData {
+        config: config,
+        camera: camera,
+        layers: layers,
+        entities: entities
+    })
+}
+
+fn write_config(writer: &mut ByteWriter, config: &WorldConfig) {
+    writer.write_string(config.get_seed());
+    writer.write_i32(config.get_layer_size()[0]);
+    writer.write_i32(config.get_layer_size()[1]);
+    writer.write_i32(config.get_top_level());
+    write_noise_config(writer, config.get_height_noise());
+    let geology = config.get_geology();
+    write_noise_config(writer, geology.get_cave_noise());
+    writer.write_f32(geology.get_cave_threshold());
+    geology.get_strata_bottoms().iter().for_each(|v| writer.write_f32(*v));
+    geology.get_ore_thresholds().iter().for_each(|v| writer.write_f32(*v));
+}
+
+fn read_config(reader: &mut ByteReader) -> Result<WorldConfig, WorldError> {
+    let seed = reader.read_string()?;
+    let layer_size = [reader.read_i32()?, reader.read_i32()?];
+    let top_level = reader.read_i32()?;
+    let noise = read_noise_config(reader)?;
+    let geology = GeologyConfig::new(
+        read_noise_config(reader)?,
+        reader.read_f32()?,
+        [reader.read_f32()?, reader.read_f32()?],
+        [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?]
+    );
+    let mut config = WorldConfig::new(&seed, layer_size, top_level, noise);
+    config.set_geology(geology);
+    config.validate()?;
+    Ok(config)
+}
+
+fn write_noise_config(writer: &mut ByteWriter, noise: &NoiseConfig) {
+    writer.write_u8(noise.get_octaves());
+    writer.write_f32(noise.get_scale());
+    writer.write_f32(noise.get_roughness());
+    writer.write_f32(noise.get_range().0);
+    writer.write_f32(noise.get_range().1);
+}
+
+fn read_noise_config(reader: &mut ByteReader) -> Result<NoiseConfig, WorldError> {
+    Ok(NoiseConfig::new(
+        reader.read_u8()?,
+        reader.read_f32()?,
+        reader.read_f32()?,
+        (reader.read_f32()?, reader.read_f32()?)
+    ))
+}
+
+fn write_camera(writer: &mut ByteWriter, camera: &SavedCamera) {
+    write_vector(writer, camera.translation);
+    write_vector(writer, camera.rotation);
+    match camera.projection {
+        Projection::Perspective { fov, aspect_ratio, near, far } => {
+            writer.write_u8(PROJECTION_PERSPECTIVE);
+            [fov, aspect_ratio, near, far].iter().for_each(|v| writer.write_f32(*v));
+        },
+        Projection::Orthographic { width, aspect_ratio } => {
+            writer.write_u8(PROJECTION_ORTHOGRAPHIC);
+            [width, aspect_ratio].iter().for_each(|v| writer.write_f32(*v));
+        }
+    }
+}
+
+fn read_camera(reader: &mut ByteReader) -> Result<SavedCamera, WorldError> {
+    let translation = read_vector(reader)?;
+    let rotation = read_vector(reader)?;
+    let projection = match reader.read_u8()? {
+        PROJECTION_PERSPECTIVE => Projection::Perspective {
+            fov: reader.read_f32()?,
+            aspect_ratio: reader.read_f32()?,
+            near: reader.read_f32()?,
+            far: reader.read_f32()?
+        },
+        PROJECTION_ORTHOGRAPHIC => Projection::Orthographic {
+            width: reader.read_f32()?,
+            aspect_ratio: reader.read_f32()?
+        },
+        code => return Err(WorldError::InvalidSave(format!("unknown projection code {}", code)))
+    };
+    Ok(SavedCamera {
+        translation: translation,
+        rotation: rotation,
+        projection: projection
+    })
+}
+
+fn write_vector(writer: &mut ByteWriter, v: Vector3<Float>) {
+    writer.write_f32(v.x);
+    writer.write_f32(v.y);
+    writer.write_f32(v.z);
+}
+
+fn read_vector(reader: &mut ByteReader) -> Result<Vector3<Float>, WorldError> {
+    Ok(Vector3::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?))
+}
+
+fn create_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut crc = i as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => 0xEDB8_8320 ^ (crc >> 1),
+                _ => crc >> 1
+            };
+        }
+        *entry = crc;
+    }
+    table
+}
+
+fn calculate_crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, b| CRC_TABLE[((crc ^ *b as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ env, fs };
+
+    use world::{ Camera, WorldConfig, WorldError };
+    use world::world_config::NoiseConfig;
+    use world::layer::ChunkMap;
+    use super::{ SavedCamera, write_save, read_save, calculate_crc32, SAVE_MAGIC };
+
+    /// Writes a save without layers and returns its bytes.
+    fn create_save_bytes(name: &str) -> (String, Vec<u8>) {
+        let path = env::temp_dir().join(format!("dwarfs-{}-{}.sav", name, ::std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let config = WorldConfig::new("save file", [16, 16], 3, NoiseConfig::default());
+        let chunks = ChunkMap::default();
+        write_save(&path, &config, &SavedCamera::from_camera(&Camera::default()), &[(0, &chunks)], &[1, 2, 3]).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        (path, bytes)
+    }
+
+    fn read_modified(path: &str, bytes: &[u8]) -> Result<(), WorldError> {
+        fs::write(path, bytes).unwrap();
+        let result = read_save(path).map(|_| ());
+        fs::remove_file(path).unwrap();
+        result
+    }
+
+    /// Replaces the checksum, so only the change itself can be rejected.
+    fn update_checksum(bytes: &mut Vec<u8>) {
+        let length = bytes.len();
+        let checksum = calculate_crc32(&bytes[..length - 4]);
+        bytes[length - 4..].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    #[test]
+    fn save_is_read_back() {
+        let (path, bytes) = create_save_bytes("read_back");
+        let save_data = read_save(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(save_data.config.get_seed(), "save file");
+        assert_eq!(save_data.config.get_layer_size(), [16, 16]);
+        assert_eq!(save_data.layers.len(), 1);
+        assert_eq!(save_data.entities, vec![1, 2, 3]);
+        assert!(bytes.starts_with(SAVE_MAGIC));
+    }
+
+    #[test]
+    fn wrong_magic_is_rejected() {
+        let (path, mut bytes) = create_save_bytes("magic");
+        bytes[0] = b'X';
+        update_checksum(&mut bytes);
+        match read_modified(&path, &bytes) {
+            Err(WorldError::InvalidSave(_)) => {},
+            other => panic!("expected invalid save, got {:?}", other.err().map(|e| e.to_string()))
+        }
+    }
+
+    #[test]
+    fn wrong_version_is_rejected() {
+        let (path, mut bytes) = create_save_bytes("version");
+        let start = SAVE_MAGIC.len();
+        bytes[start..start + 4].copy_from_slice(&99u32.to_le_bytes());
+        update_checksum(&mut bytes);
+        match read_modified(&path, &bytes) {
+            Err(WorldError::UnsupportedSaveVersion(99)) => {},
+            other => panic!("expected unsupported version, got {:?}", other.err().map(|e| e.to_string()))
+        }
+    }
+
+    #[test]
+    fn bad_checksum_is_rejected() {
+        let (path, mut bytes) = create_save_bytes("checksum");
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xFF;
+        match read_modified(&path, &bytes) {
+            Err(WorldError::SaveChecksumMismatch) => {},
+            other => panic!("expected checksum mismatch, got {:?}", other.err().map(|e| e.to_string()))
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SimSpeed {
+    Paused,
+    Normal,
+    Double,
+    Fast
+}
+
+/// Turns frame times into a number of fixed simulation steps. The time left over is kept
+/// for the next frame and used to interpolate between the last two simulation states.
+pub struct SimClock {
+    step: Duration,
+    speed: SimSpeed,
+    accumulator: Duration,
+    requested_steps: u32,
+    max_steps_per_frame: u32,
+    total_steps: u64
+}
+
+impl SimSpeed {
+    pub fn get_factor(&self) -> u32 {
+        match *self {
+            SimSpeed::Paused => 0,
+            SimSpeed::Normal => 1,
+            SimSpeed::Double => 2,
+            SimSpeed::Fast => 5
+        }
+    }
+}
+
+impl SimClock {
+    pub fn new(step: Duration, max_steps_per_frame: u32) -> Self {
+        debug_assert!(step > Duration::from_millis(0));
+        Self {
+            step: step,
+            speed: SimSpeed::Normal,
+            accumulator: Duration::from_millis(0),
+            requested_steps: 0,
+            max_steps_per_frame: max_steps_per_frame,
+            total_steps: 0
+        }
+    }
+
+    pub fn get_step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn get_speed(&self) -> SimSpeed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: SimSpeed) {
+        info!("Simulation speed = {:?}", speed);
+        self.speed = speed;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.speed {
+            SimSpeed::Paused => self.set_speed(SimSpeed::Normal),
+            _ => self.set_speed(SimSpeed::Paused)
+        }
+    }
+
+    /// Runs a single step on the next advance, only while paused.
+    pub fn request_single_step(&mut self) {
+        match self.speed {
+            SimSpeed::Paused => self.requested_steps += 1,
+            _ => { debug!("Ignoring single step request, simulation is running"); }
+        }
+    }
+
+    pub fn get_total_steps(&self) -> u64 {
+        self.total_steps
+    }
+
+    /// Returns the number of steps to simulate for the passed frame time. If the simulation can't
+    /// keep up, the steps are capped and the remaining time is dropped instead of piling up.
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        let steps = match self.speed {
+            SimSpeed::Paused => {
+                let steps = self.requested_steps;
+                self.requested_steps = 0;
+                steps
+            },
+            speed => {
+                self.accumulator += frame_time * speed.get_factor();
+                let steps = (self.accumulator.as_nanos() / self.step.as_nanos()) as u32;
+                if steps > self.max_steps_per_frame {
+                    warn!("Simulation is lagging behind, dropping {} steps", steps - self.max_steps_per_frame);
+                    self.accumulator = Duration::from_millis(0);
+                    self.max_steps_per_frame
+                } else {
+                    self.accumulator -= self.step * steps;
+                    steps
+                }
+            }
+        };
+        self.total_steps += steps as u64;
+        steps
+    }
+
+    /// How far the time is between the last and the next simulation step, from 0 to 1.
+    pub fn get_alpha(&self) -> f32 {
+        (self.accumulator.as_nanos() as f64 / self.step.as_nanos() as f64) as f32
+    }
+}
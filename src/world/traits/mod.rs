@@ -1,5 +1,7 @@
 pub mod renderable;
 pub mod updatable;
+pub mod worker;
 
 pub use self::renderable::Renderable;
 pub use self::updatable::Updatable;
+pub use self::worker::Worker;
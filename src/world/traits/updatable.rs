@@ -1,4 +1,5 @@
+use std::time::Duration;
 
 pub trait Updatable {
-    fn tick(&mut self, time_passed: u32);
+    fn tick(&mut self, time_passed: Duration);
 }
@@ -0,0 +1,11 @@
+use world::job::{ Job, Skill };
+
+/// Any entity which can claim jobs from the scheduler.
+pub trait Worker {
+    fn get_worker_id(&self) -> u32;
+    fn get_position(&self) -> [i32; 3];
+    fn get_skill_level(&self, skill: Skill) -> Option<u8>;
+    fn is_idle(&self) -> bool;
+    /// Hands a claimed job to the worker together with the path to its first work position.
+    fn assign_job(&mut self, job: Job, path: Vec<[i32; 3]>);
+}
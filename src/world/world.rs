@@ -1,131 +1,447 @@
-use std::collections::BTreeSet;
-use glm::Vector3;
-
-use application::ApplicationError;
-use graphics::{ Projection, Mesh, MeshManager, ShaderProgram, TextureArray, TextureArrayBuilder, GraphicsError };
-use graphics::projection::{ create_default_orthographic, create_default_perspective };
-use graphics::transformation::create_direction;
-use world::{ Object, Camera, Layer, LayerCreator, WorldError, traits::{ Updatable, Renderable } };
-use world::noise::{ Noise, OctavedNoise };
-use world::height_map::{ HeightMap, create_height_map };
-use utility::traits::{ Translatable, Rotatable, Scalable };
-use utility::Float;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
+use world::{ Layer, LayerCreator, WorldError, WorldConfig, WorldStats, Designations, DesignationType, Dwarf, NavGraph, JobScheduler, traits::{ Updatable, Worker } };
+use world::job::{ JobId, JobKind, JobPriority, JobEvent };
+use world::designation::apply_designation;
+use world::entity::terrain::is_cube;
+use world::picking::{ Ray, PickResult, cast_ray };
+use world::layer::{ Field, FieldGenerator };
+use world::layer::ChunkMap;
+use world::save::{ SavedCamera, write_save, read_save, encode_entities, decode_entities };
+use world::height_map::create_height_map;
+
+/// The simulated world with its terrain, dwarfs and jobs. It needs no window or GL context,
+/// the graphics are kept by a `WorldRenderer` following the world.
 pub struct World {
-    texture_array: TextureArray,
-    camera: Camera,
-    mesh_manager: MeshManager,
+    config: WorldConfig,
     top_level: i32,
     layer_creator: LayerCreator,
-    layers: BTreeSet<Layer>,
-    test_object: Object
+    layers: BTreeMap<i32, Layer>,
+    /// Levels added since the renderer last took them, so it can mesh them.
+    new_layers: Vec<i32>,
+    designations: Designations,
+    job_scheduler: JobScheduler,
+    nav_graph: NavGraph,
+    dwarfs: Vec<Dwarf>,
+    /// Set once the navigation graph has been built and the dwarfs spawned after all levels were created.
+    populated: bool,
+    tick_count: u64
 }
 
-const TEXTURE_LAYER_MUD: i32 = 0;
-
-const TEXTURES: [[i32; 3]; 1] = [
-    [0, 0, TEXTURE_LAYER_MUD]
-];
+const BOTTOM_LEVEL: i32 = -5;
+/// Length of a simulation step, every `tick` advances the world by one of them.
+pub const SIM_STEP: Duration = Duration::from_millis(100);
+const DWARF_COUNT: u32 = 7;
+const DWARF_SPAWN_RADIUS: i32 = 4;
+/// A completed job changes fields at most this far away, which includes turned slopes and channel ramps.
+const JOB_REACH: i32 = 2;
 
 impl World {
-    pub fn new(top_level: i32, layer_size: [i32; 2]) -> Result<World, WorldError> {
-        debug_assert!(top_level > 0);
-        debug_assert!(layer_size[0] > 0 && layer_size[1] > 0);
-        let texture_array = TextureArrayBuilder::new("resources/atlas.png", [32, 32])
-            .add_texture([0, 0, 0])
-            .finish()?;
-
-        let mut height_noise = OctavedNoise::default();
-        height_noise.set_octaves(4);
-        height_noise.set_scale(8e-3);
-        height_noise.set_roughness(1e+3);
-        height_noise.set_range((0., 5.));
-        let height_map = create_height_map(layer_size, &height_noise);
+    pub fn new(config: &WorldConfig) -> Result<World, WorldError> {
+        let mut world = Self::create(config)?;
+        for level in BOTTOM_LEVEL..world.top_level {
+            world.request_layer_creation(level);
+        }
+        Ok(world)
+    }
 
-        let mut mesh_manager = MeshManager::default();
-        mesh_manager.add_mesh(Mesh::from_obj("resources/obj/test.obj")?, "test");
+    /// Restores a saved world, levels missing in the save are generated again from the seed.
+    /// Saved dwarfs and jobs wait for those levels before the simulation continues.
+    /// The camera of the save is returned for the renderer, the world itself has none.
+    pub fn load(save_path: &str) -> Result<(World, SavedCamera), WorldError> {
+        let save_data = read_save(save_path)?;
+        let entities = decode_entities(&save_data.entities)?;
+        let mut world = Self::create(&save_data.config)?;
+        world.dwarfs = entities.dwarfs;
+        world.designations = entities.designations;
+        for job in entities.jobs {
+            if !world.job_scheduler.restore_job(job) {
+                warn!("Dropped saved job with a duplicate id or target");
+            }
+        }
 
-        let mut test_object = Object::new(mesh_manager.get_mesh_rc("test")?);
-        test_object.set_translation(Vector3::new(-1., -1., 1.));
+        let layer_size = world.config.get_layer_size();
+        for (level, chunks) in save_data.layers {
+            world.layers.insert(level, Layer::new(level, layer_size, chunks));
+            world.new_layers.push(level);
+        }
+        for level in BOTTOM_LEVEL..world.top_level {
+            if !world.layers.contains_key(&level) {
+                world.request_layer_creation(level);
+            }
+        }
+        Ok((world, save_data.camera))
+    }
 
-        let camera = Camera::default();
-        let cam_dir = create_direction(camera.get_rotation());
-        info!("Camera direction = {:.2}/{:.2}/{:.2}", cam_dir.x, cam_dir.y, cam_dir.z);
+    /// Only finished layers are saved, the camera is the one of the renderer.
+    /// Jobs in progress are saved as queued ones and the dwarfs without them.
+    pub fn save(&self, save_path: &str, camera: &SavedCamera) -> Result<(), WorldError> {
+        let layers: Vec<(i32, &ChunkMap)> = self.layers.iter()
+            .map(|(level, layer)| (*level, layer.get_chunks()))
+            .collect();
+        let entities = encode_entities(&self.dwarfs, &self.designations, &self.job_scheduler);
+        write_save(save_path, &self.config, camera, &layers, &entities)
+    }
 
-        let layer_creator = LayerCreator::new(layer_size, height_map, cam_dir)?;
+    fn create(config: &WorldConfig) -> Result<World, WorldError> {
+        let top_level = config.get_top_level();
+        let layer_size = config.get_layer_size();
+        debug_assert!(top_level > 0);
+        debug_assert!(layer_size[0] > 0 && layer_size[1] > 0);
+        info!("Creating world, seed = '{}', layer size = {}x{}, top level = {}", config.get_seed(), layer_size[0], layer_size[1], top_level);
+        let height_map = create_height_map(config);
+        let mut layer_creator = LayerCreator::new(FieldGenerator::new(config, height_map))?;
+        layer_creator.set_focus_level(top_level - 1);
 
-        let mut world = World {
-            texture_array: texture_array,
-            camera: camera,
-            mesh_manager: mesh_manager,
+        let world = World {
+            config: config.clone(),
             top_level: top_level,
             layer_creator: layer_creator,
-            layers: BTreeSet::new(),
-            test_object: test_object
+            layers: BTreeMap::new(),
+            new_layers: Vec::new(),
+            designations: Designations::default(),
+            job_scheduler: JobScheduler::default(),
+            nav_graph: NavGraph::default(),
+            dwarfs: Vec::new(),
+            populated: false,
+            tick_count: 0
         };
+        Ok(world)
+    }
 
-        for level in top_level..-5 {
-            world.request_layer_creation(level);
-        }
+    pub fn get_config(&self) -> &WorldConfig {
+        &self.config
+    }
 
-        Ok(world)
+    pub fn get_top_level(&self) -> i32 {
+        self.top_level
+    }
+
+    /// True once every requested level has been created.
+    pub fn is_generation_finished(&self) -> bool {
+        self.layer_creator.get_pending_count() == 0
     }
 
-    pub fn move_camera(&mut self, mut offset: Vector3<Float>) {
-        let curr_height = self.camera.get_translation().z;
-        match self.camera.get_projection() {
-            Projection::Orthographic { .. } if curr_height > 0. => { offset.z = -curr_height; },
-            Projection::Orthographic { .. } if curr_height + offset.z > 0. => { offset.z = 0.; },
-            _ => {}
+    pub fn get_layers(&self) -> &BTreeMap<i32, Layer> {
+        &self.layers
+    }
+
+    /// Returns the levels added since the last call, finished by the layer workers or loaded.
+    pub fn take_new_layers(&mut self) -> Vec<i32> {
+        self.new_layers.drain(..).collect()
+    }
+
+    /// Returns the chunks changed since the last call by level, so they can be remeshed.
+    pub fn take_dirty_chunks(&mut self) -> Vec<(i32, Vec<[i32; 2]>)> {
+        self.layers.iter_mut()
+            .filter(|(_, layer)| layer.has_dirty_chunks())
+            .map(|(level, layer)| (*level, layer.take_dirty_chunks()))
+            .collect()
+    }
+
+    pub fn get_tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    pub fn get_stats(&self) -> WorldStats {
+        WorldStats::collect(self)
+    }
+
+    pub fn request_layer_creation(&mut self, level: i32) {
+        self.layer_creator.request_layer(level);
+    }
+
+    pub fn cancel_layer_creation(&mut self, level: i32) {
+        self.layer_creator.cancel_layer(level);
+    }
+
+    pub fn get_field(&self, pos: [i32; 3]) -> Option<&Field> {
+        match self.layers.get(&pos[2]) {
+            Some(layer) => layer.get_field([pos[0], pos[1]]),
+            None => None
         }
-        self.camera.mod_translation(offset);
     }
 
-    pub fn toggle_camera_projection(&mut self) {
-        match self.camera.get_projection() {
-            Projection::Orthographic { .. } => {
-                self.camera.set_projection(create_default_perspective());
-                self.camera.set_translation(Vector3::new(-10., -10., 20.));
+    /// Changes a field, only the chunks affected by the change get remeshed on the next tick.
+    pub fn set_field(&mut self, pos: [i32; 3], field: Option<Field>) {
+        match self.layers.get_mut(&pos[2]) {
+            Some(layer) => layer.set_field([pos[0], pos[1]], field),
+            None => { warn!("Could not set field at {}/{}/{}: layer not existing", pos[0], pos[1], pos[2]); }
+        }
+    }
+
+    /// The highest level with a cube at the position, None if there is no cube in any layer.
+    pub fn get_surface_level(&self, pos: [i32; 2]) -> Option<i32> {
+        self.layers.keys()
+            .rev()
+            .cloned()
+            .find(|level| is_cube(&self.layers, [pos[0], pos[1], *level]))
+    }
+
+    /// Designates all cubes in the rectangle between the corners on the given level.
+    pub fn designate(&mut self, level: i32, corners: [[i32; 2]; 2], designation_type: DesignationType) {
+        match self.layers.get(&level) {
+            Some(layer) => {
+                let count = self.designations.designate_area(layer, corners, designation_type);
+                debug!("Designated {} fields for {:?} on level {}", count, designation_type, level);
+                for (pos, designation_type) in self.designations.iter() {
+                    if !self.job_scheduler.is_reserved(pos) {
+                        self.job_scheduler.add_job(JobKind::Dig(designation_type), pos, JobPriority::Normal);
+                    }
+                }
             },
-            Projection::Perspective { .. } => {
-                self.camera.set_projection(create_default_orthographic());
-                self.camera.set_translation(Vector3::new(0., 0., 0.));
+            None => { warn!("Could not designate area on level {}: layer not existing", level); }
+        }
+    }
+
+    /// Takes back the designation at the position and cancels its job. A dwarf already working on it finishes without effect.
+    pub fn cancel_designation(&mut self, pos: [i32; 3]) {
+        if self.designations.remove(pos).is_none() {
+            trace!("Could not cancel designation at {}/{}/{}: not designated", pos[0], pos[1], pos[2]);
+            return;
+        }
+        match self.job_scheduler.cancel_job_at(pos) {
+            Some(job) => { debug!("Cancelled job {}", job); },
+            None => { debug!("Cancelled designation at {}/{}/{} without a job", pos[0], pos[1], pos[2]); }
+        }
+    }
+
+    /// Finds the first field hit by the ray, levels above the top visible one are ignored.
+    pub fn pick(&self, ray: &Ray, top_visible_level: i32) -> Option<PickResult> {
+        let layer_size = self.config.get_layer_size();
+        let bounds = [[0, 0, BOTTOM_LEVEL], [layer_size[0] - 1, layer_size[1] - 1, top_visible_level]];
+        cast_ray(ray, bounds, |pos| self.get_field(pos).is_some())
+    }
+
+    pub fn get_designations(&self) -> &Designations {
+        &self.designations
+    }
+
+    /// Queues a job for the workers, returns None if the target is reserved by another job.
+    pub fn add_job(&mut self, kind: JobKind, target: [i32; 3], priority: JobPriority) -> Option<JobId> {
+        self.job_scheduler.add_job(kind, target, priority)
+    }
+
+    pub fn get_job_scheduler(&self) -> &JobScheduler {
+        &self.job_scheduler
+    }
+
+    pub fn get_dwarfs(&self) -> &[Dwarf] {
+        &self.dwarfs
+    }
+
+    pub fn get_nav_graph(&self) -> &NavGraph {
+        &self.nav_graph
+    }
+
+    fn build_nav_graph(&mut self) {
+        let layer_size = self.config.get_layer_size();
+        self.nav_graph = NavGraph::build(&self.layers, [0, 0, BOTTOM_LEVEL], [layer_size[0] - 1, layer_size[1] - 1, self.top_level - 1]);
+    }
+
+    /// Dwarfs are spawned around the center of the map once every level has been created.
+    fn spawn_dwarfs(&mut self) {
+        let layer_size = self.config.get_layer_size();
+        let center = [layer_size[0] / 2, layer_size[1] / 2];
+        let mut id = 0;
+        for dy in -DWARF_SPAWN_RADIUS..=DWARF_SPAWN_RADIUS {
+            for dx in -DWARF_SPAWN_RADIUS..=DWARF_SPAWN_RADIUS {
+                if id == DWARF_COUNT {
+                    break;
+                }
+                let (x, y) = (center[0] + dx, center[1] + dy);
+                let spawn_level = (BOTTOM_LEVEL..self.top_level).rev()
+                    .find(|level| self.nav_graph.is_walkable([x, y, *level]));
+                if let Some(level) = spawn_level {
+                    info!("Spawning dwarf {} at {}/{}/{}", id, x, y, level);
+                    self.dwarfs.push(Dwarf::new(id, [x, y, level]));
+                    id += 1;
+                }
             }
         }
+        if id < DWARF_COUNT {
+            warn!("Could only spawn {} of {} dwarfs", id, DWARF_COUNT);
+        }
     }
 
-    pub fn request_layer_creation(&mut self, level: i32) {
-        self.layer_creator.request_layer(level);
+    fn simulation_step(&mut self) {
+        for i in 0..self.dwarfs.len() {
+            match self.dwarfs[i].step(&self.layers, &self.nav_graph) {
+                Some(JobEvent::Completed(id)) => {
+                    if let Some(job) = self.job_scheduler.complete_job(id) {
+                        self.execute_job(job.get_kind(), job.get_target());
+                    }
+                },
+                Some(JobEvent::Abandoned(id, reason)) => self.job_scheduler.abandon_job(id, &reason),
+                Some(JobEvent::Invalid(id)) => {
+                    if let Some(job) = self.job_scheduler.remove_job(id) {
+                        warn!("Removed invalid job {}", job);
+                        self.designations.remove(job.get_target());
+                    }
+                },
+                None => {}
+            }
+        }
     }
 
-    pub fn get_camera(&self) -> &Camera {
-        &self.camera
+    /// Idle workers claim the most important job near them.
+    fn assign_jobs(&mut self) {
+        for dwarf in self.dwarfs.iter_mut().filter(|d| d.is_idle()) {
+            if let Some((job, path)) = self.job_scheduler.claim_job(dwarf, &self.nav_graph) {
+                dwarf.assign_job(job, path);
+            }
+        }
     }
 
-    pub fn get_camera_mut(&mut self) -> &mut Camera {
-        &mut self.camera
+    /// Changes the fields for a completed job and updates the navigation graph around them.
+    fn execute_job(&mut self, kind: JobKind, target: [i32; 3]) {
+        match kind {
+            JobKind::Dig(designation_type) => {
+                apply_designation(&mut self.layers, target, designation_type);
+                self.designations.remove(target);
+            },
+            JobKind::Build(material) => {
+                let mut field = Field::default();
+                field.set_material(material);
+                self.set_field(target, Some(field));
+            },
+            JobKind::Haul { .. } => return
+        }
+        let min = [target[0] - JOB_REACH, target[1] - JOB_REACH, target[2] - JOB_REACH];
+        let max = [target[0] + JOB_REACH, target[1] + JOB_REACH, target[2] + JOB_REACH];
+        self.nav_graph.update_area(&self.layers, min, max);
     }
 
-    pub fn render(&self, shader: &ShaderProgram) -> Result<(), WorldError> {
-        self.texture_array.activate();
+    /// Pending levels are created in order of their distance to this one, usually the top visible level.
+    pub fn set_focus_level(&mut self, level: i32) {
+        self.layer_creator.set_focus_level(level);
+    }
 
-        self.test_object.render(&self.camera, shader)?;
+    fn add_finished_layers(&mut self) {
+        for layer in self.layer_creator.get_finished_layers() {
+            let level = layer.get_level();
+            debug!("Adding finished layer, level = {}", level);
+            self.layers.insert(level, layer);
+            self.new_layers.push(level);
+        }
+    }
 
-        let layer_iter = match self.camera.get_translation().z {
-            height if height < 0. => self.layers.iter().skip((self.top_level - self.camera.get_translation().z as i32).max(0) as usize).rev(),
-            _ => self.layers.iter().skip(0).rev()
-        };
-        for layer in layer_iter {
-            layer.render(&self.camera, shader)?;
+    /// Work independent of the simulation steps, like adding the layers finished in the background.
+    /// Once all levels exist the dwarfs are spawned unless a save brought some, also while the simulation is paused.
+    pub fn update(&mut self) {
+        // checked before taking the finished layers, so the last of them is not missed
+        let generation_finished = self.is_generation_finished();
+        self.add_finished_layers();
+        if generation_finished && !self.populated {
+            self.build_nav_graph();
+            if self.dwarfs.is_empty() {
+                self.spawn_dwarfs();
+            }
+            self.populated = true;
         }
-        self.texture_array.deactivate();
-        Ok(())
     }
 }
 
 impl Updatable for World {
-    fn tick(&mut self, time_passed: u32) {
-        self.test_object.mod_rotation(Vector3::new(0., 0., 5f32.to_radians()));
+    /// The simulation runs in fixed steps, the passed time is expected to be `SIM_STEP`.
+    /// Other durations still advance it by one step, they are only logged.
+    fn tick(&mut self, time_passed: Duration) {
+        if time_passed != SIM_STEP {
+            trace!("Tick of {:?} treated as one simulation step of {:?}", time_passed, SIM_STEP);
+        }
+        self.tick_count += 1;
+        // dwarfs of a save would fall through levels still being generated
+        if !self.populated {
+            return;
+        }
+        self.assign_jobs();
+        self.simulation_step();
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::{ env, fs, thread };
+    use std::time::Duration;
+
+    use world::{ World, WorldConfig, DesignationType, Camera, SIM_STEP, traits::{ Updatable, Worker } };
+    use world::world_config::{ NoiseConfig, GeologyConfig };
+    use world::job::Skill;
+    use world::save::SavedCamera;
+
+    fn create_world() -> World {
+        let mut config = WorldConfig::new("round trip", [32, 32], 5, NoiseConfig::default());
+        config.set_geology(GeologyConfig::new(NoiseConfig::new(3, 6e-2, 0.4, (-1., 1.)), 0.3, [1., -2.], [0.5, 0.65, 0.75]));
+        let mut world = World::new(&config).unwrap();
+        while !world.is_generation_finished() {
+            world.update();
+            thread::sleep(Duration::from_millis(10));
+        }
+        world.update();
+        world
+    }
+
+    #[test]
+    fn saved_world_is_loaded_unchanged() {
+        let mut world = create_world();
+        for y in 10..16 {
+            for x in 10..16 {
+                if let Some(level) = world.get_surface_level([x, y]) {
+                    world.designate(level, [[x, y], [x, y]], DesignationType::Dig);
+                }
+            }
+        }
+        for _ in 0..30 {
+            world.tick(SIM_STEP);
+        }
+        let path = env::temp_dir().join(format!("dwarfs-round-trip-{}.sav", ::std::process::id()));
+        let path = path.to_str().unwrap();
+        world.save(path, &SavedCamera::from_camera(&Camera::default())).unwrap();
+        let (loaded, _) = World::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let (config, loaded_config) = (world.get_config().get_geology(), loaded.get_config().get_geology());
+        assert_eq!(config.get_cave_threshold(), loaded_config.get_cave_threshold());
+        assert_eq!(config.get_cave_noise().get_octaves(), loaded_config.get_cave_noise().get_octaves());
+        assert_eq!(config.get_strata_bottoms(), loaded_config.get_strata_bottoms());
+        assert_eq!(config.get_ore_thresholds(), loaded_config.get_ore_thresholds());
+
+        assert_eq!(world.get_layers().keys().collect::<Vec<_>>(), loaded.get_layers().keys().collect::<Vec<_>>());
+        for (level, layer) in world.get_layers() {
+            let loaded_layer = &loaded.get_layers()[level];
+            for y in 0..32 {
+                for x in 0..32 {
+                    let field = layer.get_field([x, y]).map(|f| (f.get_type(), f.get_material()));
+                    let loaded_field = loaded_layer.get_field([x, y]).map(|f| (f.get_type(), f.get_material()));
+                    assert!(field == loaded_field, "field {}/{}/{}", x, y, level);
+                }
+            }
+        }
+
+        assert_eq!(world.get_designations().iter().collect::<Vec<_>>(), loaded.get_designations().iter().collect::<Vec<_>>());
+
+        let jobs: Vec<_> = world.get_job_scheduler().get_jobs()
+            .map(|job| (job.get_id(), job.get_kind(), job.get_target(), job.get_priority()))
+            .collect();
+        let loaded_jobs: Vec<_> = loaded.get_job_scheduler().get_jobs()
+            .map(|job| (job.get_id(), job.get_kind(), job.get_target(), job.get_priority()))
+            .collect();
+        assert!(!jobs.is_empty());
+        assert_eq!(jobs, loaded_jobs);
+
+        assert!(!world.get_dwarfs().is_empty());
+        assert_eq!(world.get_dwarfs().len(), loaded.get_dwarfs().len());
+        for (dwarf, loaded_dwarf) in world.get_dwarfs().iter().zip(loaded.get_dwarfs()) {
+            assert_eq!(dwarf.get_id(), loaded_dwarf.get_id());
+            assert_eq!(dwarf.get_position(), loaded_dwarf.get_position());
+            let (needs, loaded_needs) = (dwarf.get_needs(), loaded_dwarf.get_needs());
+            assert_eq!(needs.get_hunger(), loaded_needs.get_hunger());
+            assert_eq!(needs.get_thirst(), loaded_needs.get_thirst());
+            assert_eq!(needs.get_rest(), loaded_needs.get_rest());
+            for skill in [Skill::Mining, Skill::Building, Skill::Hauling].iter() {
+                assert_eq!(dwarf.get_skills().get_level(*skill), loaded_dwarf.get_skills().get_level(*skill));
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::iter;
+use rand;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use ron;
+
+use world::WorldError;
+use world::noise::{ OctavedNoise, SimplexNoise };
+use utility::read_file;
+
+const RANDOM_SEED_LENGTH: usize = 12;
+
+/// Everything needed to create a world, the same config always creates the same world.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct WorldConfig {
+    seed: String,
+    layer_size: [i32; 2],
+    top_level: i32,
+    height_noise: NoiseConfig,
+    geology: GeologyConfig
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct NoiseConfig {
+    octaves: u8,
+    scale: f32,
+    roughness: f32,
+    range: (f32, f32)
+}
+
+/// Caves, rock strata and ore veins below the surface.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct GeologyConfig {
+    cave_noise: NoiseConfig,
+    /// Cave noise above this carves out a field.
+    cave_threshold: f32,
+    /// Levels the sandstone and the stone stratum reach down to, granite is below them.
+    strata_bottoms: [f32; 2],
+    /// Noise needed for a coal, iron or gold vein, the higher the rarer.
+    ore_thresholds: [f32; 3]
+}
+
+impl WorldConfig {
+    pub fn new(seed: &str, layer_size: [i32; 2], top_level: i32, height_noise: NoiseConfig) -> Self {
+        Self {
+            seed: seed.to_string(),
+            layer_size: layer_size,
+            top_level: top_level,
+            height_noise: height_noise,
+            geology: GeologyConfig::default()
+        }
+    }
+
+    pub fn from_file(config_path: &str) -> Result<Self, WorldError> {
+        let content = read_file(config_path)?;
+        let config: WorldConfig = ron::de::from_str(&content)?;
+        config.validate()?;
+        info!("Read world config '{}'", config_path);
+        Ok(config)
+    }
+
+    /// Supported arguments are `--config <path>`, `--seed <seed>`, `--layer-size <width>x<height>` and `--top-level <level>`.
+    /// A given config file is read first, the other arguments override its values.
+    pub fn from_args<I: Iterator<Item = String>>(args: I) -> Result<Self, WorldError> {
+        let args: Vec<String> = args.collect();
+        let mut config = match get_arg_value(&args, "--config")? {
+            Some(config_path) => Self::from_file(config_path)?,
+            None => Self::default()
+        };
+        let mut i = 0;
+        while i < args.len() {
+            let value = match args.get(i + 1) {
+                Some(value) => value.as_str(),
+                None => return Err(WorldError::InvalidArgument(args[i].clone()))
+            };
+            match args[i].as_str() {
+                "--config" => {},
+                "--seed" => config.seed = value.to_string(),
+                "--layer-size" => config.layer_size = parse_layer_size(value)?,
+                "--top-level" => config.top_level = value.parse().map_err(|_| WorldError::InvalidArgument(format!("--top-level {}", value)))?,
+                unknown => return Err(WorldError::InvalidArgument(unknown.to_string()))
+            }
+            i += 2;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn get_seed(&self) -> &str {
+        &self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: &str) {
+        self.seed = seed.to_string();
+    }
+
+    pub fn get_layer_size(&self) -> [i32; 2] {
+        self.layer_size
+    }
+
+    pub fn get_top_level(&self) -> i32 {
+        self.top_level
+    }
+
+    pub fn get_height_noise(&self) -> &NoiseConfig {
+        &self.height_noise
+    }
+
+    pub fn get_geology(&self) -> &GeologyConfig {
+        &self.geology
+    }
+
+    pub fn set_geology(&mut self, geology: GeologyConfig) {
+        self.geology = geology;
+    }
+
+    /// Every noise gets its own seed, derived from the world seed and its purpose.
+    pub fn create_simplex_noise(&self, purpose: &str) -> SimplexNoise {
+        SimplexNoise::from_seed(&derive_seed(&self.seed, purpose))
+    }
+
+    pub fn create_octaved_noise(&self, purpose: &str, noise_config: &NoiseConfig) -> OctavedNoise {
+        OctavedNoise::new(
+            noise_config.octaves,
+            noise_config.roughness,
+            noise_config.scale,
+            noise_config.range,
+            self.create_simplex_noise(purpose)
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), WorldError> {
+        if self.layer_size[0] <= 0 || self.layer_size[1] <= 0 {
+            return Err(WorldError::InvalidConfig(format!("layer size {}x{}", self.layer_size[0], self.layer_size[1])));
+        }
+        if self.top_level <= 0 {
+            return Err(WorldError::InvalidConfig(format!("top level {}", self.top_level)));
+        }
+        if self.height_noise.octaves == 0 || self.height_noise.range.0 >= self.height_noise.range.1 {
+            return Err(WorldError::InvalidConfig("height noise".to_string()));
+        }
+        let cave_noise = &self.geology.cave_noise;
+        if cave_noise.octaves == 0 || cave_noise.range.0 >= cave_noise.range.1 {
+            return Err(WorldError::InvalidConfig("cave noise".to_string()));
+        }
+        if self.geology.strata_bottoms[0] < self.geology.strata_bottoms[1] {
+            return Err(WorldError::InvalidConfig(format!("strata bottoms {:?}", self.geology.strata_bottoms)));
+        }
+        Ok(())
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        let mut rng = rand::thread_rng();
+        let seed: String = iter::repeat_with(|| rng.sample(Alphanumeric)).take(RANDOM_SEED_LENGTH).collect();
+        Self {
+            seed: seed,
+            layer_size: [128, 128],
+            top_level: 5,
+            height_noise: NoiseConfig::default(),
+            geology: GeologyConfig::default()
+        }
+    }
+}
+
+impl NoiseConfig {
+    pub fn new(octaves: u8, scale: f32, roughness: f32, range: (f32, f32)) -> Self {
+        Self {
+            octaves: octaves,
+            scale: scale,
+            roughness: roughness,
+            range: range
+        }
+    }
+
+    pub fn get_octaves(&self) -> u8 {
+        self.octaves
+    }
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+    pub fn get_roughness(&self) -> f32 {
+        self.roughness
+    }
+    pub fn get_range(&self) -> (f32, f32) {
+        self.range
+    }
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        Self::new(4, 8e-3, 1e+3, (0., 5.))
+    }
+}
+
+impl GeologyConfig {
+    pub fn new(cave_noise: NoiseConfig, cave_threshold: f32, strata_bottoms: [f32; 2], ore_thresholds: [f32; 3]) -> Self {
+        Self {
+            cave_noise: cave_noise,
+            cave_threshold: cave_threshold,
+            strata_bottoms: strata_bottoms,
+            ore_thresholds: ore_thresholds
+        }
+    }
+
+    pub fn get_cave_noise(&self) -> &NoiseConfig {
+        &self.cave_noise
+    }
+    pub fn get_cave_threshold(&self) -> f32 {
+        self.cave_threshold
+    }
+    pub fn get_strata_bottoms(&self) -> [f32; 2] {
+        self.strata_bottoms
+    }
+    pub fn get_ore_thresholds(&self) -> [f32; 3] {
+        self.ore_thresholds
+    }
+}
+
+impl Default for GeologyConfig {
+    fn default() -> Self {
+        Self::new(NoiseConfig::new(2, 8e-2, 0.5, (-1., 1.)), 0.35, [0., -3.], [0.55, 0.6, 0.7])
+    }
+}
+
+fn get_arg_value<'a>(args: &'a [String], name: &str) -> Result<Option<&'a str>, WorldError> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => match args.get(i + 1) {
+            Some(value) => Ok(Some(value.as_str())),
+            None => Err(WorldError::InvalidArgument(name.to_string()))
+        },
+        None => Ok(None)
+    }
+}
+
+fn parse_layer_size(value: &str) -> Result<[i32; 2], WorldError> {
+    let invalid = || WorldError::InvalidArgument(format!("--layer-size {}", value));
+    let dims: Vec<&str> = value.split('x').collect();
+    if dims.len() != 2 {
+        return Err(invalid());
+    }
+    let width = dims[0].parse().map_err(|_| invalid())?;
+    let height = dims[1].parse().map_err(|_| invalid())?;
+    Ok([width, height])
+}
+
+/// FNV-1a, so seeds stay the same across platforms and compiler versions.
+fn derive_seed(seed: &str, purpose: &str) -> [u8; 16] {
+    let mut derived = [0u8; 16];
+    for (half, chunk) in derived.chunks_mut(8).enumerate() {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ half as u64;
+        for byte in seed.bytes().chain(iter::once(0)).chain(purpose.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        for (i, b) in chunk.iter_mut().enumerate() {
+            *b = (hash >> (i * 8)) as u8;
+        }
+    }
+    derived
+}
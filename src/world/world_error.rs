@@ -2,12 +2,23 @@ use std::fmt;
 use std::error::Error;
 use std::io;
 
-use graphics::{ GraphicsError, mesh::MeshError };
+use ron;
+
+use graphics::{ GraphicsError, mesh::MeshError, texture::AtlasError };
+use utility::FileError;
 
 #[derive(Debug)]
 pub enum WorldError {
     Graphics(GraphicsError),
-    MeshCreation(MeshError)
+    MeshCreation(MeshError),
+    File(FileError),
+    WorkerCreation(io::Error),
+    ConfigParse(ron::de::Error),
+    InvalidConfig(String),
+    InvalidArgument(String),
+    InvalidSave(String),
+    UnsupportedSaveVersion(u32),
+    SaveChecksumMismatch
 }
 
 impl From<GraphicsError> for WorldError {
@@ -16,18 +27,44 @@ impl From<GraphicsError> for WorldError {
     }
 }
 
+impl From<AtlasError> for WorldError {
+    fn from(err: AtlasError) -> Self {
+        WorldError::Graphics(GraphicsError::from(err))
+    }
+}
+
 impl From<MeshError> for WorldError {
     fn from(err: MeshError) -> Self {
         WorldError::MeshCreation(err)
     }
 }
 
+impl From<FileError> for WorldError {
+    fn from(err: FileError) -> Self {
+        WorldError::File(err)
+    }
+}
+
+impl From<ron::de::Error> for WorldError {
+    fn from(err: ron::de::Error) -> Self {
+        WorldError::ConfigParse(err)
+    }
+}
+
 impl Error for WorldError {
 
     fn description(&self) -> &str {
         match *self {
             WorldError::Graphics(_) => "graphics",
             WorldError::MeshCreation(_) => "mesh creation",
+            WorldError::File(_) => "file",
+            WorldError::WorkerCreation(_) => "worker creation",
+            WorldError::ConfigParse(_) => "config parse",
+            WorldError::InvalidConfig(_) => "invalid config",
+            WorldError::InvalidArgument(_) => "invalid argument",
+            WorldError::InvalidSave(_) => "invalid save",
+            WorldError::UnsupportedSaveVersion(_) => "unsupported save version",
+            WorldError::SaveChecksumMismatch => "save checksum mismatch"
         }
     }
 
@@ -35,6 +72,14 @@ impl Error for WorldError {
         match *self {
             WorldError::Graphics(ref err) => Some(err),
             WorldError::MeshCreation(ref err) => Some(err),
+            WorldError::File(ref err) => Some(err),
+            WorldError::WorkerCreation(ref err) => Some(err),
+            WorldError::ConfigParse(ref err) => Some(err),
+            WorldError::InvalidConfig(_) => None,
+            WorldError::InvalidArgument(_) => None,
+            WorldError::InvalidSave(_) => None,
+            WorldError::UnsupportedSaveVersion(_) => None,
+            WorldError::SaveChecksumMismatch => None
         }
     }
 }
@@ -44,6 +89,14 @@ impl fmt::Display for WorldError {
         match *self {
             WorldError::Graphics(ref err) => write!(f, "{}/{}", self.description(), err),
             WorldError::MeshCreation(ref err) => write!(f, "{}/{}", self.description(), err),
+            WorldError::File(ref err) => write!(f, "{}/{}", self.description(), err),
+            WorldError::WorkerCreation(ref err) => write!(f, "{}: {}", self.description(), err),
+            WorldError::ConfigParse(ref err) => write!(f, "{}: {}", self.description(), err),
+            WorldError::InvalidConfig(ref s) => write!(f, "{}: {}", self.description(), s),
+            WorldError::InvalidArgument(ref s) => write!(f, "{}: '{}'", self.description(), s),
+            WorldError::InvalidSave(ref s) => write!(f, "{}: {}", self.description(), s),
+            WorldError::UnsupportedSaveVersion(version) => write!(f, "{}: {}", self.description(), version),
+            WorldError::SaveChecksumMismatch => write!(f, "{}", self.description())
         }
     }
 }
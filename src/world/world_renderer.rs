@@ -0,0 +1,323 @@
+use std::collections::{ BTreeMap, BTreeSet };
+use std::sync::Arc;
+use std::rc::Rc;
+use glm::Vector3;
+
+use graphics::{ Mesh, MeshManager, ShaderProgram, TextureArray, TextureArrayBuilder, Lighting, GraphicsError, Projection, mesh::InstanceBuffer };
+use graphics::texture::{ AtlasManifest, AtlasError };
+use graphics::transformation::create_direction;
+use graphics::projection::{ create_default_orthographic, create_default_perspective };
+use world::{ World, Camera, Layer, Object, ObjectBatch, RenderStats, WorldError, PickResult, SIM_STEP, traits::Worker };
+use world::entity::{ Dwarf, Activity };
+use world::layer::{ FieldMaterial, FieldTemplates, MaterialRegistry, MaterialTextures, LayerMesh, LayerMesher, LayerMeshWorker, AdjacentLayers };
+use utility::read_file;
+use utility::traits::{ Translatable, Rotatable };
+use utility::Float;
+
+/// The graphics of a world, needs a GL context. Follows the world on `update`, the world itself never refers to it.
+pub struct WorldRenderer {
+    camera: Camera,
+    texture_array: TextureArray,
+    lighting: Lighting,
+    mesher: Arc<LayerMesher>,
+    mesh_worker: LayerMeshWorker,
+    layer_meshes: BTreeMap<i32, LayerMesh>,
+    /// Levels meshed while a neighbour was missing, they are meshed again once it is added.
+    incomplete_levels: BTreeSet<i32>,
+    dwarf_mesh: Rc<Mesh>,
+    dwarf_objects: Vec<Object>,
+    instance_buffer: InstanceBuffer,
+    /// Tick count of the world the animations were last advanced to.
+    animated_tick: u64,
+    test_object: Object
+}
+
+const ATLAS_MANIFEST_PATH: &str = "resources/atlas.ron";
+const LIGHTING_PATH: &str = "resources/lighting.ron";
+const DWARF_MODEL_PATH: &str = "resources/gltf/dwarf.gltf";
+/// Degrees the test object turns per simulation step.
+const TEST_OBJECT_ROTATION: Float = 5.;
+/// Seconds it takes to blend from one dwarf animation to the next.
+const DWARF_ANIMATION_BLEND: Float = 0.2;
+
+impl WorldRenderer {
+    /// Loads the textures, meshes and lighting. The camera direction is fixed for the culling of layer meshes.
+    pub fn new(world: &World, camera: Camera) -> Result<Self, WorldError> {
+        let atlas_manifest = AtlasManifest::from_file(ATLAS_MANIFEST_PATH)?;
+        let texture_array = TextureArrayBuilder::from_manifest(&atlas_manifest).finish()?;
+        let material_registry = create_material_registry(&atlas_manifest)?;
+        let lighting = read_lighting(LIGHTING_PATH)?;
+
+        let mut mesh_manager = MeshManager::default();
+        mesh_manager.add_mesh(Mesh::from_obj("resources/obj/test.obj", |name| atlas_manifest.get_layer(name))?, "test");
+        mesh_manager.load_gltf(DWARF_MODEL_PATH, "Dwarf", |name| atlas_manifest.get_layer(name))?;
+
+        let mut test_object = Object::new(mesh_manager.get_mesh_rc("test")?);
+        test_object.set_translation(Vector3::new(-1., -1., 1.));
+
+        let cam_dir = create_direction(camera.get_rotation());
+        info!("Camera direction = {:.2}/{:.2}/{:.2}", cam_dir.x, cam_dir.y, cam_dir.z);
+        let field_templates = FieldTemplates::from_obj("resources/obj/slope.obj")?;
+        let mesher = Arc::new(LayerMesher::new(field_templates, material_registry, cam_dir));
+        let mesh_worker = LayerMeshWorker::new(mesher.clone())?;
+
+        let dwarf_mesh = mesh_manager.get_mesh_rc("Dwarf")?;
+        let renderer = Self {
+            camera: camera,
+            texture_array: texture_array,
+            lighting: lighting,
+            mesher: mesher,
+            mesh_worker: mesh_worker,
+            layer_meshes: BTreeMap::new(),
+            incomplete_levels: BTreeSet::new(),
+            dwarf_mesh: dwarf_mesh,
+            dwarf_objects: Vec::new(),
+            instance_buffer: InstanceBuffer::new().map_err(GraphicsError::from)?,
+            animated_tick: world.get_tick_count(),
+            test_object: test_object
+        };
+        Ok(renderer)
+    }
+
+    pub fn get_camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn get_camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    pub fn move_camera(&mut self, mut offset: Vector3<Float>) {
+        let curr_height = self.camera.get_translation().z;
+        match self.camera.get_projection() {
+            Projection::Orthographic { .. } if curr_height > 0. => { offset.z = -curr_height; },
+            Projection::Orthographic { .. } if curr_height + offset.z > 0. => { offset.z = 0.; },
+            _ => {}
+        }
+        self.camera.mod_translation(offset);
+    }
+
+    pub fn toggle_camera_projection(&mut self) {
+        match self.camera.get_projection() {
+            Projection::Orthographic { .. } => {
+                self.camera.set_projection(create_default_perspective());
+                self.camera.set_translation(Vector3::new(-10., -10., 20.));
+            },
+            Projection::Perspective { .. } => {
+                self.camera.set_projection(create_default_orthographic());
+                self.camera.set_translation(Vector3::new(0., 0., 0.));
+            }
+        }
+    }
+
+    /// Levels above are hidden, lowering the camera below the surface cuts into the world.
+    pub fn get_top_visible_level(&self, world: &World) -> i32 {
+        match self.camera.get_translation().z {
+            height if height < 0. => world.get_top_level() - 1 + height as i32,
+            _ => world.get_top_level() - 1
+        }
+    }
+
+    /// Finds the visible field under a point on the screen, given in normalized device coordinates.
+    pub fn pick(&self, world: &World, screen_pos: [Float; 2]) -> Option<PickResult> {
+        let ray = self.camera.create_ray(screen_pos)?;
+        world.pick(&ray, self.get_top_visible_level(world))
+    }
+
+    /// True once no layer mesh is being created in the background.
+    pub fn is_meshing_finished(&self) -> bool {
+        self.mesh_worker.get_pending_count() == 0
+    }
+
+    /// Meshes new and changed layers, uploads the finished meshes and places the objects, alpha is the progress towards the next simulation step.
+    pub fn update(&mut self, world: &mut World, alpha: f32) {
+        let focus_level = self.get_top_visible_level(world);
+        world.set_focus_level(focus_level);
+        self.request_new_layer_meshes(world);
+        self.add_finished_layer_meshes();
+        self.update_dirty_chunks(world);
+        self.update_dwarf_objects(world, alpha);
+        let rotation = (world.get_tick_count() % 72) as Float * TEST_OBJECT_ROTATION;
+        self.test_object.set_rotation(Vector3::new(0., 0., rotation.to_radians()));
+    }
+
+    /// New layers are meshed in the background, along with neighbours whose occlusion was missing them.
+    fn request_new_layer_meshes(&mut self, world: &mut World) {
+        for level in world.take_new_layers() {
+            self.request_layer_mesh(world, level);
+            for neighbour in [level - 1, level + 1].iter() {
+                if self.incomplete_levels.contains(neighbour) {
+                    self.request_layer_mesh(world, *neighbour);
+                }
+            }
+        }
+    }
+
+    fn request_layer_mesh(&mut self, world: &World, level: i32) {
+        let layers = world.get_layers();
+        let layer = match layers.get(&level) {
+            Some(layer) => layer,
+            None => return
+        };
+        let below = layers.get(&(level - 1)).map(|layer| layer.get_chunks().clone());
+        let above = layers.get(&(level + 1)).map(|layer| layer.get_chunks().clone());
+        if below.is_some() && above.is_some() {
+            self.incomplete_levels.remove(&level);
+        } else {
+            self.incomplete_levels.insert(level);
+        }
+        self.mesh_worker.request_layer(level, layer.get_chunks().clone(), below, above);
+    }
+
+    fn add_finished_layer_meshes(&mut self) {
+        for (level, chunk_buffers) in self.mesh_worker.get_finished_layers() {
+            let mut layer_mesh = LayerMesh::new(level);
+            for (chunk_index, buffer) in chunk_buffers {
+                if let Err(e) = layer_mesh.set_chunk_mesh(chunk_index, buffer) {
+                    error!("Could not set mesh of chunk {}/{} on level {}: {}", chunk_index[0], chunk_index[1], level, e);
+                }
+            }
+            self.layer_meshes.insert(level, layer_mesh);
+        }
+    }
+
+    /// Changed chunks are remeshed together with the chunks above and below them, whose vertices they occlude.
+    /// Layers still meshed in the background are requested again, their result would miss the change.
+    fn update_dirty_chunks(&mut self, world: &mut World) {
+        let mut dirty_chunks = BTreeSet::new();
+        for (level, chunk_indices) in world.take_dirty_chunks() {
+            for chunk_index in chunk_indices {
+                for chunk_level in level - 1..=level + 1 {
+                    dirty_chunks.insert((chunk_level, chunk_index));
+                }
+            }
+        }
+        let mut outdated_levels = BTreeSet::new();
+        let layers = world.get_layers();
+        for (level, chunk_index) in dirty_chunks {
+            if self.mesh_worker.is_pending(level) {
+                outdated_levels.insert(level);
+                continue;
+            }
+            let (layer, layer_mesh) = match (layers.get(&level), self.layer_meshes.get_mut(&level)) {
+                (Some(layer), Some(layer_mesh)) => (layer, layer_mesh),
+                _ => continue
+            };
+            let (buffer, _) = self.mesher.create_chunk_buffer(layer.get_chunks(), get_adjacent_layers(layers, level), chunk_index);
+            if let Err(e) = layer_mesh.set_chunk_mesh(chunk_index, buffer) {
+                error!("Could not update chunk {}/{} of layer {}: {}", chunk_index[0], chunk_index[1], level, e);
+            }
+        }
+        for level in outdated_levels {
+            self.request_layer_mesh(world, level);
+        }
+    }
+
+    /// Places and poses the dwarf objects between their last two simulated steps.
+    fn update_dwarf_objects(&mut self, world: &World, alpha: f32) {
+        let dwarfs = world.get_dwarfs();
+        while self.dwarf_objects.len() < dwarfs.len() {
+            self.dwarf_objects.push(Object::new(self.dwarf_mesh.clone()));
+        }
+        self.dwarf_objects.truncate(dwarfs.len());
+        let new_ticks = world.get_tick_count().saturating_sub(self.animated_tick);
+        self.animated_tick = world.get_tick_count();
+        let step = SIM_STEP.as_secs() as Float + SIM_STEP.subsec_nanos() as Float * 1e-9;
+        for (dwarf, object) in dwarfs.iter().zip(self.dwarf_objects.iter_mut()) {
+            let previous = dwarf.get_previous_position();
+            let current = dwarf.get_position();
+            let interpolate = |i: usize| previous[i] as Float + (current[i] - previous[i]) as Float * alpha;
+            object.set_translation(Vector3::new(interpolate(0), interpolate(1), interpolate(2)));
+            object.play_animation(get_dwarf_animation(dwarf), DWARF_ANIMATION_BLEND);
+            for _ in 0..new_ticks {
+                object.tick_animation(step);
+            }
+            object.update_animation(alpha);
+        }
+    }
+
+    /// Skips layers, chunks and objects outside the view of the camera, returns how many were drawn and culled.
+    pub fn render(&self, world: &World, shader: &ShaderProgram) -> Result<RenderStats, WorldError> {
+        let camera = &self.camera;
+        let frustum = camera.create_frustum();
+        let mut stats = RenderStats::default();
+        self.lighting.set_uniforms(shader).map_err(GraphicsError::from)?;
+        self.texture_array.activate();
+
+        let top_visible_level = self.get_top_visible_level(world);
+        let dwarf_objects = world.get_dwarfs().iter().zip(self.dwarf_objects.iter())
+            .filter(|(dwarf, _)| dwarf.get_position()[2] <= top_visible_level)
+            .map(|(_, object)| object);
+        let mut batch = ObjectBatch::default();
+        for object in Some(&self.test_object).into_iter().chain(dwarf_objects) {
+            if object.is_visible(&frustum) {
+                batch.add(object);
+                stats.visible_objects += 1;
+            } else {
+                stats.culled_objects += 1;
+            }
+        }
+        batch.render(camera, shader, &self.instance_buffer)?;
+        stats.object_meshes = batch.get_mesh_count();
+
+        for layer_mesh in self.layer_meshes.range(..=top_visible_level).map(|(_, l)| l).rev() {
+            if layer_mesh.get_bounding_box().map_or(false, |b| frustum.intersects(&b)) {
+                layer_mesh.render_visible(camera, shader, &frustum, &mut stats)?;
+                stats.visible_layers += 1;
+            } else {
+                stats.culled_layers += 1;
+                stats.culled_chunks += layer_mesh.get_chunk_count();
+            }
+        }
+        self.texture_array.deactivate();
+        trace!("Render stats: {}", stats);
+        Ok(stats)
+    }
+}
+
+fn get_dwarf_animation(dwarf: &Dwarf) -> &'static str {
+    match dwarf.get_activity() {
+        Activity::Walking if dwarf.is_carrying() => "Carry",
+        Activity::Walking => "Walk",
+        Activity::Working => "Work",
+        _ => "Idle"
+    }
+}
+
+fn read_lighting(lighting_path: &str) -> Result<Lighting, WorldError> {
+    let content = read_file(lighting_path)?;
+    let lighting: Lighting = ron::de::from_str(&content)?;
+    debug!("Read lighting '{}'", lighting_path);
+    Ok(lighting)
+}
+
+fn get_adjacent_layers(layers: &BTreeMap<i32, Layer>, level: i32) -> AdjacentLayers<'_> {
+    AdjacentLayers::new(
+        layers.get(&(level - 1)).map(|layer| layer.get_chunks()),
+        layers.get(&(level + 1)).map(|layer| layer.get_chunks())
+    )
+}
+
+fn create_material_registry(atlas: &AtlasManifest) -> Result<MaterialRegistry, AtlasError> {
+    let mud = atlas.require_layer("mud")?;
+    let grass = atlas.require_layer("grass")?;
+    let grass_side = atlas.require_layer("grass_side")?;
+    let stone = atlas.require_layer("stone")?;
+    let sandstone = atlas.require_layer("sandstone")?;
+    let granite = atlas.require_layer("granite")?;
+    let coal = atlas.require_layer("coal")?;
+    let iron = atlas.require_layer("iron")?;
+    let gold = atlas.require_layer("gold")?;
+
+    let mut registry = MaterialRegistry::default();
+    registry.set_fallback(MaterialTextures::uniform(mud));
+    registry.register(FieldMaterial::MUD, MaterialTextures::uniform(mud));
+    registry.register(FieldMaterial::GRASS, MaterialTextures::new(grass, grass_side, mud));
+    registry.register(FieldMaterial::STONE, MaterialTextures::uniform(stone));
+    registry.register(FieldMaterial::SANDSTONE, MaterialTextures::uniform(sandstone));
+    registry.register(FieldMaterial::GRANITE, MaterialTextures::uniform(granite));
+    registry.register(FieldMaterial::COAL, MaterialTextures::uniform(coal));
+    registry.register(FieldMaterial::IRON, MaterialTextures::uniform(iron));
+    registry.register(FieldMaterial::GOLD, MaterialTextures::uniform(gold));
+    Ok(registry)
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use world::World;
+use world::entity::Activity;
+
+/// A snapshot of the simulation state, for logging and batch runs.
+#[derive(Clone, Debug)]
+pub struct WorldStats {
+    pub tick_count: u64,
+    pub layer_count: usize,
+    pub dwarf_count: usize,
+    pub idle_count: usize,
+    pub walking_count: usize,
+    pub working_count: usize,
+    /// Dwarfs eating, drinking or sleeping.
+    pub resting_count: usize,
+    pub queued_jobs: usize,
+    pub assigned_jobs: usize,
+    pub completed_jobs: u32,
+    pub abandoned_jobs: u32,
+    pub designation_count: usize,
+    pub nav_node_count: usize
+}
+
+impl WorldStats {
+    pub fn collect(world: &World) -> Self {
+        let dwarfs = world.get_dwarfs();
+        let count_activity = |activities: &[Activity]| dwarfs.iter().filter(|d| activities.contains(&d.get_activity())).count();
+        let scheduler = world.get_job_scheduler();
+        Self {
+            tick_count: world.get_tick_count(),
+            layer_count: world.get_layers().len(),
+            dwarf_count: dwarfs.len(),
+            idle_count: count_activity(&[Activity::Idle]),
+            walking_count: count_activity(&[Activity::Walking]),
+            working_count: count_activity(&[Activity::Working]),
+            resting_count: count_activity(&[Activity::Eating, Activity::Drinking, Activity::Sleeping]),
+            queued_jobs: scheduler.get_queued_count(),
+            assigned_jobs: scheduler.get_assigned_count(),
+            completed_jobs: scheduler.get_completed_count(),
+            abandoned_jobs: scheduler.get_abandoned_count(),
+            designation_count: world.get_designations().len(),
+            nav_node_count: world.get_nav_graph().get_node_count()
+        }
+    }
+}
+
+impl fmt::Display for WorldStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ticks = {}, layers = {}, navigation nodes = {}", self.tick_count, self.layer_count, self.nav_node_count)?;
+        writeln!(f, "dwarfs = {} (idle = {}, walking = {}, working = {}, resting = {})",
+            self.dwarf_count, self.idle_count, self.walking_count, self.working_count, self.resting_count)?;
+        write!(f, "jobs: queued = {}, assigned = {}, completed = {}, abandoned = {}, designations = {}",
+            self.queued_jobs, self.assigned_jobs, self.completed_jobs, self.abandoned_jobs, self.designation_count)
+    }
+}